@@ -0,0 +1,94 @@
+//! ## Build script
+//! With feature `static-tables`, generate the 7-card lookup tables at compile time.
+//! The tables are built by the very same code as [seven::build_tables](src/eval/seven.rs), included below, and written to `OUT_DIR` as raw bytes.
+//! They are then embedded as static arrays by module `eval::tables`.
+//!
+//! Without this feature, the build script does nothing.
+
+#![allow(dead_code)]
+
+#[cfg(feature = "static-tables")]
+#[path = "src/card.rs"]
+pub mod card;
+#[cfg(feature = "static-tables")]
+#[path = "src/eval/five.rs"]
+pub mod five;
+#[cfg(feature = "static-tables")]
+#[path = "src/keys.rs"]
+pub mod keys;
+#[cfg(feature = "static-tables")]
+#[path = "src/eval/packed.rs"]
+pub mod packed;
+#[cfg(feature = "static-tables")]
+#[path = "src/eval/rank.rs"]
+pub mod rank;
+#[cfg(feature = "static-tables")]
+#[path = "src/eval/seven.rs"]
+pub mod seven;
+#[cfg(feature = "static-tables")]
+#[path = "src/eval/six.rs"]
+pub mod six;
+#[cfg(feature = "static-tables")]
+#[path = "src/eval/target.rs"]
+pub mod target;
+#[cfg(feature = "static-tables")]
+#[path = "src/util.rs"]
+pub mod util;
+
+/// Same paths as in the crate
+#[cfg(feature = "static-tables")]
+mod eval {
+    pub use super::{five, packed, rank, seven, six, target};
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "static-tables")]
+    generate();
+}
+
+/// Write tables `face_rank` (packed, as `face_row_offset` and `face_rank`), `flush_rank` and `flush_suit` to `OUT_DIR` in target endianness
+#[cfg(feature = "static-tables")]
+fn generate() {
+    for file in ["src/card.rs", "src/keys.rs", "src/util.rs", "src/eval"] {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let big_endian = std::env::var("CARGO_CFG_TARGET_ENDIAN").unwrap() == "big";
+    let to_bytes_u32 = |x: u32| match big_endian {
+        true => x.to_be_bytes(),
+        false => x.to_le_bytes(),
+    };
+    let to_bytes_u16 = |x: u16| match big_endian {
+        true => x.to_be_bytes(),
+        false => x.to_le_bytes(),
+    };
+
+    let t7 = seven::build_tables(false);
+
+    let face_row_offset = t7
+        .face_rank
+        .row_offset
+        .iter()
+        .flat_map(|x| to_bytes_u32(*x))
+        .collect::<Vec<u8>>();
+    let face_rank = t7
+        .face_rank
+        .rank
+        .iter()
+        .flat_map(|x| to_bytes_u16(*x))
+        .collect::<Vec<u8>>();
+    let flush_rank = t7
+        .flush_rank
+        .iter()
+        .flat_map(|x| to_bytes_u16(*x))
+        .collect::<Vec<u8>>();
+    let flush_suit = t7.flush_suit.iter().map(|x| *x as u8).collect::<Vec<u8>>();
+
+    std::fs::write(out_dir.join("face_row_offset.bin"), face_row_offset).unwrap();
+    std::fs::write(out_dir.join("face_rank.bin"), face_rank).unwrap();
+    std::fs::write(out_dir.join("flush_rank.bin"), flush_rank).unwrap();
+    std::fs::write(out_dir.join("flush_suit.bin"), flush_suit).unwrap();
+}
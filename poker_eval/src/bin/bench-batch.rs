@@ -0,0 +1,74 @@
+//! Benchmark batch 7-card evaluation vs one hand at a time
+//! Run with `just bench-batch`
+
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+
+use poker_eval::eval::batch::{get_rank_batch, get_rank_batch_soa, get_rank_batch_soa_scalar, HandBatch};
+use poker_eval::eval::seven::{build_tables, get_rank};
+use poker_eval::keys::DECK_SIZE;
+
+const NB_HAND: usize = 10_000_000;
+const NB_RUN: usize = 5;
+
+fn main() {
+    let t7 = build_tables(false);
+
+    let mut rng = rand::thread_rng();
+    let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+    let hands = (0..NB_HAND)
+        .map(|_| {
+            deck.shuffle(&mut rng);
+            deck[0..7].try_into().unwrap()
+        })
+        .collect::<Vec<[usize; 7]>>();
+    let batch = HandBatch::try_from(hands.as_slice()).unwrap();
+
+    #[cfg(target_arch = "x86_64")]
+    println!("avx2 = {}", is_x86_feature_detected!("avx2"));
+    println!("{} random hands, best of {} runs", NB_HAND, NB_RUN);
+
+    let mut ranks = vec![0; NB_HAND];
+
+    let loop_ = bench(|| {
+        for (rank, c) in ranks.iter_mut().zip(hands.iter()) {
+            *rank = get_rank(&t7, *c);
+        }
+    });
+    let ranks_want = ranks.clone();
+    report("get_rank loop", loop_, loop_);
+
+    let aos = bench(|| get_rank_batch(&t7, &hands, &mut ranks));
+    assert_eq!(ranks, ranks_want);
+    report("get_rank_batch", aos, loop_);
+
+    let soa_scalar = bench(|| get_rank_batch_soa_scalar(&t7, &batch, &mut ranks));
+    assert_eq!(ranks, ranks_want);
+    report("get_rank_batch_soa_scalar", soa_scalar, loop_);
+
+    let soa = bench(|| get_rank_batch_soa(&t7, &batch, &mut ranks));
+    assert_eq!(ranks, ranks_want);
+    report("get_rank_batch_soa", soa, loop_);
+}
+
+/// Best runtime in seconds
+fn bench(mut f: impl FnMut()) -> f64 {
+    (0..NB_RUN)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn report(name: &str, runtime: f64, runtime_ref: f64) {
+    println!(
+        "{:<26} {:>7.1} ms {:>7.1} M hands/s  x{:.2}",
+        name,
+        runtime * 1e3,
+        NB_HAND as f64 / runtime / 1e6,
+        runtime_ref / runtime
+    );
+}
@@ -0,0 +1,194 @@
+//! Benchmark packed lookup tables vs the previous dense `u32` layout: memory, throughput, build time
+//! Run with `just bench-tables`
+//!
+//! The dense layout is rebuilt from the packed tables, with the same keys, so both are measured on the same machine.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+
+use poker_eval::eval::seven::{build_tables, get_rank, TableSeven};
+use poker_eval::keys::DECK_SIZE;
+
+const NB_HAND: usize = 10_000_000;
+const NB_RUN: usize = 3;
+
+/// ## Dense lookup tables
+/// Layout before packing: one `u32` rank slot per key.
+struct DenseSeven {
+    face_rank: Vec<u32>,
+    flush_rank: Vec<u32>,
+}
+
+impl DenseSeven {
+    fn new(t7: &TableSeven) -> DenseSeven {
+        DenseSeven {
+            face_rank: (0..=t7.t5.pk.max_face_seven_key).map(|k| t7.face_rank.get(k)).collect(),
+            flush_rank: t7.flush_rank.iter().map(|r| *r as u32).collect(),
+        }
+    }
+}
+
+/// Same as [get_rank], with the dense tables
+fn get_rank_dense(t7: &TableSeven, dense: &DenseSeven, c: [usize; 7]) -> u32 {
+    let pk = &t7.t5.pk;
+
+    let hand_key = c.iter().map(|x| pk.card_face_key[*x]).sum::<u32>();
+    let hand_suit = t7.flush_suit[(hand_key & pk.suit_mask) as usize];
+
+    if hand_suit == -1 {
+        dense.face_rank[(hand_key >> pk.suit_bit_shift) as usize]
+    } else {
+        let hand_flush_key = c
+            .iter()
+            .filter(|x| pk.card_suit[**x] == hand_suit as usize)
+            .map(|x| pk.card_flush_key[*x])
+            .sum::<u32>();
+        dense.flush_rank[hand_flush_key as usize]
+    }
+}
+
+fn main() {
+    let build = bench(|| {
+        black_box(build_tables(false));
+    });
+    let t7 = build_tables(false);
+    let dense = DenseSeven::new(&t7);
+
+    println!("memory");
+    memory(&t7);
+
+    println!("\nthroughput, best of {} runs", NB_RUN);
+
+    let all_packed = bench(|| {
+        black_box(rank_all(|c| get_rank(&t7, c)));
+    });
+    let all_dense = bench(|| {
+        black_box(rank_all(|c| get_rank_dense(&t7, &dense, c)));
+    });
+    assert_eq!(
+        rank_all(|c| get_rank(&t7, c)),
+        rank_all(|c| get_rank_dense(&t7, &dense, c))
+    );
+    report("all 133,784,560 hands, in order", 133_784_560, all_dense, all_packed);
+
+    let mut rng = rand::thread_rng();
+    let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+    let hands = (0..NB_HAND)
+        .map(|_| {
+            deck.shuffle(&mut rng);
+            deck[0..7].try_into().unwrap()
+        })
+        .collect::<Vec<[usize; 7]>>();
+
+    let rnd_packed = bench(|| {
+        black_box(hands.iter().map(|c| get_rank(&t7, *c) as u64).sum::<u64>());
+    });
+    let rnd_dense = bench(|| {
+        black_box(
+            hands
+                .iter()
+                .map(|c| get_rank_dense(&t7, &dense, *c) as u64)
+                .sum::<u64>(),
+        );
+    });
+    report("10M random hands", NB_HAND, rnd_dense, rnd_packed);
+
+    println!("\nseven::build_tables, packed: {:.0} ms", build * 1e3);
+}
+
+/// Memory of each table, dense `u32` layout vs packed
+fn memory(t7: &TableSeven) {
+    let t5 = &t7.t5;
+    let pk = &t5.pk;
+
+    let flush_len = t7.flush_rank.len() + t5.flush_five_rank.len();
+    let suit_len = t7.flush_suit.len() + t7.flush_six_suit.len();
+    let hand_type_dense = t5
+        .hand_type
+        .iter()
+        .map(|c| std::mem::size_of::<String>() + c.as_str().len())
+        .sum::<usize>();
+
+    let rows = [
+        (
+            "face_rank",
+            4 * (pk.max_face_seven_key as usize + 1),
+            t7.face_rank.size_of(),
+        ),
+        (
+            "face_six_rank",
+            4 * (pk.max_face_six_key as usize + 1),
+            t7.face_six_rank.size_of(),
+        ),
+        (
+            "t5.face_five_rank",
+            4 * (pk.max_face_five_key as usize + 1),
+            t5.face_five_rank.size_of(),
+        ),
+        (
+            "flush ranks and suits",
+            4 * flush_len + suit_len,
+            2 * flush_len + suit_len,
+        ),
+        ("t5.hand_faces", 5 * 8 * t5.hand_faces.len(), 5 * t5.hand_faces.len()),
+        ("t5.hand_type", hand_type_dense, t5.hand_type.len()),
+    ];
+
+    println!("{:<24} {:>12} {:>12}", "table", "dense u32", "packed");
+    for (name, dense, packed) in rows.iter() {
+        println!("{:<24} {:>12} {:>12}", name, size(*dense), size(*packed));
+    }
+    let total = rows.iter().fold((0, 0), |acc, r| (acc.0 + r.1, acc.1 + r.2));
+    println!("{:<24} {:>12} {:>12}", "total", size(total.0), size(total.1));
+}
+
+/// Sum of the ranks of all 7-card hands, in order
+fn rank_all(rank: impl Fn([usize; 7]) -> u32) -> u64 {
+    let n = DECK_SIZE;
+    let mut sum = 0u64;
+    for c0 in 0..n {
+        for c1 in c0 + 1..n {
+            for c2 in c1 + 1..n {
+                for c3 in c2 + 1..n {
+                    for c4 in c3 + 1..n {
+                        for c5 in c4 + 1..n {
+                            for c6 in c5 + 1..n {
+                                sum += rank([c0, c1, c2, c3, c4, c5, c6]) as u64;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    sum
+}
+
+/// Best runtime in seconds
+fn bench(mut f: impl FnMut()) -> f64 {
+    (0..NB_RUN)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn report(name: &str, nb_hand: usize, runtime_dense: f64, runtime_packed: f64) {
+    println!(
+        "{:<32} dense {:>6.0} M/s  packed {:>6.0} M/s",
+        name,
+        nb_hand as f64 / runtime_dense / 1e6,
+        nb_hand as f64 / runtime_packed / 1e6,
+    );
+}
+
+fn size(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1_000_000 => format!("{:.1} MB", b as f64 / 1e6),
+        b => format!("{} kB", (b as f64 / 1e3).round()),
+    }
+}
@@ -1,4 +1,9 @@
 //! Hand equity calculation, based on module [eval](crate::eval) - crate main entry point  
 
 pub mod equity_det;
+pub mod equity_exact;
+pub mod equity_hilo;
 pub mod equity_mc;
+pub mod equity_omaha;
+pub mod equity_range;
+pub mod equity_stud;
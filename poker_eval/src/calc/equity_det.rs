@@ -18,6 +18,8 @@
 //!     vec![[7, 8], [22, 27]],  
 //!     // table cards  
 //!     vec![51, 30, 41],  
+//!     // dead cards  
+//!     vec![],  
 //!     // verbose  
 //!     true
 //! );
@@ -30,8 +32,8 @@ use std::{collections::HashSet, sync::Arc, thread, time::Instant, vec};
 use thiserror::Error;
 
 use crate::{
-    eval::seven::{get_rank, TableSeven},
-    keys::DECK_SIZE,
+    eval::{accumulator::HandAccumulator, seven::TableSeven},
+    keys::{Variant, DECK_SIZE},
 };
 
 #[cfg(feature = "serde")]
@@ -50,6 +52,13 @@ pub struct HandEquity {
     pub tie: f64,
 }
 
+impl std::ops::AddAssign for HandEquity {
+    fn add_assign(&mut self, other: HandEquity) {
+        self.win += other.win;
+        self.tie += other.tie;
+    }
+}
+
 /// ## Game description error
 /// This error type is used to describe the errors that can occur when describing a deterministic game.  
 #[derive(Error, Debug)]
@@ -68,18 +77,33 @@ pub enum GameError {
     /// Invalid table card
     #[error("invalid table card: {0} - must be between 0 and 51")]
     InvalidTableCard(u32),
-    // both
+    // dead
+    /// Invalid dead card
+    #[error("invalid dead card: {0} - must be between 0 and 51")]
+    InvalidDeadCard(u32),
+    // all
+    /// Card not in the deck of the variant, e.g. a 2 in short-deck
+    #[error("invalid card: {0} - not in the {1} deck")]
+    CardNotInDeck(u32, Variant),
     /// Not distinct cards
-    #[error("players: {0:?} table: {1:?} - all cards must be distinct")]
-    NotDistinctCards(Vec<[u32; 2]>, Vec<u32>),
+    #[error("players: {0:?} table: {1:?} dead: {2:?} - all cards must be distinct")]
+    NotDistinctCards(Vec<[u32; 2]>, Vec<u32>, Vec<u32>),
+    /// Not enough cards left in the deck
+    #[error("not enough cards: {0} to deal - {1} left in the deck")]
+    NotEnoughCards(u32, u32),
 }
 
 /// ## Calculate equity of hand deterministically ie. exhaustively
 /// This requires knowing all players cards - and table cards, which is always the case.
+///
+/// The dead cards, e.g. folded cards shown, are removed from the deck.
+///
+/// The deck is the one of the tables variant, e.g. 36 cards for [build_tables_variant](crate::eval::seven::build_tables_variant) with [Variant::ShortDeck].
 pub fn calc_equity_det(
     t7: Arc<TableSeven>,
     player_cards: Vec<[u32; 2]>,
     table_cards: Vec<u32>,
+    dead_cards: Vec<u32>,
     verbose: bool,
 ) -> Result<Vec<HandEquity>, GameError> {
     let start = Instant::now();
@@ -114,38 +138,65 @@ pub fn calc_equity_det(
         }
     }
 
-    let all_cards_set = player_cards
+    for d in dead_cards.iter() {
+        match *d {
+            x if (x < deck_size) => (),
+            _ => return Err(GameError::InvalidDeadCard(*d)),
+        }
+    }
+
+    let variant = t7.t5.variant;
+    let all_cards = player_cards
         .iter()
         .flatten()
-        .copied()
-        .chain(table_cards.iter().copied())
-        .collect::<HashSet<u32>>();
+        .chain(table_cards.iter())
+        .chain(dead_cards.iter());
+    if let Some(c) = all_cards.clone().find(|c| !variant.contains(**c as usize)) {
+        return Err(GameError::CardNotInDeck(*c, variant));
+    }
+
+    let all_cards_set = all_cards.copied().collect::<HashSet<u32>>();
 
     let all_cards_vec = player_cards
         .iter()
         .flatten()
         .copied()
         .chain(table_cards.iter().copied())
+        .chain(dead_cards.iter().copied())
         .collect::<Vec<u32>>();
 
     match all_cards_vec.len() == all_cards_set.len() {
         true => (),
-        false => return Err(GameError::NotDistinctCards(player_cards, table_cards)),
+        false => return Err(GameError::NotDistinctCards(player_cards, table_cards, dead_cards)),
     }
-    // end check input
 
-    let deck = (0..deck_size)
+    let deck = variant
+        .deck()
+        .into_iter()
+        .map(|c| c as u32)
         .filter(|c| !all_cards_set.contains(c))
         .collect::<Vec<u32>>();
 
+    match deck.len() >= 5 - nb_table_card {
+        true => (),
+        false => return Err(GameError::NotEnoughCards(5 - nb_table_card as u32, deck.len() as u32)),
+    }
+    // end check input
+
     let nb_deck = deck.len() as usize;
 
     let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
     let mut rank = vec![0; nb_player];
     let mut n_game = 0;
 
-    let player_cards_ = Arc::new(player_cards);
-    let table_cards_ = Arc::new(table_cards);
+    // hole cards and table cards are summed once, then extended by each runout
+    let hole = player_cards
+        .iter()
+        .map(|p| HandAccumulator::from_cards(&p.map(|x| x as usize)))
+        .collect::<Vec<HandAccumulator>>();
+    let table = HandAccumulator::from_cards(&table_cards.iter().map(|x| *x as usize).collect::<Vec<usize>>());
+
+    let hole_ = Arc::new(hole);
     let deck_ = Arc::new(deck);
 
     // zero table cards
@@ -154,7 +205,7 @@ pub fn calc_equity_det(
 
         for i1 in 0..nb_deck {
             let t7_ = Arc::clone(&t7);
-            let player_cards_ = Arc::clone(&player_cards_);
+            let hole_ = Arc::clone(&hole_);
             let deck_ = Arc::clone(&deck_);
 
             let mut eqty_ = Vec::new();
@@ -166,21 +217,17 @@ pub fn calc_equity_det(
                 let mut rank_ = vec![0; nb_player];
                 let mut n_game_ = 0;
 
+                let t1 = table.with(deck_[i1] as usize);
                 for i2 in 0..i1 {
+                    let t2 = t1.with(deck_[i2] as usize);
                     for i3 in 0..i2 {
+                        let t3 = t2.with(deck_[i3] as usize);
                         for i4 in 0..i3 {
+                            let t4 = t3.with(deck_[i4] as usize);
                             for i5 in 0..i4 {
+                                let t5 = t4.with(deck_[i5] as usize);
                                 for p in 0..nb_player {
-                                    let cards = [
-                                        player_cards_[p][0],
-                                        player_cards_[p][1],
-                                        deck_[i1],
-                                        deck_[i2],
-                                        deck_[i3],
-                                        deck_[i4],
-                                        deck_[i5],
-                                    ];
-                                    rank_[p] = get_rank(&t7_, cards.map(|x| x as usize));
+                                    rank_[p] = (hole_[p] + t5).rank_seven(&t7_);
                                 }
                                 update_eqty(&mut eqty_, &rank_);
                                 n_game_ += 1;
@@ -203,54 +250,14 @@ pub fn calc_equity_det(
         }
     }
 
-    // // zero table cards
-    // if nb_table_card == 0 {
-    //     for i1 in 0..nb_deck {
-    //         for i2 in 0..i1 {
-    //             for i3 in 0..i2 {
-    //                 for i4 in 0..i3 {
-    //                     for i5 in 0..i4 {
-    //                         for p in 0..nb_player {
-    //                             let cards = [
-    //                                 player_cards[p][0],
-    //                                 player_cards[p][1],
-    //                                 deck[i1],
-    //                                 deck[i2],
-    //                                 deck[i3],
-    //                                 deck[i4],
-    //                                 deck[i5],
-    //                             ];
-    //                             rank[p] = get_rank(&t7, cards.map(|x| x as usize));
-    //                         }
-    //                         update_eqty(&mut eqty, &rank);
-    //                         n_game += 1;
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-    // }
-
     // 3 table cards
     if nb_table_card == 3 {
-        let t7_ = Arc::clone(&t7);
-        let player_cards_ = Arc::clone(&player_cards_);
-        let table_cards_ = Arc::clone(&table_cards_);
-        let deck_ = Arc::clone(&deck_);
-
         for i1 in 0..nb_deck {
+            let t4 = table.with(deck_[i1] as usize);
             for i2 in 0..i1 {
+                let t5 = t4.with(deck_[i2] as usize);
                 for p in 0..nb_player {
-                    let cards = [
-                        player_cards_[p][0],
-                        player_cards_[p][1],
-                        table_cards_[0],
-                        table_cards_[1],
-                        table_cards_[2],
-                        deck_[i1],
-                        deck_[i2],
-                    ];
-                    rank[p] = get_rank(&t7_, cards.map(|x| x as usize));
+                    rank[p] = (hole_[p] + t5).rank_seven(&t7);
                 }
                 update_eqty(&mut eqty, &rank);
                 n_game += 1;
@@ -260,23 +267,10 @@ pub fn calc_equity_det(
 
     // 4 table cards
     if nb_table_card == 4 {
-        let t7_ = Arc::clone(&t7);
-        let player_cards_ = Arc::clone(&player_cards_);
-        let table_cards_ = Arc::clone(&table_cards_);
-        let deck_ = Arc::clone(&deck_);
-
         for i1 in 0..nb_deck {
+            let t5 = table.with(deck_[i1] as usize);
             for p in 0..nb_player {
-                let cards = [
-                    player_cards_[p][0],
-                    player_cards_[p][1],
-                    table_cards_[0],
-                    table_cards_[1],
-                    table_cards_[2],
-                    table_cards_[3],
-                    deck_[i1],
-                ];
-                rank[p] = get_rank(&t7_, cards.map(|x| x as usize));
+                rank[p] = (hole_[p] + t5).rank_seven(&t7);
             }
             update_eqty(&mut eqty, &rank);
             n_game += 1;
@@ -285,21 +279,8 @@ pub fn calc_equity_det(
 
     // 5 table cards
     if nb_table_card == 5 {
-        let t7_ = Arc::clone(&t7);
-        let player_cards_ = Arc::clone(&player_cards_);
-        let table_cards_ = Arc::clone(&table_cards_);
-
         for p in 0..nb_player {
-            let cards = [
-                player_cards_[p][0],
-                player_cards_[p][1],
-                table_cards_[0],
-                table_cards_[1],
-                table_cards_[2],
-                table_cards_[3],
-                table_cards_[4],
-            ];
-            rank[p] = get_rank(&t7_, cards.map(|x| x as usize));
+            rank[p] = (hole_[p] + table).rank_seven(&t7);
         }
         update_eqty(&mut eqty, &rank);
         n_game += 1;
@@ -322,7 +303,8 @@ pub fn calc_equity_det(
     Ok(equity)
 }
 
-fn update_eqty(eqty: &mut Vec<HandEquity>, rank: &Vec<u32>) -> () {
+/// Add the result of a game to the players equity sums - the best ranks win or share the pot
+pub(crate) fn update_eqty(eqty: &mut Vec<HandEquity>, rank: &Vec<u32>) -> () {
     let nb_player = eqty.len();
 
     let mut max_rank = rank[0];
@@ -355,8 +337,9 @@ mod tests {
 
     use crate::calc;
     use crate::eval::seven;
+    use crate::keys::Variant;
 
-    use super::HandEquity;
+    use super::{GameError, HandEquity};
 
     #[test]
     fn calc_equity_det() {
@@ -417,7 +400,8 @@ mod tests {
                 .map(|[w, t]| HandEquity { win: *w, tie: *t })
                 .collect::<Vec<HandEquity>>();
 
-            let equity = calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), true);
+            let equity =
+                calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), vec![], true);
             assert!(
                 equity.is_ok(),
                 "-> fails: players={:?}, table={:?}",
@@ -448,4 +432,127 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn calc_equity_det_short_deck() {
+        let arc_t7 = seven::build_tables(false);
+        let arc_t7_short = seven::build_tables_variant(Variant::ShortDeck, false);
+
+        // flush vs full house
+        let players = vec![[42, 26], [29, 44]];
+        let table = vec![50, 46, 30, 31, 16];
+
+        for (t7, winner) in [(arc_t7.clone(), 1), (arc_t7_short.clone(), 0)] {
+            let equity = calc::equity_det::calc_equity_det(t7.clone(), players.clone(), table.clone(), vec![], false);
+            assert!(equity.is_ok(), "-> fails: variant={}", t7.t5.variant);
+            let equity = equity.unwrap();
+            assert_eq!(equity[winner].win, 1.0, "-> fails: variant={}", t7.t5.variant);
+            assert_eq!(equity[1 - winner].win, 0.0, "-> fails: variant={}", t7.t5.variant);
+        }
+
+        // the turn: 36 - 4 - 4 = 28 river cards
+        let equity = calc::equity_det::calc_equity_det(
+            arc_t7_short.clone(),
+            players.clone(),
+            table[..4].to_vec(),
+            vec![],
+            false,
+        );
+        assert!(
+            equity.is_ok(),
+            "-> fails: players={:?}, table={:?}",
+            players,
+            &table[..4]
+        );
+        let equity = equity.unwrap();
+        let total = equity.iter().map(|e| e.win + e.tie).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-6, "-> fails: equity={:?}", equity);
+        assert!(
+            ((equity[0].win * 28.0).round() - equity[0].win * 28.0).abs() < 1e-6,
+            "-> fails: equity={:?}",
+            equity
+        );
+
+        // a 5 is not in the short deck
+        let equity =
+            calc::equity_det::calc_equity_det(arc_t7_short.clone(), vec![[42, 26], [12, 44]], vec![], vec![], false);
+        assert!(
+            matches!(equity, Err(GameError::CardNotInDeck(12, Variant::ShortDeck))),
+            "-> fails: equity={:?}",
+            equity
+        );
+    }
+
+    #[test]
+    fn calc_equity_det_dead_cards() {
+        let arc_t7 = seven::build_tables(false);
+
+        let players = vec![[8, 9], [11, 28]];
+        let table = vec![15, 47, 23, 33];
+
+        for dead in [
+            vec![],
+            vec![0, 1],
+            vec![12, 13, 14, 16, 17],
+            (34..46).collect::<Vec<u32>>(),
+        ] {
+            // brute force over the river cards not dead
+            let mut want = vec![HandEquity { win: 0.0, tie: 0.0 }; 2];
+            let mut n_game = 0.0;
+            for river in
+                (0..52).filter(|c| !table.contains(c) && !players.iter().flatten().any(|x| x == c) && !dead.contains(c))
+            {
+                let rank = players
+                    .iter()
+                    .map(|p| {
+                        seven::get_rank(
+                            &arc_t7,
+                            [p[0], p[1], table[0], table[1], table[2], table[3], river].map(|x| x as usize),
+                        )
+                    })
+                    .collect::<Vec<u32>>();
+                calc::equity_det::update_eqty(&mut want, &rank);
+                n_game += 1.0;
+            }
+
+            let equity =
+                calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), dead.clone(), false);
+            assert!(equity.is_ok(), "-> fails: dead={:?}", dead);
+
+            for (e, w) in equity.unwrap().iter().zip(want.iter()) {
+                assert!(
+                    (e.win - w.win / n_game).abs() < 1e-9 && (e.tie - w.tie / n_game).abs() < 1e-9,
+                    "-> fails: dead={:?}\nfound:equity={:?}, want:equity={:?}",
+                    dead,
+                    e,
+                    w
+                );
+            }
+        }
+
+        // invalid dead cards
+        let equity = calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), vec![52], false);
+        assert!(
+            matches!(equity, Err(GameError::InvalidDeadCard(52))),
+            "-> fails: equity={:?}",
+            equity
+        );
+
+        let equity = calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), vec![9], false);
+        assert!(
+            matches!(equity, Err(GameError::NotDistinctCards(..))),
+            "-> fails: equity={:?}",
+            equity
+        );
+
+        let dead = (0..52)
+            .filter(|c| !table.contains(c) && !players.iter().flatten().any(|x| x == c))
+            .collect::<Vec<u32>>();
+        let equity = calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), dead, false);
+        assert!(
+            matches!(equity, Err(GameError::NotEnoughCards(1, 0))),
+            "-> fails: equity={:?}",
+            equity
+        );
+    }
 }
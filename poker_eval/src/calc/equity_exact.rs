@@ -0,0 +1,533 @@
+//! # Exact equity calculation with unknown cards
+//! This module provides the function to calculate the equity of hands exactly, i.e. exhaustively, when some players cards are unknown.
+//! It takes the same input as the [monte carlo](super::equity_mc) calculation, and enumerates every completion of the unknown cards.
+
+//!   # Example
+//!
+//! ```
+//! use poker_eval::eval::seven::build_tables;
+//! use poker_eval::calc::equity_exact::calc_equity_exact;
+//!
+//! // you need create Arc<TableSeven> arc_t7 beforehand once
+//! let arc_t7 = build_tables(true);
+//!
+//! // then you can call calc_equity_exact multiple times
+//! let equity = calc_equity_exact(
+//!     // clone of Arc<TableSeven>
+//!     arc_t7.clone(),
+//!     // player cards
+//!     vec![vec![8, 9], vec![11, 28], vec![]],
+//!     // table cards
+//!     vec![15, 47, 23],
+//!     // dead cards
+//!     vec![],
+//!     // verbose
+//!     true,
+//! );
+//! println!("equity = {:?}", equity);
+//! // Ok([HandEquity { win: 0.24933834468718188, tie: 0.010927286896279196 }, HandEquity { win: 0.29893732451871985, tie: 0.012931269132819573 }, HandEquity { win: 0.4190319585668423, tie: 0.008833816198157336 }])
+//! ```
+
+use std::{sync::Arc, thread, time::Instant};
+
+use super::equity_det::{update_eqty, HandEquity};
+use super::equity_mc::{check_game, McGameError};
+use crate::eval::{accumulator::HandAccumulator, seven::TableSeven};
+
+/// ## Calculate equity of hands exactly, with unknown cards
+/// This does not require knowing all players cards, the input is the same as [calc_equity_monte_carlo](super::equity_mc::calc_equity_monte_carlo).
+/// Every completion of the unknown table and players cards is enumerated, all being equally likely.
+/// The equity of each player is returned, including players with partially or fully unknown cards.
+/// The dead cards, e.g. folded cards shown, are removed from the deck.
+///
+/// The first unknown cards - the board, or on the river the cards of the first player with unknown cards - are enumerated up to suit symmetry:
+/// + the suit permutations that leave the known cards of each player, the table cards and the dead cards unchanged also leave the equity unchanged
+/// + so only one combination of each class is enumerated, weighted by the size of the class
+///
+/// One random hand on a known flop takes milliseconds, preflop or several random hands on the flop take seconds.
+pub fn calc_equity_exact(
+    t7: Arc<TableSeven>,
+    player_cards: Vec<Vec<u32>>,
+    table_cards: Vec<u32>,
+    dead_cards: Vec<u32>,
+    verbose: bool,
+) -> Result<Vec<HandEquity>, McGameError> {
+    let start = Instant::now();
+
+    // start check input
+    let deck = check_game(t7.t5.variant, &player_cards, &table_cards, &dead_cards)?;
+    // end check input
+
+    let nb_player = player_cards.len();
+    let nb_board = 5 - table_cards.len();
+
+    // known cards are summed once
+    let table = HandAccumulator::from_cards(&table_cards.iter().map(|x| *x as usize).collect::<Vec<usize>>());
+    let hole = player_cards
+        .iter()
+        .map(|p| HandAccumulator::from_cards(&p.iter().map(|x| *x as usize).collect::<Vec<usize>>()))
+        .collect::<Vec<HandAccumulator>>();
+    let nb_missing = player_cards.iter().map(|p| 2 - p.len()).collect::<Vec<usize>>();
+
+    // the board, else the first player with unknown cards, else nothing
+    let top_player = match nb_board {
+        0 => nb_missing.iter().position(|m| *m > 0),
+        _ => None,
+    };
+    let nb_top = match top_player {
+        Some(p) => nb_missing[p],
+        None => nb_board,
+    };
+
+    let perms = suit_symmetries(&player_cards, &table_cards, &dead_cards);
+    let tops = canonical_combinations(&deck, nb_top, &perms);
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+    let chunk_size = tops.len().div_ceil(n_thread).max(1);
+
+    let arr_eqty = thread::scope(|s| {
+        let handles = tops
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let (t7_, deck_, hole_, nb_missing_) = (&t7, &deck, &hole, &nb_missing);
+                s.spawn(move || {
+                    let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
+                    let mut eqty_top = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
+                    let mut rank = vec![0u32; nb_player];
+                    let mut n_game = 0u64;
+
+                    for (cards, weight) in chunk.iter() {
+                        let cards = &cards[..nb_top];
+                        let used = cards.iter().fold(0u64, |acc, c| acc | 1 << c);
+
+                        // the top cards are dealt, to the board or to the top player
+                        let mut board = table;
+                        let mut hole = hole_.clone();
+                        let mut nb_missing = nb_missing_.clone();
+                        match top_player {
+                            Some(p) => {
+                                cards.iter().for_each(|c| hole[p].push(*c as usize));
+                                nb_missing[p] = 0;
+                            }
+                            None => cards.iter().for_each(|c| board.push(*c as usize)),
+                        }
+
+                        let deal = Deal {
+                            t7: t7_,
+                            deck: deck_,
+                            hole: &hole,
+                            nb_missing: &nb_missing,
+                        };
+
+                        eqty_top.iter_mut().for_each(|e| *e = HandEquity { win: 0.0, tie: 0.0 });
+                        let n_game_top = deal.deal_board(board, used, &mut rank, &mut eqty_top);
+
+                        for (e, e_top) in eqty.iter_mut().zip(eqty_top.iter()) {
+                            e.win += *weight as f64 * e_top.win;
+                            e.tie += *weight as f64 * e_top.tie;
+                        }
+                        n_game += weight * n_game_top;
+                    }
+                    (eqty, n_game)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<(Vec<HandEquity>, u64)>>()
+    });
+
+    let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
+    let mut n_game = 0u64;
+    for (eqty_, n_game_) in arr_eqty {
+        for p in 0..nb_player {
+            eqty[p] += eqty_[p];
+        }
+        n_game += n_game_;
+    }
+
+    for e in eqty.iter_mut() {
+        e.win /= n_game as f64;
+        e.tie /= n_game as f64;
+    }
+
+    let end = Instant::now();
+
+    if verbose {
+        println!("calc_equity_exact nb suit symmetries: {}", perms.len());
+        println!("calc_equity_exact nb top combinations: {}", tops.len());
+        println!("calc_equity_exact nb games: {}", n_game);
+        println!("calc_equity_exact runtime: {:?}", end - start);
+    }
+
+    Ok(eqty)
+}
+
+/// ## Deal of the remaining unknown cards
+/// Once the top cards are dealt, the board is complete: enumerates the unknown cards of each player.
+struct Deal<'a> {
+    t7: &'a TableSeven,
+    deck: &'a [u32],
+    hole: &'a [HandAccumulator],
+    nb_missing: &'a [usize],
+}
+
+impl Deal<'_> {
+    /// Rank the players on the complete board, then enumerate their unknown cards - returns the number of games
+    fn deal_board(&self, board: HandAccumulator, used: u64, rank: &mut Vec<u32>, eqty: &mut Vec<HandEquity>) -> u64 {
+        debug_assert_eq!(board.len(), 5);
+
+        // players with all cards known are ranked once per board
+        for (p, h) in self.hole.iter().enumerate() {
+            if self.nb_missing[p] == 0 {
+                rank[p] = (*h + board).rank_seven(self.t7);
+            }
+        }
+        self.deal_player(0, board, used, rank, eqty)
+    }
+
+    /// Enumerate the unknown cards of the players, starting from player `p` - returns the number of games
+    fn deal_player(
+        &self,
+        p: usize,
+        board: HandAccumulator,
+        used: u64,
+        rank: &mut Vec<u32>,
+        eqty: &mut Vec<HandEquity>,
+    ) -> u64 {
+        let q = match (p..self.hole.len()).find(|q| self.nb_missing[*q] > 0) {
+            Some(q) => q,
+            None => {
+                update_eqty(eqty, rank);
+                return 1;
+            }
+        };
+
+        let hand = self.hole[q] + board;
+        let mut n_game = 0;
+        for (i1, c1) in self.deck.iter().enumerate() {
+            if used & 1 << c1 != 0 {
+                continue;
+            }
+            let hand1 = hand.with(*c1 as usize);
+            if self.nb_missing[q] == 1 {
+                rank[q] = hand1.rank_seven(self.t7);
+                n_game += self.deal_player(q + 1, board, used | 1 << c1, rank, eqty);
+                continue;
+            }
+            for c2 in self.deck[..i1].iter() {
+                if used & 1 << c2 != 0 {
+                    continue;
+                }
+                rank[q] = hand1.with(*c2 as usize).rank_seven(self.t7);
+                n_game += self.deal_player(q + 1, board, used | 1 << c1 | 1 << c2, rank, eqty);
+            }
+        }
+        n_game
+    }
+}
+
+/// ## Suit symmetries
+/// Permutations of the 4 suits that leave the known cards of each player, the table cards and the dead cards unchanged, as sets.
+/// The identity is always one of them.
+fn suit_symmetries(player_cards: &[Vec<u32>], table_cards: &[u32], dead_cards: &[u32]) -> Vec<[u32; 4]> {
+    let mut sets = player_cards.to_vec();
+    sets.push(table_cards.to_vec());
+    sets.push(dead_cards.to_vec());
+    for s in sets.iter_mut() {
+        s.sort();
+    }
+
+    let mut perms = vec![];
+    for a in 0..4 {
+        for b in 0..4 {
+            for c in 0..4 {
+                for d in 0..4 {
+                    let perm = [a, b, c, d];
+                    let mut suits = perm;
+                    suits.sort();
+                    if suits != [0, 1, 2, 3] {
+                        continue;
+                    }
+                    if sets.iter().all(|s| {
+                        let mut s_ = s.iter().map(|x| permute_suit(*x, &perm)).collect::<Vec<u32>>();
+                        s_.sort();
+                        s_ == *s
+                    }) {
+                        perms.push(perm);
+                    }
+                }
+            }
+        }
+    }
+    perms
+}
+
+fn permute_suit(card: u32, perm: &[u32; 4]) -> u32 {
+    card - card % 4 + perm[(card % 4) as usize]
+}
+
+/// ## Canonical combinations
+/// Combinations of `k` cards of the deck, up to 5, one per class of suit symmetry, with the size of the class.
+/// The representative of a class is its smallest combination, the cards being sorted.
+/// The remaining slots of the array are filled with `u32::MAX`.
+pub(crate) fn canonical_combinations(deck: &[u32], k: usize, perms: &[[u32; 4]]) -> Vec<([u32; 5], u64)> {
+    let mut deck = deck.to_vec();
+    deck.sort();
+
+    let mut tops = vec![];
+    let mut images = Vec::with_capacity(perms.len());
+    for_each_combination(&deck, k, |comb| {
+        images.clear();
+        for perm in perms {
+            let mut image = *comb;
+            image[..k].iter_mut().for_each(|c| *c = permute_suit(*c, perm));
+            image[..k].sort();
+            images.push(image);
+        }
+        if images.iter().all(|x| x >= comb) {
+            images.sort();
+            images.dedup();
+            tops.push((*comb, images.len() as u64));
+        }
+    });
+    tops
+}
+
+/// ## Combinations of `k` cards of the deck, up to 5, in lexicographic order
+/// Each one is passed to `f` in turn, so they are not stored.
+/// The remaining slots of the array are filled with `u32::MAX`.
+fn for_each_combination<F: FnMut(&[u32; 5])>(deck: &[u32], k: usize, mut f: F) {
+    if k > deck.len() {
+        return;
+    }
+
+    // indexes in the deck of the cards of the combination
+    let mut idx = [0, 1, 2, 3, 4];
+    let mut comb = [u32::MAX; 5];
+    loop {
+        for i in 0..k {
+            comb[i] = deck[idx[i]];
+        }
+        f(&comb);
+
+        // last index that can be moved up, the next ones following it
+        let i = match (0..k).rev().find(|i| idx[*i] < deck.len() - k + i) {
+            Some(i) => i,
+            None => return,
+        };
+        idx[i] += 1;
+        for j in i + 1..k {
+            idx[j] = idx[j - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{calc_equity_exact, canonical_combinations, suit_symmetries};
+    use crate::calc::{self, equity_det::HandEquity, equity_mc::McGameError};
+    use crate::eval::seven::{self, TableSeven};
+    use std::sync::Arc;
+
+    /// Average of the deterministic equity over every completion of the unknown players cards
+    fn calc_equity_brute(t7: &Arc<TableSeven>, players: &[Vec<u32>], table: &[u32]) -> Vec<HandEquity> {
+        let mut completions: Vec<Vec<[u32; 2]>> = vec![vec![]];
+        for p in players.iter() {
+            let mut completions_ = vec![];
+            for comp in completions.iter() {
+                let used = comp
+                    .iter()
+                    .flatten()
+                    .chain(players.iter().flatten())
+                    .chain(table.iter())
+                    .copied()
+                    .collect::<Vec<u32>>();
+                let free = (0..52).filter(|c| !used.contains(c)).collect::<Vec<u32>>();
+                let holes = match p.len() {
+                    2 => vec![[p[0], p[1]]],
+                    1 => free.iter().map(|c| [p[0], *c]).collect(),
+                    _ => free
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(i, c1)| free[..i].iter().map(move |c2| [*c1, *c2]))
+                        .collect(),
+                };
+                for h in holes {
+                    let mut comp_ = comp.clone();
+                    comp_.push(h);
+                    completions_.push(comp_);
+                }
+            }
+            completions = completions_;
+        }
+
+        let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; players.len()];
+        for comp in completions.iter() {
+            let eqty_ =
+                calc::equity_det::calc_equity_det(t7.clone(), comp.clone(), table.to_vec(), vec![], false).unwrap();
+            for (e, e_) in eqty.iter_mut().zip(eqty_.iter()) {
+                *e += *e_;
+            }
+        }
+        for e in eqty.iter_mut() {
+            e.win /= completions.len() as f64;
+            e.tie /= completions.len() as f64;
+        }
+        eqty
+    }
+
+    #[test]
+    fn check_canonical_combinations() {
+        let deck = (0..52).filter(|c| ![0, 4, 8].contains(c)).collect::<Vec<u32>>();
+
+        // clubs fixed: the 3 other suits are interchangeable
+        let perms = suit_symmetries(&[vec![0, 4]], &[8], &[]);
+        assert_eq!(perms.len(), 6);
+
+        // the classes cover all combinations
+        for (k, nb) in [(1, 49), (2, 1176), (3, 18424)] {
+            let tops = canonical_combinations(&deck, k, &perms);
+            assert_eq!(tops.iter().map(|(_, w)| w).sum::<u64>(), nb, "-> fails: k={}", k);
+            assert!(tops.len() < nb as usize, "-> fails: k={}", k);
+        }
+
+        // no symmetry
+        let perms = suit_symmetries(&[vec![0, 5]], &[10, 15], &[]);
+        assert_eq!(perms, vec![[0, 1, 2, 3]]);
+
+        // then all combinations, once each, in lexicographic order
+        let deck = [9, 2, 7, 4, 0, 5, 11];
+        for (k, nb) in [(0, 1), (1, 7), (2, 21), (3, 35), (5, 21)] {
+            let tops = canonical_combinations(&deck, k, &perms);
+            assert_eq!(tops.len(), nb, "-> fails: k={}", k);
+            assert!(
+                tops.windows(2).all(|w| w[0].0 < w[1].0)
+                    && tops.iter().all(|(c, w)| *w == 1 && c[k..].iter().all(|x| *x == u32::MAX)),
+                "-> fails: k={} combinations={:?}",
+                k,
+                tops
+            );
+        }
+    }
+
+    #[test]
+    fn calc_equity_exact_vs_det() {
+        let arc_t7 = seven::build_tables(false);
+
+        // all cards known
+        let tests: Vec<(Vec<[u32; 2]>, Vec<u32>)> = vec![
+            (vec![[8, 29], [4, 11]], vec![]),
+            (vec![[7, 8], [22, 27]], vec![51, 30, 41]),
+            (vec![[8, 9], [11, 28], [0, 51]], vec![15, 47, 23, 33]),
+            (vec![[8, 9], [11, 28]], vec![15, 47, 23, 33, 1]),
+        ];
+
+        for (players, table) in tests.iter() {
+            let equity_det =
+                calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), vec![], false)
+                    .unwrap();
+            let equity = calc_equity_exact(
+                arc_t7.clone(),
+                players.iter().map(|p| p.to_vec()).collect(),
+                table.clone(),
+                vec![],
+                false,
+            )
+            .unwrap();
+
+            for (e, d) in equity.iter().zip(equity_det.iter()) {
+                assert!(
+                    (e.win - d.win).abs() < 1e-12 && (e.tie - d.tie).abs() < 1e-12,
+                    "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                    players,
+                    table,
+                    e,
+                    d
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn calc_equity_exact_unknown() {
+        let arc_t7 = seven::build_tables(false);
+
+        // unknown players cards
+        let tests: Vec<(Vec<Vec<u32>>, Vec<u32>)> = vec![
+            // random hand, on the river
+            (vec![vec![8, 9], vec![]], vec![15, 47, 23, 33, 1]),
+            // one card known, on the turn
+            (vec![vec![8, 9], vec![11]], vec![15, 47, 23, 33]),
+            // three-way, on the turn
+            (vec![vec![8, 9], vec![11, 28], vec![]], vec![15, 47, 23, 33]),
+            // suit symmetry, on the turn
+            (vec![vec![0, 4], vec![]], vec![8, 12, 48, 32]),
+            // suit symmetry, on the flop
+            (vec![vec![0, 4], vec![1]], vec![8, 12, 48]),
+        ];
+
+        for (players, table) in tests.iter() {
+            let equity_brute = calc_equity_brute(&arc_t7, players, table);
+            let equity = calc_equity_exact(arc_t7.clone(), players.clone(), table.clone(), vec![], false).unwrap();
+
+            assert_eq!(equity.len(), players.len());
+            for (e, b) in equity.iter().zip(equity_brute.iter()) {
+                assert!(
+                    (e.win - b.win).abs() < 1e-12 && (e.tie - b.tie).abs() < 1e-12,
+                    "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                    players,
+                    table,
+                    e,
+                    b
+                );
+            }
+
+            // wins and shares of ties sum to 1 over all seats
+            let total = equity.iter().map(|e| e.win + e.tie).sum::<f64>();
+            assert!((total - 1.0).abs() < 1e-12, "-> fails: equity={:?}", equity);
+        }
+
+        let equity = calc_equity_exact(arc_t7.clone(), vec![vec![8, 9], vec![9]], vec![], vec![], false);
+        assert!(
+            matches!(equity, Err(McGameError::NotDistinctCards(..))),
+            "-> fails: equity={:?}",
+            equity
+        );
+    }
+
+    #[test]
+    fn calc_equity_exact_vs_mc() {
+        let arc_t7 = seven::build_tables(false);
+
+        // random hand, on the flop with dead cards
+        let players = vec![vec![8, 9], vec![]];
+        let table = vec![15, 47, 23];
+        let dead = vec![0, 1, 2];
+
+        let equity = calc_equity_exact(arc_t7.clone(), players.clone(), table.clone(), dead.clone(), false).unwrap();
+        let equity_mc = calc::equity_mc::calc_equity_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            table.clone(),
+            dead.clone(),
+            calc::equity_mc::McStop::nb_game(2_000_000),
+            Some(0),
+        )
+        .unwrap();
+
+        for (e, m) in equity.iter().zip(equity_mc.equity.iter()) {
+            assert!(
+                (e.win - m.win).abs() <= 4.0 * m.win_std_err && (e.tie - m.tie).abs() <= 4.0 * m.tie_std_err,
+                "-> fails: players={:?}, table={:?}, dead={:?}\nfound:equity={:?}, want:equity={:?}",
+                players,
+                table,
+                dead,
+                e,
+                m
+            );
+        }
+    }
+}
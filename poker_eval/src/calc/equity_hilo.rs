@@ -0,0 +1,397 @@
+//! # Hi-Lo split pot equity calculation
+//! In Hi-Lo games, e.g. Omaha/8 or Stud/8, the pot is split between the best high hand and the best qualifying low hand:
+//! + the low must be 5 distinct faces of 8 or lower, ace being low, see [low](crate::eval::low)
+//! + if no low qualifies, the best high hand wins the whole pot
+//! + each half is split between tied hands
+//!
+//! So the equity of a player is the fraction of the pot it gets, see [HiLoEquity].
+//!
+//! This module provides:
+//! - [calc_equity_omaha_hilo_det]: Omaha/8 equity, deterministically, i.e. exhaustively, knowing all player cards
+//! - [get_rank_hilo_seven]: high rank and qualifying low rank of 7 cards, e.g. for Stud/8
+
+//!   # Example
+//!
+//! ```
+//! use std::sync::Arc;
+//! use poker_eval::eval::{five, low};
+//! use poker_eval::calc::equity_hilo::calc_equity_omaha_hilo_det;
+//!
+//! // you need create Arc<TableFive> arc_t5 and Arc<TableLow> arc_tl beforehand once
+//! let arc_t5 = Arc::new(five::build_tables(false));
+//! let arc_tl = Arc::new(low::build_tables(false));
+//!
+//! // then you can call calc_equity_omaha_hilo_det multiple times
+//! let equity = calc_equity_omaha_hilo_det(
+//!     // clone of Arc<TableFive>
+//!     arc_t5.clone(),
+//!     // clone of Arc<TableLow>
+//!     arc_tl.clone(),
+//!     // players cards: Ah 2h 3c Kd vs Qs Qd Js Td
+//!     vec![vec![50, 2, 4, 45], vec![43, 41, 39, 33]],
+//!     // table cards: 4s 7d Qc
+//!     vec![11, 21, 40],
+//!     // verbose
+//!     false,
+//! )
+//! .unwrap();
+//! println!("equity = {:?}", equity);
+//!
+//! // fractions of the pot
+//! assert!((equity[0].pot() + equity[1].pot() - 1.0).abs() < 1e-9);
+//! ```
+
+use std::{sync::Arc, thread, time::Instant};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::equity_omaha::{build_deck, check_game, for_each_runout, to_usize, OmahaGameError};
+use crate::eval::{
+    five::TableFive,
+    low::{is_eight_or_better, rank_a5_low_seven, TableLow},
+    omaha::OmahaHole,
+    seven::{get_rank, TableSeven},
+};
+
+/// ## Hi-Lo hand equity container
+/// Fractions of the pot won by a hand, separating the high and low halves, and the probability to scoop.
+/// The actual equity is the sum of the high and low shares, see [HiLoEquity::pot].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HiLoEquity {
+    /// Fraction of the pot won with the high hand - between 0 and 1, the whole pot if no low qualifies
+    pub high: f64,
+    /// Fraction of the pot won with the low hand - between 0 and 0.5
+    pub low: f64,
+    /// Probability of winning the whole pot alone - between 0 and 1
+    pub scoop: f64,
+}
+
+impl HiLoEquity {
+    /// Fraction of the pot won - between 0 and 1
+    pub fn pot(&self) -> f64 {
+        self.high + self.low
+    }
+}
+
+impl std::ops::AddAssign for HiLoEquity {
+    fn add_assign(&mut self, other: HiLoEquity) {
+        self.high += other.high;
+        self.low += other.low;
+        self.scoop += other.scoop;
+    }
+}
+
+/// ## Calculate Omaha/8 equity deterministically ie. exhaustively
+/// This requires knowing all players cards: 4 or 5 each, same for all players.
+///
+/// High and low hands are both made of exactly 2 hole cards and 3 board cards, not necessarily the same.
+pub fn calc_equity_omaha_hilo_det(
+    t5: Arc<TableFive>,
+    tl: Arc<TableLow>,
+    player_cards: Vec<Vec<u32>>,
+    table_cards: Vec<u32>,
+    verbose: bool,
+) -> Result<Vec<HiLoEquity>, OmahaGameError> {
+    let start = Instant::now();
+
+    check_game(&player_cards, &table_cards, t5.variant, false)?;
+
+    let nb_player = player_cards.len();
+    let nb_table_card = table_cards.len();
+    let deck = build_deck(&player_cards, &table_cards, t5.variant);
+
+    let hole = player_cards
+        .iter()
+        .map(|p| OmahaHole::from_cards(&to_usize(p)))
+        .collect::<Vec<OmahaHole>>();
+    let table = to_usize(&table_cards);
+
+    let mut eqty = vec![HiLoEquity::default(); nb_player];
+    let mut n_game = 0;
+
+    if nb_table_card == 0 {
+        // zero table cards: one thread per first card
+        let hole_ = Arc::new(hole);
+        let deck_ = Arc::new(deck);
+        let mut handles = vec![];
+
+        for i1 in 0..deck_.len() {
+            let t5_ = Arc::clone(&t5);
+            let tl_ = Arc::clone(&tl);
+            let hole_ = Arc::clone(&hole_);
+            let deck_ = Arc::clone(&deck_);
+
+            let handle =
+                thread::spawn(move || calc_eqty_hilo_runouts(&t5_, &tl_, &hole_, &[deck_[i1]], &deck_[0..i1], 4));
+            handles.push(handle);
+        }
+
+        for handle in handles {
+            let (eqty_, n_game_) = handle.join().unwrap();
+            for p in 0..nb_player {
+                eqty[p] += eqty_[p];
+            }
+            n_game += n_game_;
+        }
+    } else {
+        // 3, 4 or 5 table cards
+        (eqty, n_game) = calc_eqty_hilo_runouts(&t5, &tl, &hole, &table, &deck, 5 - nb_table_card);
+    }
+
+    let equity = eqty
+        .iter()
+        .map(|e| HiLoEquity {
+            high: e.high / (n_game as f64),
+            low: e.low / (n_game as f64),
+            scoop: e.scoop / (n_game as f64),
+        })
+        .collect::<Vec<HiLoEquity>>();
+
+    let end = Instant::now();
+
+    if verbose {
+        println!("calc_equity_omaha_hilo_det runtime: {:?}", end - start);
+    }
+
+    Ok(equity)
+}
+
+/// ## Get the high rank and the 8-or-better low rank of a 7-card hand
+/// As in Stud/8: the best 5 cards for high and for low, not necessarily the same.
+/// The low rank is None if it does not qualify.
+pub fn get_rank_hilo_seven(t7: &TableSeven, tl: &TableLow, c: [usize; 7]) -> (u32, Option<u32>) {
+    let rank_low = rank_a5_low_seven(tl, c);
+    let rank_low = match is_eight_or_better(rank_low) {
+        true => Some(rank_low),
+        false => None,
+    };
+    (get_rank(t7, c), rank_low)
+}
+
+/// Hi-Lo equity sums and number of games, for all boards made of `prefix` and `k` cards among `deck`
+fn calc_eqty_hilo_runouts(
+    t5: &TableFive,
+    tl: &TableLow,
+    hole: &[OmahaHole],
+    prefix: &[usize],
+    deck: &[usize],
+    k: usize,
+) -> (Vec<HiLoEquity>, u32) {
+    let nb_player = hole.len();
+
+    let mut eqty = vec![HiLoEquity::default(); nb_player];
+    let mut rank_high = vec![0; nb_player];
+    let mut rank_low = vec![None; nb_player];
+
+    let n_game = for_each_runout(prefix, deck, k, |board_| {
+        for p in 0..nb_player {
+            rank_high[p] = hole[p].rank(t5, board_);
+            rank_low[p] = hole[p].rank_low(tl, board_);
+        }
+        update_eqty_hilo(&mut eqty, &rank_high, &rank_low);
+    });
+
+    (eqty, n_game)
+}
+
+/// Add the result of a Hi-Lo game to the players equity sums
+/// + the best high ranks share the high half, or the whole pot if no low qualifies
+/// + the best qualifying low ranks share the low half
+pub(crate) fn update_eqty_hilo(eqty: &mut [HiLoEquity], rank_high: &[u32], rank_low: &[Option<u32>]) {
+    let max_high = rank_high.iter().max().copied().unwrap_or(0);
+    let nb_max_high = rank_high.iter().filter(|r| **r == max_high).count();
+
+    let max_low = rank_low.iter().max().copied().flatten();
+    let nb_max_low = rank_low.iter().filter(|r| max_low.is_some() && **r == max_low).count();
+
+    let half = match max_low {
+        Some(_) => 0.5,
+        None => 1.0,
+    };
+
+    for (p, e) in eqty.iter_mut().enumerate() {
+        let mut share = 0.0;
+        if rank_high[p] == max_high {
+            e.high += half / nb_max_high as f64;
+            share += half / nb_max_high as f64;
+        }
+        if max_low.is_some() && rank_low[p] == max_low {
+            e.low += 0.5 / nb_max_low as f64;
+            share += 0.5 / nb_max_low as f64;
+        }
+        if share == 1.0 {
+            e.scoop += 1.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use super::{calc_equity_omaha_hilo_det, get_rank_hilo_seven, update_eqty_hilo, HiLoEquity};
+    use crate::calc::equity_omaha::calc_equity_omaha_det;
+    use crate::eval::five::{self, get_rank_five, TableFive};
+    use crate::eval::low::{self, is_eight_or_better, rank_a5_low, TableLow};
+    use crate::eval::seven;
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_hilo_normal() {
+        is_normal::<HiLoEquity>();
+    }
+
+    #[test]
+    fn update_hilo() {
+        for (rank_high, rank_low, want) in [
+            // scoop: best high and best low
+            (
+                vec![10, 5],
+                vec![Some(6170), Some(6150)],
+                vec![[0.5, 0.5, 1.0], [0.0, 0.0, 0.0]],
+            ),
+            // scoop: best high, no low
+            (vec![10, 5], vec![None, None], vec![[1.0, 0.0, 1.0], [0.0, 0.0, 0.0]]),
+            // split: high vs low
+            (
+                vec![10, 5],
+                vec![None, Some(6150)],
+                vec![[0.5, 0.0, 0.0], [0.0, 0.5, 0.0]],
+            ),
+            // quartered: best high, tied low
+            (
+                vec![10, 5],
+                vec![Some(6150), Some(6150)],
+                vec![[0.5, 0.25, 0.0], [0.0, 0.25, 0.0]],
+            ),
+            // tied high, no low
+            (
+                vec![10, 10, 5],
+                vec![None, None, None],
+                vec![[0.5, 0.0, 0.0], [0.5, 0.0, 0.0], [0.0, 0.0, 0.0]],
+            ),
+        ] {
+            let mut eqty = vec![HiLoEquity::default(); rank_high.len()];
+            update_eqty_hilo(&mut eqty, &rank_high, &rank_low);
+            for (e, [high, low, scoop]) in eqty.iter().zip(want.iter()) {
+                assert_eq!(
+                    (e.high, e.low, e.scoop),
+                    (*high, *low, *scoop),
+                    "-> fails: rank_high={:?}, rank_low={:?}\nfound:equity={:?}",
+                    rank_high,
+                    rank_low,
+                    eqty
+                );
+            }
+        }
+    }
+
+    /// Best high and qualifying low over all 2 hole cards and 3 board cards
+    fn rank_brute_force(t5: &TableFive, tl: &TableLow, hole: &[u32], board: &[u32]) -> (u32, Option<u32>) {
+        let mut max_high = 0;
+        let mut max_low = 0;
+        for h1 in 0..hole.len() {
+            for h2 in 0..h1 {
+                for b1 in 0..board.len() {
+                    for b2 in 0..b1 {
+                        for b3 in 0..b2 {
+                            let c = [hole[h1], hole[h2], board[b1], board[b2], board[b3]].map(|c| c as usize);
+                            max_high = max_high.max(get_rank_five(t5, c));
+                            max_low = max_low.max(rank_a5_low(tl, c));
+                        }
+                    }
+                }
+            }
+        }
+        (max_high, Some(max_low).filter(|r| is_eight_or_better(*r)))
+    }
+
+    #[test]
+    fn calc_equity_omaha_hilo_det_turn() {
+        let arc_t5 = Arc::new(five::build_tables(false));
+        let arc_tl = Arc::new(low::build_tables(false));
+
+        let tests: Vec<(Vec<Vec<u32>>, Vec<u32>)> = vec![
+            // Ah 2h 3c Kd vs Qs Qd Js Td on 4s 7d Qc 8c
+            (vec![vec![50, 2, 4, 45], vec![43, 41, 39, 33]], vec![11, 21, 40, 24]),
+            // Ac 2c 5d 6d vs As 3s Kh Kc vs 7h 8h 9s Ts on 4c 4d Jc 2s
+            (
+                vec![vec![48, 0, 13, 17], vec![51, 7, 46, 44], vec![22, 26, 31, 35]],
+                vec![8, 9, 36, 3],
+            ),
+        ];
+
+        for (players, table) in tests.iter() {
+            // brute force over the river cards
+            let mut want = vec![HiLoEquity::default(); players.len()];
+            let mut n_game = 0.0;
+            for river in (0..52).filter(|c| !table.contains(c) && !players.iter().any(|p| p.contains(c))) {
+                let board = [table.as_slice(), &[river]].concat();
+                let (rank_high, rank_low): (Vec<u32>, Vec<Option<u32>>) = players
+                    .iter()
+                    .map(|p| rank_brute_force(&arc_t5, &arc_tl, p, &board))
+                    .unzip();
+                update_eqty_hilo(&mut want, &rank_high, &rank_low);
+                n_game += 1.0;
+            }
+
+            let equity =
+                calc_equity_omaha_hilo_det(arc_t5.clone(), arc_tl.clone(), players.clone(), table.clone(), false);
+            assert!(equity.is_ok(), "-> fails: players={:?}, table={:?}", players, table);
+
+            let precision = 1e-9;
+            for (e, w) in equity.unwrap().iter().zip(want.iter()) {
+                assert!(
+                    (e.high - w.high / n_game).abs() < precision
+                        && (e.low - w.low / n_game).abs() < precision
+                        && (e.scoop - w.scoop / n_game).abs() < precision,
+                    "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                    players,
+                    table,
+                    e,
+                    w
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn calc_equity_omaha_hilo_det_no_low() {
+        let arc_t5 = Arc::new(five::build_tables(false));
+        let arc_tl = Arc::new(low::build_tables(false));
+
+        // Ah 2h 3c Kd vs Qs Qd Js Td on Kc Jd 9h: no low possible, same as high only
+        let players = vec![vec![50, 2, 4, 45], vec![43, 41, 39, 33]];
+        let table = vec![44, 37, 30];
+
+        let equity =
+            calc_equity_omaha_hilo_det(arc_t5.clone(), arc_tl.clone(), players.clone(), table.clone(), false).unwrap();
+        let equity_high = calc_equity_omaha_det(arc_t5.clone(), players.clone(), table.clone(), false).unwrap();
+
+        let precision = 1e-9;
+        for (e, h) in equity.iter().zip(equity_high.iter()) {
+            assert_eq!(e.low, 0.0, "-> fails: equity={:?}", e);
+            assert!((e.high - (h.win + h.tie)).abs() < precision, "-> fails: equity={:?}", e);
+            assert!((e.scoop - h.win).abs() < precision, "-> fails: equity={:?}", e);
+        }
+        assert!((equity.iter().map(|e| e.pot()).sum::<f64>() - 1.0).abs() < precision);
+    }
+
+    #[test]
+    fn rank_hilo_seven() {
+        let t7 = seven::build_tables(false);
+        let tl = low::build_tables(false);
+
+        // Kc Kd 8h 6s 4c 3d Ah: pair of kings, 8643A low
+        let (rank_high, rank_low) = get_rank_hilo_seven(&t7, &tl, [44, 45, 26, 19, 8, 5, 50]);
+        assert_eq!(rank_high, seven::get_rank(&t7, [44, 45, 26, 19, 8, 5, 50]));
+        assert_eq!(rank_low, Some(rank_a5_low(&tl, [26, 19, 8, 5, 50])));
+
+        // Kc Kd 9h 6s 4c 3d Ah: no low
+        let (_, rank_low) = get_rank_hilo_seven(&t7, &tl, [44, 45, 30, 19, 8, 5, 50]);
+        assert_eq!(rank_low, None);
+    }
+}
@@ -5,7 +5,7 @@
 //!
 //! ```
 //! use poker_eval::eval::seven::build_tables;
-//! use poker_eval::calc::equity_mc::calc_equity_monte_carlo;
+//! use poker_eval::calc::equity_mc::{calc_equity_monte_carlo, McStop};
 //!
 //! // you need create Arc<TableSeven> arc_t7 beforehand once
 //! let arc_t7 = build_tables(true);
@@ -18,25 +18,137 @@
 //!     vec![vec![8, 9], vec![11, 28], vec![]],
 //!     // table cards
 //!     vec![15, 47, 23, 33],
+//!     // dead cards
+//!     vec![],
 //!     // number of game
-//!     10_000_000,
+//!     McStop::nb_game(10_000_000),
+//!     // seed, None for a random one
+//!     Some(42),
 //! );
 //! println!("equity = {:?}", equity);
-//! // Ok(HandEquity { win: 0.3167, tie: 0.0 })
+//! ```
+//!
+//! The simulation can also stop early, once a target standard error is reached or a time budget is spent.
+//!
+//! ```
+//! use poker_eval::eval::seven::build_tables;
+//! use poker_eval::calc::equity_mc::{calc_equity_monte_carlo, McStop};
+//!
+//! let arc_t7 = build_tables(false);
+//!
+//! let stop = McStop {
+//!     // at most
+//!     nb_game: 100_000_000,
+//!     // standard error of all win and tie probabilities
+//!     std_err: Some(1e-3),
+//!     // time budget, e.g. Some(Duration::from_millis(500)) - none here, so the standard error is always reached
+//!     time: None,
+//! };
+//! let equity = calc_equity_monte_carlo(arc_t7, vec![vec![8, 9], vec![]], vec![15, 47, 23], vec![], stop, None).unwrap();
+//! assert!(equity.equity.iter().all(|e| e.win_std_err <= 1e-3 && e.tie_std_err <= 1e-3));
+//! println!("nb_game = {}, equity = {:?}", equity.nb_game, equity.equity);
 //! ```
 
-use rand::seq::SliceRandom;
-use std::{collections::HashSet, sync::Arc, thread, time::Instant};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
 use super::equity_det::HandEquity;
 use crate::{
-    eval::seven::{get_rank, TableSeven},
-    keys::DECK_SIZE,
+    eval::{accumulator::HandAccumulator, seven::TableSeven},
+    keys::{Variant, DECK_SIZE},
 };
 
+/// Maximum number of games of a monte carlo simulation
+pub const MAX_NB_GAME: u32 = 1_000_000_000;
+
+/// Normal quantile of the 95% confidence intervals
+pub const CI_Z: f64 = 1.96;
+
+/// Number of games between two checks of the stopping rule
+pub(crate) const NB_GAME_ROUND: u32 = 500_000;
+
+/// Number of games drawn from the same random stream
+pub(crate) const NB_GAME_CHUNK: u32 = 10_000;
+
+/// ## Monte carlo stopping rule
+/// The simulation runs `nb_game` games at most.  
+/// It stops earlier when any of the optional targets is reached:
+/// + `std_err`: the standard errors of all win and tie probabilities are below it
+/// + `time`: the time budget is spent  
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct McStop {
+    /// Maximum number of games - between 1 and [MAX_NB_GAME]
+    pub nb_game: u32,
+    /// Target standard error, optional
+    pub std_err: Option<f64>,
+    /// Time budget, optional
+    pub time: Option<Duration>,
+}
+
+impl McStop {
+    /// Fixed number of games, without early stopping
+    pub fn nb_game(nb_game: u32) -> McStop {
+        McStop {
+            nb_game,
+            std_err: None,
+            time: None,
+        }
+    }
+}
+
+/// ## Monte carlo hand equity
+/// Estimated win and tie probabilities of a hand, with their standard errors and 95% confidence intervals.  
+/// The intervals are the normal approximation, clipped to [0, 1].  
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct McHandEquity {
+    /// Probability of winning - between 0 and 1
+    pub win: f64,
+    /// Probability of tie - between 0 and 1
+    pub tie: f64,
+    /// Standard error of the probability of winning
+    pub win_std_err: f64,
+    /// Standard error of the probability of tie
+    pub tie_std_err: f64,
+    /// 95% confidence interval of the probability of winning
+    pub win_ci: [f64; 2],
+    /// 95% confidence interval of the probability of tie
+    pub tie_ci: [f64; 2],
+}
+
+impl McHandEquity {
+    /// Point estimate only
+    pub fn hand_equity(&self) -> HandEquity {
+        HandEquity {
+            win: self.win,
+            tie: self.tie,
+        }
+    }
+}
+
+/// ## Monte carlo equity
+/// Equity of each player, and the number of games actually simulated.  
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct McEquity {
+    /// Number of games simulated
+    pub nb_game: u32,
+    /// Equity of each player
+    pub equity: Vec<McHandEquity>,
+}
+
 /// ## Game description error
 /// This error type is used to describe the errors that can occur when describing a monte carlo game.  
+/// It is also used by the exact calculation [calc_equity_exact](super::equity_exact::calc_equity_exact), which takes the same input.  
 #[derive(Error, Debug)]
 pub enum McGameError {
     // player
@@ -56,25 +168,150 @@ pub enum McGameError {
     /// invalid table card
     #[error("invalid table card {0}: {1} - must be between 0 and 51")]
     InvalidTableCard(u32, u32),
-    //both
+    // dead
+    /// invalid dead card
+    #[error("invalid dead card: {0} - must be between 0 and 51")]
+    InvalidDeadCard(u32),
+    // all
+    /// card not in the deck of the variant, e.g. a 2 in short-deck
+    #[error("invalid card: {0} - not in the {1} deck")]
+    CardNotInDeck(u32, Variant),
     /// not distinct cards
-    #[error("players: {0:?} table: {1:?} - all cards must be distinct")]
-    NotDistinctCards(Vec<Vec<u32>>, Vec<u32>),
+    #[error("players: {0:?} table: {1:?} dead: {2:?} - all cards must be distinct")]
+    NotDistinctCards(Vec<Vec<u32>>, Vec<u32>, Vec<u32>),
+    /// not enough cards left in the deck
+    #[error("not enough cards: {0} to deal - {1} left in the deck")]
+    NotEnoughCards(u32, u32),
+    // stop
+    /// invalid number of games
+    #[error("invalid nb games: {0} - must be between 1 and 1000000000")]
+    InvalidNbGame(u32),
+    /// invalid target standard error
+    #[error("invalid std err: {0} - must be positive")]
+    InvalidStdErr(f64),
 }
 
-/// ## Calculate equity of hand in monte carlo mode
+/// ## Calculate equity of hands in monte carlo mode
 /// This does not require knowing all players cards.  
+/// The equity of each player is returned, including players with partially or fully unknown cards.  
+/// For each game, the wins and the shares of ties sum to 1 over the players.  
+/// The deck is the one of the tables variant, e.g. 36 cards for [Variant::ShortDeck].  
+/// The dead cards, e.g. folded cards shown, are removed from the deck.  
+/// The games are simulated until the stopping rule [McStop] is met.  
+/// The games are split in chunks of fixed size, each drawn from its own random stream, and the chunks are spread over the threads:
+/// + with `seed`, the streams are derived from it, so the same seed gives identical results on any machine, unless a time budget is set
+/// + without `seed`, the streams are seeded from the OS entropy  
+///
+/// The streams are [SmallRng], fast but not portable across versions of the `rand` crate.  
 pub fn calc_equity_monte_carlo(
     t7: Arc<TableSeven>,
     player_cards: Vec<Vec<u32>>,
     table_cards: Vec<u32>,
-    nb_game: u32,
-) -> Result<HandEquity, McGameError> {
+    dead_cards: Vec<u32>,
+    stop: McStop,
+    seed: Option<u64>,
+) -> Result<McEquity, McGameError> {
+    let nb_player = player_cards.len() as u32;
+
+    // start check input
+    let deck = check_game(t7.t5.variant, &player_cards, &table_cards, &dead_cards)?;
+
+    match stop.nb_game {
+        1..=MAX_NB_GAME => (),
+        _ => return Err(McGameError::InvalidNbGame(stop.nb_game)),
+    }
+    match stop.std_err {
+        Some(x) if x.is_nan() || x <= 0.0 => return Err(McGameError::InvalidStdErr(x)),
+        _ => (),
+    }
+    // end check input
+
+    let nb_rnd_cards =
+        2 * player_cards.len() - player_cards.iter().map(|p| p.len()).sum::<usize>() + 5 - table_cards.len();
+
+    let start = Instant::now();
+
+    // known cards are summed once
+    let table = HandAccumulator::from_cards(&table_cards.iter().map(|x| *x as usize).collect::<Vec<usize>>());
+    let hole = player_cards
+        .iter()
+        .map(|p| HandAccumulator::from_cards(&p.iter().map(|x| *x as usize).collect::<Vec<usize>>()))
+        .collect::<Vec<HandAccumulator>>();
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+
+    // master stream, from which the stream of each chunk is seeded
+    let mut rng = match seed {
+        Some(x) => SmallRng::seed_from_u64(x),
+        None => SmallRng::from_entropy(),
+    };
+
+    // without early stopping, all games are run in one round
+    let nb_game_round = match stop.std_err.is_some() || stop.time.is_some() {
+        true => NB_GAME_ROUND,
+        false => stop.nb_game,
+    };
+
+    let mut moments = vec![EqtyMoments::default(); nb_player as usize];
+    let mut nb_game = 0u32;
+
+    while nb_game < stop.nb_game {
+        let n_round = nb_game_round.min(stop.nb_game - nb_game);
+
+        let arr_moments = calc_chunks(&mut rng, &deck, nb_rnd_cards, n_round, n_thread, |sampler, n_game| {
+            calc_eqty_batch(&t7, &player_cards, table, &hole, sampler, n_game)
+        });
+
+        for m in arr_moments.iter() {
+            for (p, x) in m.iter().enumerate() {
+                moments[p] += *x;
+            }
+        }
+        nb_game += n_round;
+
+        if let Some(time) = stop.time {
+            if start.elapsed() >= time {
+                break;
+            }
+        }
+        if let Some(std_err) = stop.std_err {
+            let eqty = moments
+                .iter()
+                .map(|m| m.to_equity(nb_game))
+                .collect::<Vec<McHandEquity>>();
+            if eqty
+                .iter()
+                .all(|e| e.win_std_err <= std_err && e.tie_std_err <= std_err)
+            {
+                break;
+            }
+        }
+    }
+
+    let equity = moments
+        .iter()
+        .map(|m| m.to_equity(nb_game))
+        .collect::<Vec<McHandEquity>>();
+
+    let end = Instant::now();
+    println!("runtime = {:?}", end - start);
+
+    Ok(McEquity { nb_game, equity })
+}
+
+/// ## Check a partial game description
+/// Players have 0, 1 or 2 known cards, except the first one who has 2, and 0 to 5 table cards are known.  
+/// Returns the deck of the variant, without the known and dead cards.  
+pub(crate) fn check_game(
+    variant: Variant,
+    player_cards: &[Vec<u32>],
+    table_cards: &[u32],
+    dead_cards: &[u32],
+) -> Result<Vec<u32>, McGameError> {
     let deck_size = DECK_SIZE as u32;
     let nb_player = player_cards.len() as u32;
     let nb_table_card = table_cards.len();
 
-    // start check input
     match nb_player {
         1..=10 => (),
         _ => return Err(McGameError::InvalidNbPlayer(nb_player)),
@@ -115,287 +352,675 @@ pub fn calc_equity_monte_carlo(
         }
     }
 
-    let all_cards_set = player_cards
+    for d in dead_cards.iter() {
+        match *d {
+            x if (x < deck_size) => (),
+            _ => return Err(McGameError::InvalidDeadCard(*d)),
+        }
+    }
+
+    let all_cards = player_cards
         .iter()
         .flatten()
-        .copied()
-        .chain(table_cards.iter().copied())
-        .collect::<HashSet<u32>>();
+        .chain(table_cards.iter())
+        .chain(dead_cards.iter());
+    if let Some(c) = all_cards.clone().find(|c| !variant.contains(**c as usize)) {
+        return Err(McGameError::CardNotInDeck(*c, variant));
+    }
+
+    let all_cards_set = all_cards.copied().collect::<HashSet<u32>>();
 
     let all_cards_vec = player_cards
         .iter()
         .flatten()
         .copied()
         .chain(table_cards.iter().copied())
+        .chain(dead_cards.iter().copied())
         .collect::<Vec<u32>>();
 
     match all_cards_vec.len() == all_cards_set.len() {
         true => (),
-        false => return Err(McGameError::NotDistinctCards(player_cards, table_cards)),
+        false => {
+            return Err(McGameError::NotDistinctCards(
+                player_cards.to_vec(),
+                table_cards.to_vec(),
+                dead_cards.to_vec(),
+            ))
+        }
     }
-    // end check input
-
-    let start = Instant::now();
 
-    let deck = (0..deck_size)
+    let deck = variant
+        .deck()
+        .into_iter()
+        .map(|c| c as u32)
         .filter(|c| !all_cards_set.contains(c))
         .collect::<Vec<u32>>();
 
-    let mut arr_eqty = vec![];
-
-    let mut handles = vec![];
-
-    let n_thread = thread::available_parallelism().unwrap().get();
-    let n_game_per_thread = nb_game / n_thread as u32;
+    let nb_rnd_cards = 2 * player_cards.len() - player_cards.iter().map(|p| p.len()).sum::<usize>() + 5 - nb_table_card;
+    match deck.len() >= nb_rnd_cards {
+        true => (),
+        false => return Err(McGameError::NotEnoughCards(nb_rnd_cards as u32, deck.len() as u32)),
+    }
 
-    // println!("n_thread = {}", n_thread);
-    // println!("n_game_per_thread = {}", n_game_per_thread);
+    Ok(deck)
+}
 
-    for _ in 0..n_thread {
-        let t7_ = Arc::clone(&t7);
-        let player_cards_ = player_cards.clone();
-        let table_cards_ = table_cards.clone();
-        let deck_ = deck.clone();
+/// ## Equity moments
+/// Sums over the games of the win and tie shares of a player, and of the squared tie shares.  
+/// The win share is 0 or 1 so it is its own square.  
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct EqtyMoments {
+    pub(crate) win: f64,
+    pub(crate) tie: f64,
+    pub(crate) tie_sq: f64,
+}
 
-        let handle = thread::spawn(move || {
-            let eqty_ = calc_eqty_batch(t7_, player_cards_, table_cards_, deck_, n_game_per_thread);
-            eqty_
-        });
+impl std::ops::AddAssign for EqtyMoments {
+    fn add_assign(&mut self, other: EqtyMoments) {
+        self.win += other.win;
+        self.tie += other.tie;
+        self.tie_sq += other.tie_sq;
+    }
+}
 
-        handles.push(handle);
+impl EqtyMoments {
+    /// Means, standard errors and confidence intervals over `nb_game` games
+    pub(crate) fn to_equity(self, nb_game: u32) -> McHandEquity {
+        let n = nb_game as f64;
+        let win = self.win / n;
+        let tie = self.tie / n;
+        let win_std_err = std_err(win, win, n);
+        let tie_std_err = std_err(tie, self.tie_sq / n, n);
+        McHandEquity {
+            win,
+            tie,
+            win_std_err,
+            tie_std_err,
+            win_ci: conf_int(win, win_std_err),
+            tie_ci: conf_int(tie, tie_std_err),
+        }
     }
+}
 
-    for (_i, handle) in handles.into_iter().enumerate() {
-        let eqty = handle.join().unwrap();
-        // println!("eqty[{}] = {:?}", _i, eqty);
-        arr_eqty.push(eqty);
+/// Standard error of a mean, from the means of the values and of their squares
+fn std_err(mean: f64, mean_sq: f64, n: f64) -> f64 {
+    match n > 1.0 {
+        true => ((mean_sq - mean * mean).max(0.0) / (n - 1.0)).sqrt(),
+        false => f64::INFINITY,
     }
+}
 
-    let eqty = HandEquity {
-        win: arr_eqty.iter().map(|x| x.win).sum::<f64>() / arr_eqty.len() as f64,
-        tie: arr_eqty.iter().map(|x| x.tie).sum::<f64>() / arr_eqty.len() as f64,
-    };
+/// 95% confidence interval of a probability, clipped to [0, 1]
+fn conf_int(mean: f64, std_err: f64) -> [f64; 2] {
+    [(mean - CI_Z * std_err).max(0.0), (mean + CI_Z * std_err).min(1.0)]
+}
 
-    let end = Instant::now();
-    println!("runtime = {:?}", end - start);
+/// Update the moments of each player with the ranks of a game
+fn update_eqty_moments(moments: &mut [EqtyMoments], rank: &[u32]) {
+    let max_rank = *rank.iter().max().unwrap();
+    let nb_max = rank.iter().filter(|r| **r == max_rank).count();
 
-    Ok(eqty)
+    for (m, r) in moments.iter_mut().zip(rank.iter()) {
+        if *r == max_rank {
+            if nb_max == 1 {
+                m.win += 1.0;
+            } else {
+                let share = 1.0 / (nb_max as f64);
+                m.tie += share;
+                m.tie_sq += share * share;
+            }
+        }
+    }
 }
 
-fn calc_eqty_batch(
-    t7: Arc<TableSeven>,
-    player_cards: Vec<Vec<u32>>,
-    table_cards: Vec<u32>,
+/// ## Random cards sampler
+/// State of the random draws of a thread, kept between the rounds of a simulation.  
+pub(crate) struct Sampler {
     deck: Vec<u32>,
-    nb_game: u32,
-) -> HandEquity {
-    let _start = Instant::now();
+    rnd_cards: Vec<u32>,
+    rng: SmallRng,
+}
 
-    let nb_player = player_cards.len() as u32;
-    let nb_player_cards = player_cards.iter().map(|p| p.len()).sum::<usize>();
-    let nb_table_cards = table_cards.len();
-    let nb_rnd_cards = 2 * (nb_player as usize) - nb_player_cards + (5 - nb_table_cards);
+impl Sampler {
+    pub(crate) fn new(deck: Vec<u32>, nb_rnd_cards: usize, rng: SmallRng) -> Sampler {
+        Sampler {
+            deck,
+            rnd_cards: vec![0u32; nb_rnd_cards],
+            rng,
+        }
+    }
+
+    pub(crate) fn draw(&mut self) -> &[u32] {
+        draw_card(&mut self.rnd_cards, &mut self.deck, &mut self.rng);
+        &self.rnd_cards
+    }
+
+    /// Random stream, for other draws than cards
+    pub(crate) fn rng(&mut self) -> &mut SmallRng {
+        &mut self.rng
+    }
+}
 
-    let mut deck_ = deck.clone();
+/// ## Run games in chunks spread over threads
+/// The `nb_game` games are split in chunks of [NB_GAME_CHUNK] games.  
+/// Each chunk has its own sampler, whose random stream is seeded from `rng` in chunk order.  
+/// Each thread runs a contiguous range of chunks, and the results are returned in chunk order.  
+/// So they do not depend on the number of threads.
+pub(crate) fn calc_chunks<T, F>(
+    rng: &mut SmallRng,
+    deck: &[u32],
+    nb_rnd_cards: usize,
+    nb_game: u32,
+    n_thread: usize,
+    f: F,
+) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&mut Sampler, u32) -> T + Sync,
+{
+    let nb_chunk = nb_game.div_ceil(NB_GAME_CHUNK) as usize;
+    let chunks = (0..nb_chunk as u32)
+        .map(|c| (rng.gen::<u64>(), (nb_game - c * NB_GAME_CHUNK).min(NB_GAME_CHUNK)))
+        .collect::<Vec<(u64, u32)>>();
 
-    let mut rnd_cards = vec![0u32; nb_rnd_cards];
-    let mut rnd_table_cards = vec![0u32; 5 - nb_table_cards];
-    let mut rank = vec![0u32; nb_player as usize];
-    let mut cards = [0u32; 7];
+    let n_thread = n_thread.clamp(1, nb_chunk.max(1));
 
-    let mut rnd_state = 0usize;
-    let mut rnd_count = 0u32;
+    thread::scope(|s| {
+        let handles = (0..n_thread)
+            .map(|i| {
+                let (chunks_, f_) = (&chunks[i * nb_chunk / n_thread..(i + 1) * nb_chunk / n_thread], &f);
+                s.spawn(move || {
+                    chunks_
+                        .iter()
+                        .map(|(seed, n_game)| {
+                            let mut sampler = Sampler::new(deck.to_vec(), nb_rnd_cards, SmallRng::seed_from_u64(*seed));
+                            f_(&mut sampler, *n_game)
+                        })
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect::<Vec<T>>()
+    })
+}
 
-    let mut eqty = HandEquity { win: 0.0, tie: 0.0 };
+fn calc_eqty_batch(
+    t7: &TableSeven,
+    player_cards: &[Vec<u32>],
+    table: HandAccumulator,
+    hole: &[HandAccumulator],
+    sampler: &mut Sampler,
+    nb_game: u32,
+) -> Vec<EqtyMoments> {
+    let nb_table_cards = table.len();
 
-    // // debug
-    // let mut freq_rnd = (0..deck.len())
-    //     .into_iter()
-    //     .map(|x| (x as u32, 0.0))
-    //     .collect::<HashMap<u32, f64>>();
+    let mut rank = vec![0u32; player_cards.len()];
+    let mut moments = vec![EqtyMoments::default(); player_cards.len()];
 
     for _g in 0..nb_game {
-        draw_card(&mut rnd_cards, &mut deck_, &mut rnd_state, &mut rnd_count);
-
-        // // debug
-        // for x in rnd_cards.iter() {
-        //     match freq_rnd.get(x) {
-        //         Some(count) => freq_rnd.insert(*x, count + 1.0),
-        //         None => freq_rnd.insert(*x, 1.0),
-        //     };
-        // }
+        let rnd_cards = sampler.draw();
 
         let mut r = 0;
 
-        for i in 0..5 - nb_table_cards {
-            rnd_table_cards[i] = rnd_cards[i];
+        let mut table_ = table;
+        for _ in 0..5 - nb_table_cards {
+            table_.push(rnd_cards[r] as usize);
             r += 1;
         }
 
         for (p, player) in player_cards.iter().enumerate() {
-            if p == 0 {
-                cards[0] = player[0];
-                cards[1] = player[1];
-            } else {
-                match player.len() {
-                    2 => {
-                        cards[0] = player[0];
-                        cards[1] = player[1];
-                    }
-                    1 => {
-                        cards[0] = player[0];
-                        cards[1] = rnd_cards[r];
-                        r += 1;
-                    }
-                    _ => {
-                        cards[0] = rnd_cards[r];
-                        cards[1] = rnd_cards[r + 1];
-                        r += 2;
-                    }
-                }
+            let mut hand = hole[p] + table_;
+            for _ in player.len()..2 {
+                hand.push(rnd_cards[r] as usize);
+                r += 1;
             }
-            for (t, table) in table_cards.iter().enumerate() {
-                cards[2 + t] = *table;
-            }
-            for (i, rnd) in rnd_table_cards.iter().enumerate() {
-                cards[2 + nb_table_cards + i] = *rnd;
-            }
-
-            let cards_ = cards.map(|x| x as usize);
-            rank[p] = get_rank(&t7, cards_);
+            rank[p] = hand.rank_seven(t7);
         }
         assert_eq!(r, rnd_cards.len());
 
-        let mut max_rank = rank[0];
-        let mut nb_max = 1;
-        for p in 1..nb_player as usize {
-            if rank[p] > max_rank {
-                max_rank = rank[p];
-                nb_max = 1;
-            } else if rank[p] == max_rank {
-                nb_max += 1;
-            }
-        }
-
-        if rank[0] == max_rank {
-            if nb_max == 1 {
-                eqty.win += 1.0;
-            } else {
-                eqty.tie += 1.0 / nb_max as f64;
-            }
-        }
+        update_eqty_moments(&mut moments, &rank);
     }
 
-    eqty.win /= nb_game as f64;
-    eqty.tie /= nb_game as f64;
-
-    // // debug
-    // for (_, v) in freq_rnd.iter_mut() {
-    //     *v /= nb_game as f64;
-    //     *v *= 100.0;
-    // }
-    // println!("freq_rnd = {:?}", freq_rnd);
-
-    let _end = Instant::now();
-    // println!("runtime = {:?}", _end - _start);
-
-    eqty
+    moments
 }
 
-fn draw_card(rnd_card: &mut Vec<u32>, deck: &mut Vec<u32>, state: &mut usize, count: &mut u32) -> () {
-    for c in 0..rnd_card.len() {
-        rnd_card[c] = deck[*state];
-
-        *state += 1;
-        if *state == deck.len() {
-            *state = 0;
+/// ## Draw random cards from the deck
+/// Partial Fisher-Yates shuffle: each of the first `rnd_card.len()` cards of the deck is swapped with a uniformly drawn card among the remaining ones.  
+/// So each draw is a uniform sample of distinct cards, independent of the previous draws.  
+/// The deck is reordered in place, its content is unchanged.  
+/// Each 64-bit random number gives two indexes, one per 32-bit half, see [bounded].  
+pub(crate) fn draw_card<R: Rng>(rnd_card: &mut [u32], deck: &mut [u32], rng: &mut R) {
+    let n = deck.len() as u32;
+    let mut i = 0;
+    while i < rnd_card.len() {
+        let x = rng.next_u64();
+        for h in [x as u32, (x >> 32) as u32] {
+            if i == rnd_card.len() {
+                break;
+            }
+            if let Some(j) = bounded(h, n - i as u32) {
+                deck.swap(i, i + j as usize);
+                rnd_card[i] = deck[i];
+                i += 1;
+            }
         }
     }
-    *count += 1;
-    if *count % 100 == 0 {
-        let mut rng = rand::thread_rng();
-        deck.shuffle(&mut rng);
-    }
+}
 
-    ()
+/// ## Bounded random number
+/// Maps a uniform 32-bit number to a uniform number in `0..range`, with Lemire's multiply-shift.  
+/// The rare values that would bias the result are rejected, and `None` is returned.  
+fn bounded(x: u32, range: u32) -> Option<u32> {
+    let m = x as u64 * range as u64;
+    let low = m as u32;
+    if low < range && low < range.wrapping_neg() % range {
+        return None;
+    }
+    Some((m >> 32) as u32)
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::HandEquity;
+    use super::{calc_chunks, draw_card, McGameError, McStop, MAX_NB_GAME, NB_GAME_CHUNK};
     use crate::calc;
     use crate::eval::seven;
+    use rand::{rngs::SmallRng, SeedableRng};
+    use std::{collections::HashMap, time::Duration};
+
+    /// Chi-square statistic of counts against the uniform distribution
+    fn chi_square(count: &[u64]) -> f64 {
+        let expected = count.iter().sum::<u64>() as f64 / count.len() as f64;
+        count
+            .iter()
+            .map(|c| (*c as f64 - expected).powi(2) / expected)
+            .sum::<f64>()
+    }
 
     #[test]
-    fn calc_equity_mc() {
-        let arc_t7 = seven::build_tables(true);
-
-        let tests: Vec<(Vec<Vec<u32>>, Vec<u32>, u32, [f64; 2], f64)> = vec![
-            (
-                vec![vec![8, 9], vec![11, 28]],
-                vec![15, 47, 23, 33],
-                100_000_000,
-                [0.7502161, 0.0],
-                1e-3,
-            ),
-            (
-                vec![vec![8, 9], vec![11, 28]],
-                vec![15, 47, 23],
-                100_000_000,
-                [0.5231909, 0.0192552],
-                1e-3,
-            ),
-            (
-                vec![vec![8, 9], vec![11, 28], vec![]],
-                vec![15, 47, 23, 33],
-                100_000_000,
-                [0.3167, 0.0],
-                1e-3,
-            ),
-            (
-                vec![vec![8, 9], vec![11]],
-                vec![15, 47, 23, 33],
-                100_000_000,
-                [0.3934488, 0.0088431],
-                1e-3,
-            ),
-            (
-                vec![vec![8, 9], vec![11], vec![]],
-                vec![15, 47, 23, 33],
-                100_000_000,
-                [0.1678894, 0.003496],
-                1e-3,
-            ),
+    fn draw_card_uniform() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut deck = (0..10).collect::<Vec<u32>>();
+        let mut rnd_card = vec![0u32; 3];
+
+        // each set of 3 cards, as a bit mask
+        let mut count_set = HashMap::<u32, u64>::new();
+        // first card of a draw and first card of the next draw
+        let mut count_pair = vec![0u64; 100];
+        let mut prev = None;
+
+        for _ in 0..1_200_000 {
+            draw_card(&mut rnd_card, &mut deck, &mut rng);
+
+            let set = rnd_card.iter().fold(0u32, |acc, c| acc | 1 << c);
+            assert_eq!(set.count_ones(), 3, "-> fails: cards not distinct: {:?}", rnd_card);
+            *count_set.entry(set).or_insert(0) += 1;
+
+            if let Some(p) = prev {
+                count_pair[10 * p + rnd_card[0] as usize] += 1;
+            }
+            prev = Some(rnd_card[0] as usize);
+        }
+
+        let mut deck_ = deck.clone();
+        deck_.sort();
+        assert_eq!(deck_, (0..10).collect::<Vec<u32>>(), "-> fails: deck content changed");
+
+        // 120 sets - 119 degrees of freedom, 99.99% quantile ~ 185
+        assert_eq!(count_set.len(), 120);
+        let chi2 = chi_square(&count_set.values().copied().collect::<Vec<u64>>());
+        assert!(chi2 < 185.0, "-> fails: sets not uniform: chi2={}", chi2);
+
+        // 100 pairs - 99 degrees of freedom, 99.99% quantile ~ 160
+        let chi2 = chi_square(&count_pair);
+        assert!(
+            chi2 < 160.0,
+            "-> fails: consecutive draws not independent: chi2={}",
+            chi2
+        );
+    }
+
+    #[test]
+    fn calc_equity_mc_vs_det() {
+        let arc_t7 = seven::build_tables(false);
+
+        // spots enumerated exactly by calc_equity_det
+        let tests = [
+            (vec![[8, 29], [4, 11]], vec![], vec![]),
+            (vec![[8, 9], [11, 28]], vec![15, 47, 23], vec![]),
+            (vec![[8, 29], [4, 11]], vec![13, 14, 50], vec![0, 1, 2]),
+            (vec![[8, 9], [11, 28], [0, 51]], vec![15, 47, 23, 33], vec![]),
+            (vec![[48, 49], [50, 51], [44, 45]], vec![0, 4, 8], vec![]),
         ];
 
-        for (players, table, nb_game, result, precision) in tests.iter() {
-            let result_ = HandEquity {
-                win: result[0],
-                tie: result[1],
-            };
+        for (players, table, dead) in tests.iter() {
+            let equity_det =
+                calc::equity_det::calc_equity_det(arc_t7.clone(), players.clone(), table.clone(), dead.clone(), false)
+                    .unwrap();
+            let equity = calc::equity_mc::calc_equity_monte_carlo(
+                arc_t7.clone(),
+                players.iter().map(|p| p.to_vec()).collect(),
+                table.clone(),
+                dead.clone(),
+                McStop::nb_game(2_000_000),
+                Some(2024),
+            )
+            .unwrap();
+
+            // within 4 standard errors
+            for (e, d) in equity.equity.iter().zip(equity_det.iter()) {
+                assert!(
+                    (e.win - d.win).abs() <= 4.0 * e.win_std_err && (e.tie - d.tie).abs() <= 4.0 * e.tie_std_err,
+                    "-> fails: players={:?}, table={:?}, dead={:?}\nfound:equity={:?}, want:equity={:?}",
+                    players,
+                    table,
+                    dead,
+                    e,
+                    d
+                );
+            }
+        }
+    }
 
-            let equity =
-                calc::equity_mc::calc_equity_monte_carlo(arc_t7.clone(), players.clone(), table.clone(), *nb_game);
-            println!("equity = {:?}", equity);
+    #[test]
+    fn calc_equity_mc() {
+        let arc_t7 = seven::build_tables(false);
 
-            assert!(
-                equity.is_ok(),
-                "-> fails: players={:?}, table={:?}",
+        let tests: Vec<(Vec<Vec<u32>>, Vec<u32>)> = vec![
+            (vec![vec![8, 9], vec![11, 28]], vec![15, 47, 23, 33]),
+            (vec![vec![8, 9], vec![11, 28]], vec![15, 47, 23]),
+            (vec![vec![8, 9], vec![11, 28], vec![]], vec![15, 47, 23, 33]),
+            (vec![vec![8, 9], vec![11]], vec![15, 47, 23, 33]),
+            (vec![vec![8, 9], vec![11], vec![]], vec![15, 47, 23, 33]),
+        ];
+        let nb_game = 1_000_000;
+
+        for (players, table) in tests.iter() {
+            let want =
+                calc::equity_exact::calc_equity_exact(arc_t7.clone(), players.clone(), table.clone(), vec![], false)
+                    .unwrap();
+            let equity = calc::equity_mc::calc_equity_monte_carlo(
+                arc_t7.clone(),
                 players.clone(),
                 table.clone(),
-            );
+                vec![],
+                McStop::nb_game(nb_game),
+                Some(0),
+            )
+            .unwrap();
 
-            if let Ok(eqty) = equity {
+            assert_eq!(equity.nb_game, nb_game);
+            assert_eq!(equity.equity.len(), players.len());
+            for (e, w) in equity.equity.iter().zip(want.iter()) {
                 assert!(
-                    (eqty.win - result_.win).abs() < *precision,
+                    (e.win - w.win).abs() <= 4.0 * e.win_std_err && (e.tie - w.tie).abs() <= 4.0 * e.tie_std_err,
                     "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
                     players,
                     table,
-                    eqty,
-                    result_
+                    e,
+                    w
                 );
             }
+
+            // wins and shares of ties sum to 1 over all seats
+            let total = equity.equity.iter().map(|e| e.win + e.tie).sum::<f64>();
+            assert!(
+                (total - 1.0).abs() < 1e-9,
+                "-> fails: players={:?}, table={:?}\nfound:equity={:?}",
+                players,
+                table,
+                equity.equity
+            );
+        }
+    }
+
+    #[test]
+    fn calc_equity_mc_dead_cards() {
+        let arc_t7 = seven::build_tables(false);
+
+        let players = vec![vec![8, 9], vec![11, 28]];
+        let table = vec![15, 47, 23];
+        let dead = vec![0, 1, 12, 13, 14, 16, 17];
+
+        let equity_det = calc::equity_det::calc_equity_det(
+            arc_t7.clone(),
+            vec![[8, 9], [11, 28]],
+            table.clone(),
+            dead.clone(),
+            false,
+        )
+        .unwrap();
+        let equity = calc::equity_mc::calc_equity_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            table.clone(),
+            dead.clone(),
+            McStop::nb_game(10_000_000),
+            None,
+        );
+        assert!(
+            equity.is_ok(),
+            "-> fails: players={:?}, table={:?}, dead={:?}",
+            players,
+            table,
+            dead
+        );
+
+        for (e, d) in equity.unwrap().equity.iter().zip(equity_det.iter()) {
+            assert!(
+                (e.win - d.win).abs() < 1e-2 && (e.tie - d.tie).abs() < 1e-2,
+                "-> fails: players={:?}, table={:?}, dead={:?}\nfound:equity={:?}, want:equity={:?}",
+                players,
+                table,
+                dead,
+                e,
+                d
+            );
+        }
+
+        let equity = calc::equity_mc::calc_equity_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            table.clone(),
+            vec![8],
+            McStop::nb_game(1_000),
+            None,
+        );
+        assert!(
+            matches!(equity, Err(McGameError::NotDistinctCards(..))),
+            "-> fails: equity={:?}",
+            equity
+        );
+    }
+
+    #[test]
+    fn calc_equity_mc_seed() {
+        let arc_t7 = seven::build_tables(false);
+
+        let players = vec![vec![8, 9], vec![11], vec![]];
+        let table = vec![15, 47, 23];
+
+        let calc = |seed: Option<u64>| {
+            calc::equity_mc::calc_equity_monte_carlo(
+                arc_t7.clone(),
+                players.clone(),
+                table.clone(),
+                vec![],
+                McStop::nb_game(100_000),
+                seed,
+            )
+            .unwrap()
+            .equity
+        };
+
+        // same seed, bit-identical results
+        let eqty_a = calc(Some(42));
+        let eqty_b = calc(Some(42));
+        for (a, b) in eqty_a.iter().zip(eqty_b.iter()) {
+            assert!(
+                a.win.to_bits() == b.win.to_bits() && a.tie.to_bits() == b.tie.to_bits(),
+                "-> fails: seed=42\nfound:equity={:?}, want:equity={:?}",
+                eqty_b,
+                eqty_a
+            );
+        }
+
+        // other seed, other results
+        let eqty_c = calc(Some(43));
+        assert!(
+            eqty_a
+                .iter()
+                .zip(eqty_c.iter())
+                .any(|(a, c)| a.win != c.win || a.tie != c.tie),
+            "-> fails: seed=42 and seed=43 give the same equity={:?}",
+            eqty_a
+        );
+    }
+
+    #[test]
+    fn calc_chunks_n_thread() {
+        let deck = (0..52).collect::<Vec<u32>>();
+
+        // sum of the cards drawn in each chunk, 3 chunks and a partial one
+        let calc = |n_thread: usize| {
+            let mut rng = SmallRng::seed_from_u64(42);
+            calc_chunks(&mut rng, &deck, 7, 3 * NB_GAME_CHUNK + 10, n_thread, |sampler, n_game| {
+                (0..n_game)
+                    .map(|_| sampler.draw().iter().map(|c| *c as u64).sum::<u64>())
+                    .sum::<u64>()
+            })
+        };
+
+        let sums = calc(1);
+        assert_eq!(sums.len(), 4, "-> fails: nb chunks: {}", sums.len());
+        for n_thread in [2, 3, 8] {
+            assert_eq!(
+                calc(n_thread),
+                sums,
+                "-> fails: n_thread={} gives other results than n_thread=1",
+                n_thread
+            );
+        }
+    }
+
+    #[test]
+    fn calc_equity_mc_std_err() {
+        let arc_t7 = seven::build_tables(false);
+
+        let players = vec![vec![8, 9], vec![11, 28]];
+        let table = vec![15, 47, 23];
+
+        let equity_det =
+            calc::equity_det::calc_equity_det(arc_t7.clone(), vec![[8, 9], [11, 28]], table.clone(), vec![], false)
+                .unwrap();
+
+        // fixed number of games: the exact equity is within the error bars
+        let equity = calc::equity_mc::calc_equity_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            table.clone(),
+            vec![],
+            McStop::nb_game(1_000_000),
+            Some(7),
+        )
+        .unwrap();
+        assert_eq!(equity.nb_game, 1_000_000);
+
+        for (e, d) in equity.equity.iter().zip(equity_det.iter()) {
+            // binomial standard error of the win probability
+            let win_std_err = (e.win * (1.0 - e.win) / 1e6).sqrt();
+            assert!(
+                (e.win_std_err - win_std_err).abs() < 1e-6 * win_std_err.max(1e-9),
+                "-> fails: found:std_err={}, want:std_err={}",
+                e.win_std_err,
+                win_std_err
+            );
+            assert!(
+                (e.win - d.win).abs() < 4.0 * e.win_std_err && (e.tie - d.tie).abs() < 4.0 * e.tie_std_err,
+                "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                players,
+                table,
+                e,
+                d
+            );
+            assert!(
+                e.win_ci[0] < e.win && e.win < e.win_ci[1] && e.tie_ci[0] < e.tie && e.tie < e.tie_ci[1],
+                "-> fails: equity={:?}",
+                e
+            );
         }
+
+        // target standard error: stops before the maximum number of games
+        let stop = McStop {
+            nb_game: MAX_NB_GAME,
+            std_err: Some(1e-3),
+            time: None,
+        };
+        let equity = calc::equity_mc::calc_equity_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            table.clone(),
+            vec![],
+            stop,
+            None,
+        )
+        .unwrap();
+        assert!(
+            equity.nb_game < MAX_NB_GAME
+                && equity
+                    .equity
+                    .iter()
+                    .all(|e| e.win_std_err <= 1e-3 && e.tie_std_err <= 1e-3),
+            "-> fails: nb_game={}, equity={:?}",
+            equity.nb_game,
+            equity.equity
+        );
+
+        // time budget: stops before the maximum number of games
+        let stop = McStop {
+            nb_game: MAX_NB_GAME,
+            std_err: None,
+            time: Some(Duration::from_millis(200)),
+        };
+        let equity = calc::equity_mc::calc_equity_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            table.clone(),
+            vec![],
+            stop,
+            None,
+        )
+        .unwrap();
+        assert!(equity.nb_game < MAX_NB_GAME, "-> fails: nb_game={}", equity.nb_game);
+
+        // invalid stopping rules
+        let calc = |stop: McStop| {
+            calc::equity_mc::calc_equity_monte_carlo(arc_t7.clone(), players.clone(), table.clone(), vec![], stop, None)
+        };
+        let equity = calc(McStop::nb_game(0));
+        assert!(
+            matches!(equity, Err(McGameError::InvalidNbGame(0))),
+            "-> fails: equity={:?}",
+            equity
+        );
+        let equity = calc(McStop::nb_game(MAX_NB_GAME + 1));
+        assert!(
+            matches!(equity, Err(McGameError::InvalidNbGame(_))),
+            "-> fails: equity={:?}",
+            equity
+        );
+        let equity = calc(McStop {
+            nb_game: 1_000,
+            std_err: Some(0.0),
+            time: None,
+        });
+        assert!(
+            matches!(equity, Err(McGameError::InvalidStdErr(_))),
+            "-> fails: equity={:?}",
+            equity
+        );
     }
 }
@@ -0,0 +1,643 @@
+//! # Omaha equity calculation
+//! This module provides the functions to calculate the equity of Omaha hands, PLO4 or PLO5, based on module [omaha](crate::eval::omaha):
+//! - [calc_equity_omaha_det]: deterministically, i.e. exhaustively, knowing all player cards
+//! - [calc_equity_omaha_monte_carlo]: in monte carlo mode, knowing some player cards
+//!
+//! The number of hole cards, 4 or 5, is given by the first player.
+//! The deck is the one of the tables variant, e.g. 36 cards for short-deck.
+//! Both return the equity of each player.
+
+//!   # Example
+//!
+//! ```
+//! use std::sync::Arc;
+//! use poker_eval::eval::five::build_tables;
+//! use poker_eval::calc::equity_omaha::{calc_equity_omaha_det, calc_equity_omaha_monte_carlo};
+//!
+//! // you need create Arc<TableFive> arc_t5 beforehand once
+//! let arc_t5 = Arc::new(build_tables(false));
+//!
+//! // then you can call calc_equity_omaha_det multiple times
+//! let equity = calc_equity_omaha_det(
+//!     // clone of Arc<TableFive>
+//!     arc_t5.clone(),
+//!     // players cards: As Ad Kh Qh vs Jc Tc 9d 8d
+//!     vec![vec![51, 49, 46, 42], vec![36, 32, 29, 25]],
+//!     // table cards: Ts 7h 2c
+//!     vec![35, 22, 0],
+//!     // verbose
+//!     false,
+//! );
+//! println!("equity = {:?}", equity);
+//!
+//! // or calc_equity_omaha_monte_carlo with partially known cards
+//! let equity = calc_equity_omaha_monte_carlo(
+//!     arc_t5.clone(),
+//!     vec![vec![51, 49, 46, 42], vec![36, 32], vec![]],
+//!     vec![35, 22, 0],
+//!     // number of game
+//!     1_000_000,
+//!     // seed, None for a random one
+//!     Some(42),
+//! );
+//! println!("equity = {:?}", equity);
+//! ```
+
+use rand::{rngs::SmallRng, SeedableRng};
+use std::{collections::HashSet, sync::Arc, thread, time::Instant};
+
+use thiserror::Error;
+
+use super::equity_det::{update_eqty, HandEquity};
+use super::equity_mc::{calc_chunks, Sampler, MAX_NB_GAME};
+use crate::{
+    eval::{
+        five::TableFive,
+        omaha::{OmahaBoard, OmahaHole},
+    },
+    keys::{Variant, DECK_SIZE},
+};
+
+/// ## Omaha game description error
+/// This error type is used to describe the errors that can occur when describing an Omaha game.
+#[derive(Error, Debug)]
+pub enum OmahaGameError {
+    // player
+    /// Invalid number of players
+    #[error("invalid nb players: {0} - must be between 2 and {1}")]
+    InvalidNbPlayer(u32, u32),
+    /// Invalid first player
+    #[error("invalid first player: {0:?} - 4 or 5 cards must be provided")]
+    InvalidFirstPlayer(Vec<u32>),
+    /// Invalid other player
+    #[error("invalid other player {0}: {1:?} - as many cards as the first player must be provided, or fewer in monte carlo mode")]
+    InvalidOtherPlayer(u32, Vec<u32>),
+    /// Invalid player card
+    #[error("invalid player card: {1} for player {0} - must be between 0 and 51")]
+    InvalidPlayerCard(u32, u32),
+    // table
+    /// Invalid number of table cards
+    #[error("invalid nb table cards: {0} - must be among 0, 3, 4 or 5")]
+    InvalidNbTableCard(u32),
+    /// Invalid table card
+    #[error("invalid table card: {0} - must be between 0 and 51")]
+    InvalidTableCard(u32),
+    // both
+    /// Card not in the deck of the variant, e.g. a 2 in short-deck
+    #[error("invalid card: {0} - not in the {1} deck")]
+    CardNotInDeck(u32, Variant),
+    /// Not distinct cards
+    #[error("players: {0:?} table: {1:?} - all cards must be distinct")]
+    NotDistinctCards(Vec<Vec<u32>>, Vec<u32>),
+    // monte carlo
+    /// Invalid number of games
+    #[error("invalid nb games: {0} - must be between 1 and 1000000000")]
+    InvalidNbGame(u32),
+}
+
+/// ## Calculate equity of Omaha hands deterministically ie. exhaustively
+/// This requires knowing all players cards: 4 or 5 each, same for all players.
+pub fn calc_equity_omaha_det(
+    t5: Arc<TableFive>,
+    player_cards: Vec<Vec<u32>>,
+    table_cards: Vec<u32>,
+    verbose: bool,
+) -> Result<Vec<HandEquity>, OmahaGameError> {
+    let start = Instant::now();
+
+    check_game(&player_cards, &table_cards, t5.variant, false)?;
+
+    let nb_player = player_cards.len();
+    let nb_table_card = table_cards.len();
+    let deck = build_deck(&player_cards, &table_cards, t5.variant);
+
+    let hole = player_cards
+        .iter()
+        .map(|p| OmahaHole::from_cards(&to_usize(p)))
+        .collect::<Vec<OmahaHole>>();
+    let table = to_usize(&table_cards);
+
+    let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
+    let mut n_game = 0;
+
+    if nb_table_card == 0 {
+        // zero table cards: one thread per first card
+        let hole_ = Arc::new(hole);
+        let deck_ = Arc::new(deck);
+        let mut handles = vec![];
+
+        for i1 in 0..deck_.len() {
+            let t5_ = Arc::clone(&t5);
+            let hole_ = Arc::clone(&hole_);
+            let deck_ = Arc::clone(&deck_);
+
+            let handle = thread::spawn(move || calc_eqty_runouts(&t5_, &hole_, &[deck_[i1]], &deck_[0..i1], 4));
+            handles.push(handle);
+        }
+
+        for handle in handles {
+            let (eqty_, n_game_) = handle.join().unwrap();
+            for p in 0..nb_player {
+                eqty[p].win += eqty_[p].win;
+                eqty[p].tie += eqty_[p].tie;
+            }
+            n_game += n_game_;
+        }
+    } else {
+        // 3, 4 or 5 table cards
+        (eqty, n_game) = calc_eqty_runouts(&t5, &hole, &table, &deck, 5 - nb_table_card);
+    }
+
+    let equity = eqty
+        .iter()
+        .map(|e| HandEquity {
+            win: e.win / (n_game as f64),
+            tie: e.tie / (n_game as f64),
+        })
+        .collect::<Vec<HandEquity>>();
+
+    let end = Instant::now();
+
+    if verbose {
+        println!("calc_equity_omaha_det runtime: {:?}", end - start);
+    }
+
+    Ok(equity)
+}
+
+/// ## Calculate equity of Omaha hands in monte carlo mode
+/// This does not require knowing all players cards:
+/// the first player must have 4 or 5 cards, the other players as many or fewer.  
+/// The games are drawn in chunks spread over the threads, as in [calc_equity_monte_carlo](super::equity_mc::calc_equity_monte_carlo),
+/// so the same `seed` gives identical results on any machine.
+pub fn calc_equity_omaha_monte_carlo(
+    t5: Arc<TableFive>,
+    player_cards: Vec<Vec<u32>>,
+    table_cards: Vec<u32>,
+    nb_game: u32,
+    seed: Option<u64>,
+) -> Result<Vec<HandEquity>, OmahaGameError> {
+    check_game(&player_cards, &table_cards, t5.variant, true)?;
+    match nb_game {
+        1..=MAX_NB_GAME => (),
+        _ => return Err(OmahaGameError::InvalidNbGame(nb_game)),
+    }
+
+    let deck = build_deck(&player_cards, &table_cards, t5.variant)
+        .iter()
+        .map(|c| *c as u32)
+        .collect::<Vec<u32>>();
+
+    let nb_hole = player_cards[0].len();
+    let nb_player_cards = player_cards.iter().map(|p| p.len()).sum::<usize>();
+    let nb_rnd_cards = nb_hole * player_cards.len() - nb_player_cards + (5 - table_cards.len());
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+    let mut rng = match seed {
+        Some(x) => SmallRng::seed_from_u64(x),
+        None => SmallRng::from_entropy(),
+    };
+
+    let arr_eqty = calc_chunks(&mut rng, &deck, nb_rnd_cards, nb_game, n_thread, |sampler, n_game| {
+        calc_eqty_batch(&t5, &player_cards, &table_cards, sampler, n_game)
+    });
+
+    // win and tie counts over all games
+    let mut equity = vec![HandEquity { win: 0.0, tie: 0.0 }; player_cards.len()];
+    for eqty in arr_eqty.iter() {
+        for (e, e_) in equity.iter_mut().zip(eqty.iter()) {
+            *e += *e_;
+        }
+    }
+    for e in equity.iter_mut() {
+        e.win /= nb_game as f64;
+        e.tie /= nb_game as f64;
+    }
+
+    Ok(equity)
+}
+
+/// Check game description - in monte carlo mode, other players may have fewer cards
+pub(crate) fn check_game(
+    player_cards: &[Vec<u32>],
+    table_cards: &[u32],
+    variant: Variant,
+    monte_carlo: bool,
+) -> Result<(), OmahaGameError> {
+    let deck_size = DECK_SIZE as u32;
+    let nb_player = player_cards.len() as u32;
+
+    // start check input
+    let nb_hole = match player_cards.first() {
+        Some(p) if (p.len() == 4 || p.len() == 5) => p.len(),
+        Some(p) => return Err(OmahaGameError::InvalidFirstPlayer(p.clone())),
+        None => return Err(OmahaGameError::InvalidNbPlayer(nb_player, 10)),
+    };
+
+    // enough cards for all players and the table
+    let max_nb_player = ((variant.deck_size() as u32 - 5) / nb_hole as u32).min(10);
+    match nb_player {
+        x if (2..=max_nb_player).contains(&x) => (),
+        _ => return Err(OmahaGameError::InvalidNbPlayer(nb_player, max_nb_player)),
+    }
+
+    for (i, p) in player_cards.iter().enumerate().skip(1) {
+        match p.len() {
+            x if x == nb_hole => (),
+            x if monte_carlo && x < nb_hole => (),
+            _ => return Err(OmahaGameError::InvalidOtherPlayer(i as u32, p.clone())),
+        }
+    }
+
+    for (i, p) in player_cards.iter().enumerate() {
+        for c in p.iter() {
+            match *c {
+                x if (x < deck_size) => (),
+                _ => return Err(OmahaGameError::InvalidPlayerCard(i as u32, *c)),
+            }
+        }
+    }
+
+    let nb_table_card = table_cards.len();
+    match nb_table_card {
+        0 | 3 | 4 | 5 => (),
+        _ => return Err(OmahaGameError::InvalidNbTableCard(nb_table_card as u32)),
+    }
+    for t in table_cards.iter() {
+        match *t {
+            x if (x < deck_size) => (),
+            _ => return Err(OmahaGameError::InvalidTableCard(*t)),
+        }
+    }
+
+    let all_cards = player_cards.iter().flatten().chain(table_cards.iter());
+    if let Some(c) = all_cards.clone().find(|c| !variant.contains(**c as usize)) {
+        return Err(OmahaGameError::CardNotInDeck(*c, variant));
+    }
+
+    let all_cards_set = all_cards.collect::<HashSet<&u32>>();
+    let nb_card = player_cards.iter().map(|p| p.len()).sum::<usize>() + nb_table_card;
+
+    match nb_card == all_cards_set.len() {
+        true => Ok(()),
+        false => Err(OmahaGameError::NotDistinctCards(player_cards.to_vec(), table_cards.to_vec())),
+    }
+    // end check input
+}
+
+/// Cards of the variant deck not in player or table cards
+pub(crate) fn build_deck(player_cards: &[Vec<u32>], table_cards: &[u32], variant: Variant) -> Vec<usize> {
+    let all_cards_set = player_cards
+        .iter()
+        .flatten()
+        .chain(table_cards.iter())
+        .map(|c| *c as usize)
+        .collect::<HashSet<usize>>();
+
+    variant
+        .deck()
+        .into_iter()
+        .filter(|c| !all_cards_set.contains(c))
+        .collect::<Vec<usize>>()
+}
+
+pub(crate) fn to_usize(cards: &[u32]) -> Vec<usize> {
+    cards.iter().map(|c| *c as usize).collect()
+}
+
+/// Equity sums and number of games, for all boards made of `prefix` and `k` cards among `deck`
+fn calc_eqty_runouts(
+    t5: &TableFive,
+    hole: &[OmahaHole],
+    prefix: &[usize],
+    deck: &[usize],
+    k: usize,
+) -> (Vec<HandEquity>, u32) {
+    let nb_player = hole.len();
+
+    let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
+    let mut rank = vec![0; nb_player];
+
+    let n_game = for_each_runout(prefix, deck, k, |board_| {
+        for p in 0..nb_player {
+            rank[p] = hole[p].rank(t5, board_);
+        }
+        update_eqty(&mut eqty, &rank);
+    });
+
+    (eqty, n_game)
+}
+
+/// Call `f` for all boards made of `prefix` and `k` cards among `deck` - returns the number of boards
+pub(crate) fn for_each_runout(prefix: &[usize], deck: &[usize], k: usize, mut f: impl FnMut(&OmahaBoard)) -> u32 {
+    let nb_deck = deck.len();
+    let mut n_game = 0;
+
+    if k > nb_deck {
+        return n_game;
+    }
+
+    let mut board = prefix.to_vec();
+    board.resize(prefix.len() + k, 0);
+
+    // idx = indices in deck of the k cards, increasing
+    let mut idx = (0..k).collect::<Vec<usize>>();
+
+    loop {
+        for (j, i) in idx.iter().enumerate() {
+            board[prefix.len() + j] = deck[*i];
+        }
+        f(&OmahaBoard::from_cards(&board));
+        n_game += 1;
+
+        // next combination
+        let mut j = k;
+        while j > 0 && idx[j - 1] == nb_deck - k + j - 1 {
+            j -= 1;
+        }
+        if j == 0 {
+            break;
+        }
+        idx[j - 1] += 1;
+        for l in j..k {
+            idx[l] = idx[l - 1] + 1;
+        }
+    }
+
+    n_game
+}
+
+/// Win and tie counts of each player over `nb_game` random games
+fn calc_eqty_batch(
+    t5: &TableFive,
+    player_cards: &[Vec<u32>],
+    table_cards: &[u32],
+    sampler: &mut Sampler,
+    nb_game: u32,
+) -> Vec<HandEquity> {
+    let nb_player = player_cards.len();
+    let nb_hole = player_cards[0].len();
+    let nb_table_cards = table_cards.len();
+
+    // players with all cards known are built once
+    let hole = player_cards
+        .iter()
+        .map(|p| match p.len() == nb_hole {
+            true => Some(OmahaHole::from_cards(&to_usize(p))),
+            false => None,
+        })
+        .collect::<Vec<Option<OmahaHole>>>();
+
+    let mut board = to_usize(table_cards);
+    board.resize(5, 0);
+    let mut cards = Vec::with_capacity(nb_hole);
+
+    let mut eqty = vec![HandEquity { win: 0.0, tie: 0.0 }; nb_player];
+    let mut rank = vec![0; nb_player];
+
+    for _g in 0..nb_game {
+        let rnd_cards = sampler.draw();
+
+        let mut r = 0;
+        for c in board[nb_table_cards..].iter_mut() {
+            *c = rnd_cards[r] as usize;
+            r += 1;
+        }
+        let board_ = OmahaBoard::from_cards(&board);
+
+        for (p, player) in player_cards.iter().enumerate() {
+            rank[p] = match hole[p] {
+                Some(hole_) => hole_.rank(t5, &board_),
+                None => {
+                    cards.clear();
+                    cards.extend(player.iter().map(|c| *c as usize));
+                    while cards.len() < nb_hole {
+                        cards.push(rnd_cards[r] as usize);
+                        r += 1;
+                    }
+                    OmahaHole::from_cards(&cards).rank(t5, &board_)
+                }
+            };
+        }
+        debug_assert_eq!(r, rnd_cards.len());
+
+        update_eqty(&mut eqty, &rank);
+    }
+
+    eqty
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use super::{calc_equity_omaha_det, calc_equity_omaha_monte_carlo, OmahaGameError};
+    use crate::calc::equity_mc::MAX_NB_GAME;
+    use crate::eval::five::{build_tables, get_rank_five, TableFive};
+
+    /// Best rank over all 2 hole cards and 3 board cards, with get_rank_five
+    fn rank_brute_force(t5: &TableFive, hole: &[u32], board: &[u32]) -> u32 {
+        let mut max_rank = 0;
+        for h1 in 0..hole.len() {
+            for h2 in 0..h1 {
+                for b1 in 0..board.len() {
+                    for b2 in 0..b1 {
+                        for b3 in 0..b2 {
+                            let c = [hole[h1], hole[h2], board[b1], board[b2], board[b3]].map(|c| c as usize);
+                            max_rank = max_rank.max(get_rank_five(t5, c));
+                        }
+                    }
+                }
+            }
+        }
+        max_rank
+    }
+
+    /// Equity on the turn, by brute force over the river cards
+    fn equity_turn_brute_force(t5: &TableFive, players: &[Vec<u32>], table: &[u32]) -> Vec<[f64; 2]> {
+        let mut eqty = vec![[0.0; 2]; players.len()];
+        let mut n_game = 0.0;
+        for river in (0..52).filter(|c| !table.contains(c) && !players.iter().any(|p| p.contains(c))) {
+            let board = [table, &[river]].concat();
+            let rank = players
+                .iter()
+                .map(|p| rank_brute_force(t5, p, &board))
+                .collect::<Vec<u32>>();
+            let max_rank = *rank.iter().max().unwrap();
+            let nb_max = rank.iter().filter(|r| **r == max_rank).count();
+            for (p, r) in rank.iter().enumerate() {
+                if *r == max_rank {
+                    match nb_max {
+                        1 => eqty[p][0] += 1.0,
+                        _ => eqty[p][1] += 1.0 / nb_max as f64,
+                    }
+                }
+            }
+            n_game += 1.0;
+        }
+        eqty.iter().map(|[w, t]| [w / n_game, t / n_game]).collect()
+    }
+
+    #[test]
+    fn calc_equity_omaha_det_turn() {
+        let arc_t5 = Arc::new(build_tables(false));
+
+        let tests: Vec<(Vec<Vec<u32>>, Vec<u32>)> = vec![
+            // PLO4: As Ad Kh Qh vs Jc Tc 9d 8d on Ts 7h 2c 3h
+            (vec![vec![51, 49, 46, 42], vec![36, 32, 29, 25]], vec![35, 22, 0, 6]),
+            // PLO4: 3 players
+            (
+                vec![vec![8, 9, 44, 45], vec![12, 16, 20, 24], vec![50, 51, 2, 3]],
+                vec![13, 14, 30, 47],
+            ),
+            // PLO5: 2 players
+            (vec![vec![51, 49, 46, 42, 1], vec![36, 32, 29, 25, 4]], vec![35, 22, 0, 6]),
+        ];
+
+        for (players, table) in tests.iter() {
+            let results = equity_turn_brute_force(&arc_t5, players, table);
+
+            let equity = calc_equity_omaha_det(arc_t5.clone(), players.clone(), table.clone(), false);
+            assert!(
+                equity.is_ok(),
+                "-> fails: players={:?}, table={:?}",
+                players.clone(),
+                table.clone(),
+            );
+
+            let precision = 1e-9;
+            if let Ok(equity) = equity {
+                for (e, [w, t]) in equity.iter().zip(results.iter()) {
+                    assert!(
+                        (e.win - w).abs() < precision && (e.tie - t).abs() < precision,
+                        "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                        players,
+                        table,
+                        e,
+                        [w, t]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn calc_equity_omaha_mc() {
+        let arc_t5 = Arc::new(build_tables(false));
+
+        let tests: Vec<(Vec<Vec<u32>>, Vec<u32>)> = vec![
+            // PLO4 on the flop
+            (vec![vec![51, 49, 46, 42], vec![36, 32, 29, 25]], vec![35, 22, 0]),
+            // PLO5 on the flop, 3 players
+            (
+                vec![vec![51, 49, 46, 42, 1], vec![36, 32, 29, 25, 4], vec![8, 9, 44, 45, 5]],
+                vec![35, 22, 0],
+            ),
+        ];
+
+        for (players, table) in tests.iter() {
+            let equity_det = calc_equity_omaha_det(arc_t5.clone(), players.clone(), table.clone(), false).unwrap();
+            let equity_mc =
+                calc_equity_omaha_monte_carlo(arc_t5.clone(), players.clone(), table.clone(), 1_000_000, None)
+                    .unwrap();
+
+            let sum = equity_det.iter().map(|e| e.win + e.tie).sum::<f64>();
+            assert!((sum - 1.0).abs() < 1e-9, "-> fails: sum={}", sum);
+
+            let precision = 1e-2;
+            for (e_mc, e_det) in equity_mc.iter().zip(equity_det.iter()) {
+                assert!(
+                    (e_mc.win - e_det.win).abs() < precision && (e_mc.tie - e_det.tie).abs() < precision,
+                    "-> fails: players={:?}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                    players,
+                    table,
+                    e_mc,
+                    e_det
+                );
+            }
+        }
+
+        // unknown cards: symmetric players
+        let equity =
+            calc_equity_omaha_monte_carlo(arc_t5.clone(), vec![vec![0, 1, 2, 3], vec![]], vec![], 1_000_000, None);
+        assert!(equity.is_ok());
+        if let Ok(equity) = equity {
+            assert!(equity[1].win > equity[0].win, "-> fails: equity={:?}", equity);
+        }
+
+        // same seed, same results
+        let (players, table) = (vec![vec![51, 49, 46, 42], vec![36, 32], vec![]], vec![35, 22, 0]);
+        let equity_a =
+            calc_equity_omaha_monte_carlo(arc_t5.clone(), players.clone(), table.clone(), 100_000, Some(42)).unwrap();
+        let equity_b = calc_equity_omaha_monte_carlo(arc_t5.clone(), players, table, 100_000, Some(42)).unwrap();
+        assert!(
+            equity_a
+                .iter()
+                .zip(equity_b.iter())
+                .all(|(a, b)| a.win == b.win && a.tie == b.tie),
+            "-> fails: seed=42\nfound:equity={:?}, want:equity={:?}",
+            equity_b,
+            equity_a
+        );
+    }
+
+    #[test]
+    fn calc_equity_omaha_invalid() {
+        let arc_t5 = Arc::new(build_tables(false));
+        let det = |players: Vec<Vec<u32>>, table: Vec<u32>| calc_equity_omaha_det(arc_t5.clone(), players, table, false);
+
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3]], vec![]),
+            Err(OmahaGameError::InvalidNbPlayer(1, 10))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3, 4]; 10], vec![]),
+            Err(OmahaGameError::InvalidNbPlayer(10, 9))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2], vec![4, 5, 6, 7]], vec![]),
+            Err(OmahaGameError::InvalidFirstPlayer(_))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3], vec![4, 5, 6]], vec![]),
+            Err(OmahaGameError::InvalidOtherPlayer(1, _))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3], vec![4, 5, 6, 52]], vec![]),
+            Err(OmahaGameError::InvalidPlayerCard(1, 52))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]], vec![8, 9]),
+            Err(OmahaGameError::InvalidNbTableCard(2))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]], vec![8, 9, 60]),
+            Err(OmahaGameError::InvalidTableCard(60))
+        ));
+        assert!(matches!(
+            det(vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]], vec![8, 9, 3]),
+            Err(OmahaGameError::NotDistinctCards(_, _))
+        ));
+
+        // fewer cards only in monte carlo mode, at least one game
+        let mc = |nb_game: u32| {
+            calc_equity_omaha_monte_carlo(arc_t5.clone(), vec![vec![0, 1, 2, 3], vec![4, 5]], vec![], nb_game, None)
+        };
+        assert!(matches!(mc(0), Err(OmahaGameError::InvalidNbGame(0))));
+        assert!(matches!(
+            mc(MAX_NB_GAME + 1),
+            Err(OmahaGameError::InvalidNbGame(x)) if x == MAX_NB_GAME + 1
+        ));
+        for nb_game in [1, 3, 100] {
+            let equity = mc(nb_game).unwrap();
+            let sum = equity.iter().map(|e| e.win + e.tie).sum::<f64>();
+            assert!(
+                equity.iter().all(|e| e.win.is_finite() && e.tie.is_finite()) && (sum - 1.0).abs() < 1e-9,
+                "-> fails: nb_game={}\nfound:equity={:?}",
+                nb_game,
+                equity
+            );
+        }
+    }
+}
@@ -0,0 +1,946 @@
+//! # Range equity calculation
+//! This module provides the function to calculate the equity of hand ranges, see [Range], against each other.
+//! A hand is a range of one combo, e.g. `"AhKh"`, so this also covers hand vs range.
+//!
+//! The calculation depends on the street:
+//! + on the flop, turn or river, all combos of the ranges and all completions of the board are enumerated, unless there are too many games
+//! + preflop, or if there are too many games to enumerate, the combos and the board are sampled in monte carlo mode, the combos in proportion to their weights
+
+//!   # Example
+//!
+//! ```
+//! use poker_eval::eval::seven::build_tables;
+//! use poker_eval::calc::equity_mc::McStop;
+//! use poker_eval::calc::equity_range::calc_equity_range;
+//! use poker_eval::range::Range;
+//!
+//! // you need create Arc<TableSeven> arc_t7 beforehand once
+//! let arc_t7 = build_tables(true);
+//!
+//! let hero: Range = "AhKh".parse().unwrap();
+//! let villain: Range = "QQ+, AKs, AQo:0.5".parse().unwrap();
+//!
+//! // then you can call calc_equity_range multiple times
+//! let equity = calc_equity_range(
+//!     // clone of Arc<TableSeven>
+//!     arc_t7.clone(),
+//!     // ranges, the hero first
+//!     vec![hero, villain],
+//!     // table cards
+//!     vec![51, 30, 41],
+//!     // dead cards
+//!     vec![],
+//!     // number of games if simulated - unused if enumerated
+//!     McStop::nb_game(1_000_000),
+//!     // seed if simulated, None for a random one
+//!     None,
+//! )
+//! .unwrap();
+//! println!("equity = {:?}", equity.equity);
+//! // equity = [HandEquity { win: 0.31409749670619236, tie: 0.08355292050944226 }, HandEquity { win: 0.5187966622749232, tie: 0.08355292050944226 }]
+//! println!("hero combos = {:?}", equity.hero);
+//! ```
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::{collections::HashSet, sync::Arc, thread, time::Instant};
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::equity_det::HandEquity;
+use super::equity_exact::canonical_combinations;
+use super::equity_mc::{calc_chunks, EqtyMoments, McHandEquity, McStop, Sampler, MAX_NB_GAME, NB_GAME_ROUND};
+use crate::{
+    eval::{accumulator::HandAccumulator, seven::TableSeven},
+    keys::{Variant, DECK_SIZE},
+    range::Range,
+};
+
+/// Maximum number of games of an enumeration, as estimated beforehand, above which the games are simulated
+pub const MAX_NB_GAME_EXACT: f64 = 1e8;
+
+/// Maximum number of draws of the combos of a simulated game, until they have no common card
+pub const MAX_NB_DRAW: u32 = 10_000;
+
+/// ## Combo equity
+/// Equity of a combo of the hero range against the other ranges.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ComboEquity {
+    /// Combo, highest card first
+    pub combo: [u32; 2],
+    /// Probability of the combo, given its weight, the other ranges and the known cards - between 0 and 1
+    pub freq: f64,
+    /// Equity of the combo
+    pub equity: HandEquity,
+}
+
+/// ## Range equity
+/// Equity of each range, and of each combo of the hero range, the first one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RangeEquity {
+    /// Whether all games were enumerated, else they were simulated
+    pub exact: bool,
+    /// Number of games enumerated or simulated
+    pub nb_game: u64,
+    /// Equity of each range
+    pub equity: Vec<HandEquity>,
+    /// Equity of each combo of the hero range with a positive probability, in card number order
+    pub hero: Vec<ComboEquity>,
+}
+
+/// ## Game description error
+/// This error type is used to describe the errors that can occur when describing a range game.
+#[derive(Error, Debug)]
+pub enum RangeGameError {
+    // range
+    /// invalid number of ranges
+    #[error("invalid nb ranges: {0} - must be between 2 and 10")]
+    InvalidNbRange(u32),
+    /// range without any combo
+    #[error("empty range {0}: {1} - at least one combo with a positive weight, without table and dead cards, must be provided")]
+    EmptyRange(u32, String),
+    /// ranges without any combos with distinct cards
+    #[error("ranges: {0:?} - at least one combo per range, all cards distinct, must be possible")]
+    NoDistinctCombos(Vec<String>),
+    /// ranges whose combos have common cards in almost all draws
+    #[error("ranges: {0:?} - combos with distinct cards must be drawn within 10000 draws")]
+    ConflictingCombos(Vec<String>),
+    // table
+    /// invalid number of table cards
+    #[error("invalid nb table cards: {0} - must be among 0, 3, 4 or 5")]
+    InvalidNbTableCard(u32),
+    /// invalid table card
+    #[error("invalid table card: {0} - must be between 0 and 51")]
+    InvalidTableCard(u32),
+    // dead
+    /// invalid dead card
+    #[error("invalid dead card: {0} - must be between 0 and 51")]
+    InvalidDeadCard(u32),
+    // all
+    /// card not in the deck of the variant, e.g. a 2 in short-deck
+    #[error("invalid card: {0} - not in the {1} deck")]
+    CardNotInDeck(u32, Variant),
+    /// not distinct cards
+    #[error("table: {0:?} dead: {1:?} - all cards must be distinct")]
+    NotDistinctCards(Vec<u32>, Vec<u32>),
+    /// not enough cards left in the deck
+    #[error("not enough cards: {0} to deal - {1} left in the deck")]
+    NotEnoughCards(u32, u32),
+    // stop
+    /// invalid number of games
+    #[error("invalid nb games: {0} - must be between 1 and 1000000000")]
+    InvalidNbGame(u32),
+    /// invalid target standard error
+    #[error("invalid std err: {0} - must be positive")]
+    InvalidStdErr(f64),
+}
+
+/// ## Calculate equity of ranges
+/// The first range is the hero one, a single hand being a range of one combo.
+/// The players combos are drawn together, each in proportion to its weight, provided they have no common card.
+/// Then the board is completed from the rest of the deck.
+/// The combos holding table or dead cards, or cards not in the deck of the tables variant, are ignored, as are those with a null weight.
+///
+/// The equity of each range and of each combo of the hero range is returned:
+/// + on the flop, turn or river, all combos and all board completions are enumerated, so the equity is exact
+/// + preflop, the games are simulated until the stopping rule [McStop] is met, the random streams being derived from `seed` if any, as in [calc_equity_monte_carlo](super::equity_mc::calc_equity_monte_carlo)
+///
+/// The enumeration takes about the number of board completions times the number of combos tuples, e.g. a second for two ranges of 270 combos on the flop, on one core.
+/// Above [MAX_NB_GAME_EXACT] games, e.g. three random hands on the flop, the games are simulated as preflop.
+/// The combos of a simulated game are drawn again while they have common cards, up to [MAX_NB_DRAW] times, else [RangeGameError::ConflictingCombos] is returned.
+pub fn calc_equity_range(
+    t7: Arc<TableSeven>,
+    ranges: Vec<Range>,
+    table_cards: Vec<u32>,
+    dead_cards: Vec<u32>,
+    stop: McStop,
+    seed: Option<u64>,
+) -> Result<RangeEquity, RangeGameError> {
+    // start check input
+    let (deck, combos) = check_range_game(t7.t5.variant, &ranges, &table_cards, &dead_cards)?;
+    // end check input
+
+    let table = HandAccumulator::from_cards(&table_cards.iter().map(|x| *x as usize).collect::<Vec<usize>>());
+
+    // enumerated on the flop, turn or river, unless too many games
+    let exact =
+        !table_cards.is_empty() && nb_game_exact(&combos, deck.len(), 5 - table_cards.len()) <= MAX_NB_GAME_EXACT;
+
+    let sums = match exact {
+        true => calc_range_exact(&t7, &combos, table, &deck),
+        false => {
+            match stop.nb_game {
+                1..=MAX_NB_GAME => (),
+                _ => return Err(RangeGameError::InvalidNbGame(stop.nb_game)),
+            }
+            match stop.std_err {
+                Some(x) if x.is_nan() || x <= 0.0 => return Err(RangeGameError::InvalidStdErr(x)),
+                _ => (),
+            }
+            calc_range_monte_carlo(&t7, &combos, table, deck, stop, seed).ok_or_else(|| {
+                RangeGameError::ConflictingCombos(ranges.iter().map(|r| r.to_string()).collect())
+            })?
+        }
+    };
+
+    let equity = sums
+        .moments
+        .iter()
+        .map(|m| HandEquity {
+            win: m.win / sums.weight,
+            tie: m.tie / sums.weight,
+        })
+        .collect::<Vec<HandEquity>>();
+
+    let hero = combos[0]
+        .iter()
+        .zip(sums.hero.iter().zip(sums.hero_weight.iter()))
+        .filter(|(_, (_, w))| **w > 0.0)
+        .map(|(c, (e, w))| ComboEquity {
+            combo: c.cards,
+            freq: w / sums.weight,
+            equity: HandEquity {
+                win: e.win / w,
+                tie: e.tie / w,
+            },
+        })
+        .collect::<Vec<ComboEquity>>();
+
+    Ok(RangeEquity {
+        exact,
+        nb_game: sums.nb_game,
+        equity,
+        hero,
+    })
+}
+
+/// ## Combo of a range
+/// Its cards, as a bit mask and as a partial hand, and its weight.
+#[derive(Debug, Copy, Clone)]
+struct Combo {
+    cards: [u32; 2],
+    mask: u64,
+    hole: HandAccumulator,
+    weight: f64,
+}
+
+/// ## Check a range game description
+/// Returns the deck of the variant without the table and dead cards, and the combos of each range that can be dealt.
+fn check_range_game(
+    variant: Variant,
+    ranges: &[Range],
+    table_cards: &[u32],
+    dead_cards: &[u32],
+) -> Result<(Vec<u32>, Vec<Vec<Combo>>), RangeGameError> {
+    let deck_size = DECK_SIZE as u32;
+    let nb_range = ranges.len() as u32;
+    let nb_table_card = table_cards.len();
+
+    match nb_range {
+        2..=10 => (),
+        _ => return Err(RangeGameError::InvalidNbRange(nb_range)),
+    }
+
+    match nb_table_card {
+        0 | 3..=5 => (),
+        _ => return Err(RangeGameError::InvalidNbTableCard(nb_table_card as u32)),
+    }
+    for t in table_cards.iter() {
+        match *t {
+            x if (x < deck_size) => (),
+            _ => return Err(RangeGameError::InvalidTableCard(*t)),
+        }
+    }
+    for d in dead_cards.iter() {
+        match *d {
+            x if (x < deck_size) => (),
+            _ => return Err(RangeGameError::InvalidDeadCard(*d)),
+        }
+    }
+
+    let known_cards = table_cards.iter().chain(dead_cards.iter());
+    if let Some(c) = known_cards.clone().find(|c| !variant.contains(**c as usize)) {
+        return Err(RangeGameError::CardNotInDeck(*c, variant));
+    }
+    let known_cards_set = known_cards.copied().collect::<HashSet<u32>>();
+    match known_cards_set.len() == nb_table_card + dead_cards.len() {
+        true => (),
+        false => {
+            return Err(RangeGameError::NotDistinctCards(
+                table_cards.to_vec(),
+                dead_cards.to_vec(),
+            ))
+        }
+    }
+
+    let deck = variant
+        .deck()
+        .into_iter()
+        .map(|c| c as u32)
+        .filter(|c| !known_cards_set.contains(c))
+        .collect::<Vec<u32>>();
+
+    let nb_rnd_cards = 2 * ranges.len() + 5 - nb_table_card;
+    match deck.len() >= nb_rnd_cards {
+        true => (),
+        false => return Err(RangeGameError::NotEnoughCards(nb_rnd_cards as u32, deck.len() as u32)),
+    }
+
+    // combos with table or dead cards, or out of the deck, are removed
+    let blocked = (0..deck_size).filter(|c| !deck.contains(c)).collect::<Vec<u32>>();
+    let mut combos = vec![];
+    for (i, r) in ranges.iter().enumerate() {
+        let mut r_ = r.clone();
+        r_.remove_cards(&blocked).unwrap();
+        let combos_ = r_
+            .combos()
+            .into_iter()
+            .filter(|(_, w)| *w > 0.0)
+            .map(|(c, w)| Combo {
+                cards: c,
+                mask: 1 << c[0] | 1 << c[1],
+                hole: HandAccumulator::from_cards(&[c[0] as usize, c[1] as usize]),
+                weight: w,
+            })
+            .collect::<Vec<Combo>>();
+        if combos_.is_empty() {
+            return Err(RangeGameError::EmptyRange(i as u32, r.to_string()));
+        }
+        combos.push(combos_);
+    }
+
+    if !has_distinct_combos(&combos, 0) {
+        return Err(RangeGameError::NoDistinctCombos(
+            ranges.iter().map(|r| r.to_string()).collect(),
+        ));
+    }
+
+    Ok((deck, combos))
+}
+
+/// Whether one combo per range can be dealt, all cards distinct, without the cards of `used`
+fn has_distinct_combos(combos: &[Vec<Combo>], used: u64) -> bool {
+    match combos.split_first() {
+        None => true,
+        Some((first, others)) => first
+            .iter()
+            .any(|c| c.mask & used == 0 && has_distinct_combos(others, used | c.mask)),
+    }
+}
+
+/// Number of games of the enumeration, at most: the number of board completions times the number of combos tuples
+fn nb_game_exact(combos: &[Vec<Combo>], nb_deck: usize, nb_board: usize) -> f64 {
+    let nb_board_completion = (0..nb_board).fold(1.0, |acc, i| acc * (nb_deck - i) as f64 / (i + 1) as f64);
+    combos.iter().fold(nb_board_completion, |acc, r| acc * r.len() as f64)
+}
+
+/// ## Range equity sums
+/// Sums over the games, each weighted by the product of its combos weights:
+/// + of the win and tie shares of each range, and of the squared tie shares
+/// + of the win and tie shares of each hero combo, and of its weight
+#[derive(Debug, Clone)]
+struct RangeSums {
+    moments: Vec<EqtyMoments>,
+    hero: Vec<HandEquity>,
+    hero_weight: Vec<f64>,
+    weight: f64,
+    nb_game: u64,
+}
+
+impl std::ops::AddAssign<&RangeSums> for RangeSums {
+    fn add_assign(&mut self, other: &RangeSums) {
+        for (m, m_) in self.moments.iter_mut().zip(other.moments.iter()) {
+            *m += *m_;
+        }
+        for (e, e_) in self.hero.iter_mut().zip(other.hero.iter()) {
+            *e += *e_;
+        }
+        for (w, w_) in self.hero_weight.iter_mut().zip(other.hero_weight.iter()) {
+            *w += *w_;
+        }
+        self.weight += other.weight;
+        self.nb_game += other.nb_game;
+    }
+}
+
+impl RangeSums {
+    fn new(nb_range: usize, nb_hero: usize) -> RangeSums {
+        RangeSums {
+            moments: vec![EqtyMoments::default(); nb_range],
+            hero: vec![HandEquity { win: 0.0, tie: 0.0 }; nb_hero],
+            hero_weight: vec![0.0; nb_hero],
+            weight: 0.0,
+            nb_game: 0,
+        }
+    }
+
+    /// Add a game, from the index of the combo of each range and its rank
+    fn add(&mut self, idx: &[usize], rank: &[u32], weight: f64) {
+        let max_rank = *rank.iter().max().unwrap();
+        let nb_max = rank.iter().filter(|r| **r == max_rank).count();
+
+        for (p, r) in rank.iter().enumerate() {
+            if *r != max_rank {
+                continue;
+            }
+            let m = &mut self.moments[p];
+            if nb_max == 1 {
+                m.win += weight;
+            } else {
+                let share = 1.0 / (nb_max as f64);
+                m.tie += weight * share;
+                m.tie_sq += weight * share * share;
+            }
+        }
+
+        if rank[0] == max_rank {
+            let e = &mut self.hero[idx[0]];
+            match nb_max {
+                1 => e.win += weight,
+                _ => e.tie += weight / (nb_max as f64),
+            }
+        }
+        self.hero_weight[idx[0]] += weight;
+        self.weight += weight;
+        self.nb_game += 1;
+    }
+}
+
+/// ## Enumerate all games
+/// The board completions are spread over the threads.
+/// For each board, the combos of each range are ranked once, then the tuples of combos with distinct cards are enumerated.
+fn calc_range_exact(t7: &TableSeven, combos: &[Vec<Combo>], table: HandAccumulator, deck: &[u32]) -> RangeSums {
+    let nb_range = combos.len();
+    let nb_board = 5 - table.len();
+
+    // all completions, the identity being the only permutation
+    let boards = canonical_combinations(deck, nb_board, &[[0, 1, 2, 3]]);
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+    let chunk_size = boards.len().div_ceil(n_thread).max(1);
+
+    let arr_sums = thread::scope(|s| {
+        let handles = boards
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    let mut sums = RangeSums::new(nb_range, combos[0].len());
+                    let mut ranked = vec![vec![]; nb_range];
+                    let mut idx = vec![0usize; nb_range];
+                    let mut rank = vec![0u32; nb_range];
+
+                    for (cards, _) in chunk.iter() {
+                        let mut board = table;
+                        let mut board_mask = 0u64;
+                        for c in cards[..nb_board].iter() {
+                            board.push(*c as usize);
+                            board_mask |= 1 << c;
+                        }
+
+                        for (r, combos_) in ranked.iter_mut().zip(combos.iter()) {
+                            r.clear();
+                            for (i, c) in combos_.iter().enumerate() {
+                                if c.mask & board_mask == 0 {
+                                    r.push((i, (c.hole + board).rank_seven(t7)));
+                                }
+                            }
+                        }
+
+                        let deal = RangeDeal {
+                            combos,
+                            ranked: &ranked,
+                        };
+                        deal.deal_range(0, board_mask, 1.0, &mut idx, &mut rank, &mut sums);
+                    }
+                    sums
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<RangeSums>>()
+    });
+
+    let mut sums = RangeSums::new(nb_range, combos[0].len());
+    for s in arr_sums.iter() {
+        sums += s;
+    }
+    sums
+}
+
+/// ## Deal of the combos on a complete board
+/// The combos of each range not holding board cards, with their ranks.
+struct RangeDeal<'a> {
+    combos: &'a [Vec<Combo>],
+    ranked: &'a [Vec<(usize, u32)>],
+}
+
+impl RangeDeal<'_> {
+    /// Enumerate the combos of the ranges, starting from range `p`, without the cards of `used`
+    fn deal_range(&self, p: usize, used: u64, weight: f64, idx: &mut [usize], rank: &mut [u32], sums: &mut RangeSums) {
+        if p == self.ranked.len() {
+            sums.add(idx, rank, weight);
+            return;
+        }
+        for (i, r) in self.ranked[p].iter() {
+            let c = &self.combos[p][*i];
+            if c.mask & used != 0 {
+                continue;
+            }
+            idx[p] = *i;
+            rank[p] = *r;
+            self.deal_range(p + 1, used | c.mask, weight * c.weight, idx, rank, sums);
+        }
+    }
+}
+
+/// ## Simulate games
+/// Same rounds and stopping rule as [calc_equity_monte_carlo](super::equity_mc::calc_equity_monte_carlo), the standard errors being those of the ranges equity.
+/// Returns `None` if the combos of a game could not be drawn without common cards.
+fn calc_range_monte_carlo(
+    t7: &TableSeven,
+    combos: &[Vec<Combo>],
+    table: HandAccumulator,
+    deck: Vec<u32>,
+    stop: McStop,
+    seed: Option<u64>,
+) -> Option<RangeSums> {
+    let nb_range = combos.len();
+    let nb_board = 5 - table.len();
+
+    let start = Instant::now();
+
+    // cumulative weights, to draw the combos
+    let cum_weights = combos
+        .iter()
+        .map(|r| {
+            r.iter()
+                .scan(0.0, |acc, c| {
+                    *acc += c.weight;
+                    Some(*acc)
+                })
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<Vec<f64>>>();
+
+    // enough cards are drawn to complete the board without the combos cards
+    let nb_rnd_cards = (nb_board + 2 * nb_range).min(deck.len());
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+
+    // master stream, from which the stream of each chunk is seeded
+    let mut rng = match seed {
+        Some(x) => SmallRng::seed_from_u64(x),
+        None => SmallRng::from_entropy(),
+    };
+
+    let nb_game_round = match stop.std_err.is_some() || stop.time.is_some() {
+        true => NB_GAME_ROUND,
+        false => stop.nb_game,
+    };
+
+    let mut sums = RangeSums::new(nb_range, combos[0].len());
+    let mut nb_game = 0u32;
+
+    while nb_game < stop.nb_game {
+        let n_round = nb_game_round.min(stop.nb_game - nb_game);
+
+        let arr_sums = calc_chunks(&mut rng, &deck, nb_rnd_cards, n_round, n_thread, |sampler, n_game| {
+            calc_range_batch(t7, combos, &cum_weights, table, sampler, n_game)
+        });
+
+        for s in arr_sums.iter() {
+            sums += s.as_ref()?;
+        }
+        nb_game += n_round;
+
+        if let Some(time) = stop.time {
+            if start.elapsed() >= time {
+                break;
+            }
+        }
+        if let Some(std_err) = stop.std_err {
+            let eqty = sums
+                .moments
+                .iter()
+                .map(|m| m.to_equity(nb_game))
+                .collect::<Vec<McHandEquity>>();
+            if eqty
+                .iter()
+                .all(|e| e.win_std_err <= std_err && e.tie_std_err <= std_err)
+            {
+                break;
+            }
+        }
+    }
+
+    Some(sums)
+}
+
+fn calc_range_batch(
+    t7: &TableSeven,
+    combos: &[Vec<Combo>],
+    cum_weights: &[Vec<f64>],
+    table: HandAccumulator,
+    sampler: &mut Sampler,
+    nb_game: u32,
+) -> Option<RangeSums> {
+    let nb_range = combos.len();
+
+    let mut sums = RangeSums::new(nb_range, combos[0].len());
+    let mut idx = vec![0usize; nb_range];
+    let mut rank = vec![0u32; nb_range];
+
+    for _g in 0..nb_game {
+        // combos drawn in proportion to their weights, again if they have common cards
+        let mut nb_draw = 0;
+        let used = 'draw: loop {
+            nb_draw += 1;
+            if nb_draw > MAX_NB_DRAW {
+                return None;
+            }
+            let rng = sampler.rng();
+            let mut used = 0u64;
+            for (p, cum) in cum_weights.iter().enumerate() {
+                let x = rng.gen::<f64>() * cum[cum.len() - 1];
+                let i = cum.partition_point(|w| *w <= x).min(cum.len() - 1);
+                if combos[p][i].mask & used != 0 {
+                    continue 'draw;
+                }
+                idx[p] = i;
+                used |= combos[p][i].mask;
+            }
+            break used;
+        };
+
+        // the first drawn cards not in the combos complete the board
+        let mut board = table;
+        for c in sampler.draw().iter() {
+            if board.len() == 5 {
+                break;
+            }
+            if used & 1 << c == 0 {
+                board.push(*c as usize);
+            }
+        }
+
+        for (p, r) in rank.iter_mut().enumerate() {
+            *r = (combos[p][idx[p]].hole + board).rank_seven(t7);
+        }
+
+        sums.add(&idx, &rank, 1.0);
+    }
+
+    Some(sums)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{calc_equity_range, RangeGameError};
+    use crate::calc::{self, equity_det::HandEquity, equity_mc::McStop};
+    use crate::eval::seven::{self, TableSeven};
+    use crate::range::Range;
+    use std::sync::Arc;
+
+    /// Weighted average of the deterministic equity of a hand against each combo of a range
+    fn calc_equity_weighted(t7: &Arc<TableSeven>, hand: [u32; 2], range: &Range, table: &[u32]) -> [HandEquity; 2] {
+        let mut eqty = [HandEquity { win: 0.0, tie: 0.0 }; 2];
+        let mut weight = 0.0;
+        for (c, w) in range.combos() {
+            if c.iter().any(|x| hand.contains(x) || table.contains(x)) {
+                continue;
+            }
+            let eqty_ =
+                calc::equity_det::calc_equity_det(t7.clone(), vec![hand, c], table.to_vec(), vec![], false).unwrap();
+            for (e, e_) in eqty.iter_mut().zip(eqty_.iter()) {
+                e.win += w * e_.win;
+                e.tie += w * e_.tie;
+            }
+            weight += w;
+        }
+        for e in eqty.iter_mut() {
+            e.win /= weight;
+            e.tie /= weight;
+        }
+        eqty
+    }
+
+    fn range(s: &str) -> Range {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn calc_equity_range_exact() {
+        let arc_t7 = seven::build_tables(false);
+
+        // hand vs weighted range, against the deterministic equity
+        let tests = [
+            ("AhKh", "QQ+, AKs, AQo:0.5", vec![51, 30, 41]),
+            ("7c7d", "JJ-88, AKs:0.3, T9s", vec![8, 27, 33, 45]),
+            ("2c3c", "KQo:0.25, 44", vec![15, 47, 23, 33, 1]),
+        ];
+
+        for (hand, villain, table) in tests.iter() {
+            let hand_ = range(hand).combos()[0].0;
+            let want = calc_equity_weighted(&arc_t7, hand_, &range(villain), table);
+            let equity = calc_equity_range(
+                arc_t7.clone(),
+                vec![range(hand), range(villain)],
+                table.clone(),
+                vec![],
+                McStop::nb_game(1),
+                None,
+            )
+            .unwrap();
+
+            assert!(equity.exact);
+            for (e, w) in equity.equity.iter().zip(want.iter()) {
+                assert!(
+                    (e.win - w.win).abs() < 1e-12 && (e.tie - w.tie).abs() < 1e-12,
+                    "-> fails: hand={}, villain={}, table={:?}\nfound:equity={:?}, want:equity={:?}",
+                    hand,
+                    villain,
+                    table,
+                    e,
+                    w
+                );
+            }
+            assert_eq!(equity.hero.len(), 1);
+            assert!((equity.hero[0].freq - 1.0).abs() < 1e-12);
+        }
+
+        // hero range, each combo against the deterministic equity
+        let (hero, villain, table) = (range("AKs, 99"), range("QQ+, AQs:0.5"), vec![51, 30, 41, 2]);
+        let equity = calc_equity_range(
+            arc_t7.clone(),
+            vec![hero.clone(), villain.clone()],
+            table.clone(),
+            vec![],
+            McStop::nb_game(1),
+            None,
+        )
+        .unwrap();
+        assert_eq!(equity.hero.len(), 3 + 3, "-> fails: hero={:?}", equity.hero);
+        let mut eqty = HandEquity { win: 0.0, tie: 0.0 };
+        for c in equity.hero.iter() {
+            let want = calc_equity_weighted(&arc_t7, c.combo, &villain, &table);
+            assert!(
+                (c.equity.win - want[0].win).abs() < 1e-12 && (c.equity.tie - want[0].tie).abs() < 1e-12,
+                "-> fails: combo={:?}\nfound:equity={:?}, want:equity={:?}",
+                c.combo,
+                c.equity,
+                want[0]
+            );
+            eqty.win += c.freq * c.equity.win;
+            eqty.tie += c.freq * c.equity.tie;
+        }
+        assert!((equity.hero.iter().map(|c| c.freq).sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((eqty.win - equity.equity[0].win).abs() < 1e-12);
+        assert!((eqty.tie - equity.equity[0].tie).abs() < 1e-12);
+    }
+
+    #[test]
+    fn calc_equity_range_vs_exact() {
+        let arc_t7 = seven::build_tables(false);
+
+        // a random hand is the range of all combos
+        let mut any = Range::new();
+        for c1 in 0..52 {
+            for c2 in 0..c1 {
+                any.insert([c1, c2], 1.0).unwrap();
+            }
+        }
+
+        let players = vec![vec![8, 9], vec![11, 28], vec![]];
+        let table = vec![15, 47, 23, 33];
+        let want = calc::equity_exact::calc_equity_exact(arc_t7.clone(), players.clone(), table.clone(), vec![], false)
+            .unwrap();
+
+        let ranges = vec![range("4c4d"), range("4s9c"), any];
+        let equity =
+            calc_equity_range(arc_t7.clone(), ranges, table.clone(), vec![], McStop::nb_game(1), None).unwrap();
+
+        for (e, w) in equity.equity.iter().zip(want.iter()) {
+            assert!(
+                (e.win - w.win).abs() < 1e-12 && (e.tie - w.tie).abs() < 1e-12,
+                "-> fails: found:equity={:?}, want:equity={:?}",
+                e,
+                w
+            );
+        }
+
+        // wins and shares of ties sum to 1 over all ranges
+        let total = equity.equity.iter().map(|e| e.win + e.tie).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-12, "-> fails: equity={:?}", equity.equity);
+    }
+
+    #[test]
+    fn calc_equity_range_mc() {
+        let arc_t7 = seven::build_tables(false);
+
+        // preflop, against the weighted deterministic equity
+        let (hero, villain) = (range("AhKh"), range("QQ, JTs:0.5"));
+        let want = calc_equity_weighted(&arc_t7, [50, 46], &villain, &[]);
+        let equity = calc_equity_range(
+            arc_t7.clone(),
+            vec![hero, villain],
+            vec![],
+            vec![],
+            McStop::nb_game(1_000_000),
+            Some(0),
+        )
+        .unwrap();
+
+        assert!(!equity.exact);
+        assert_eq!(equity.nb_game, 1_000_000);
+        for (e, w) in equity.equity.iter().zip(want.iter()) {
+            let std_err = (w.win * (1.0 - w.win) / 1e6).sqrt();
+            assert!(
+                (e.win - w.win).abs() <= 4.0 * std_err && (e.tie - w.tie).abs() <= 1e-3,
+                "-> fails: found:equity={:?}, want:equity={:?}",
+                e,
+                w
+            );
+        }
+
+        // same seed, same results
+        let equity_ = calc_equity_range(
+            arc_t7.clone(),
+            vec![range("AhKh"), range("QQ, JTs:0.5")],
+            vec![],
+            vec![],
+            McStop::nb_game(1_000_000),
+            Some(0),
+        )
+        .unwrap();
+        assert_eq!(equity.equity[0].win, equity_.equity[0].win);
+
+        // combos drawn in proportion to their weights
+        let equity = calc_equity_range(
+            arc_t7.clone(),
+            vec![range("AA:0.25, KK"), range("QQ")],
+            vec![],
+            vec![],
+            McStop::nb_game(1_000_000),
+            Some(0),
+        )
+        .unwrap();
+        let freq_aa = equity
+            .hero
+            .iter()
+            .filter(|c| c.combo[0] >= 48)
+            .map(|c| c.freq)
+            .sum::<f64>();
+        assert!(
+            (freq_aa - 0.2).abs() < 4.0 * (0.2 * 0.8 / 1e6_f64).sqrt(),
+            "-> fails: freq_aa={}",
+            freq_aa
+        );
+    }
+
+    #[test]
+    fn calc_equity_range_too_many_games() {
+        let arc_t7 = seven::build_tables(false);
+
+        // three random hands on the flop, about 2.5e12 games to enumerate, are simulated
+        let mut any = Range::new();
+        for c1 in 0..52 {
+            for c2 in 0..c1 {
+                any.insert([c1, c2], 1.0).unwrap();
+            }
+        }
+        let ranges = vec![any.clone(), any.clone(), any];
+        let equity =
+            calc_equity_range(arc_t7.clone(), ranges, vec![51, 30, 41], vec![], McStop::nb_game(100_000), Some(0))
+                .unwrap();
+
+        assert!(!equity.exact);
+        assert_eq!(equity.nb_game, 100_000);
+        for e in equity.equity.iter() {
+            assert!(
+                (e.win + e.tie - 1.0 / 3.0).abs() < 1e-2,
+                "-> fails: equity={:?}",
+                equity.equity
+            );
+        }
+    }
+
+    #[test]
+    fn calc_equity_range_errors() {
+        let arc_t7 = seven::build_tables(false);
+        let calc_range = |ranges: Vec<&str>, table: Vec<u32>, dead: Vec<u32>| {
+            calc_equity_range(
+                arc_t7.clone(),
+                ranges.into_iter().map(range).collect(),
+                table,
+                dead,
+                McStop::nb_game(1000),
+                None,
+            )
+        };
+
+        let equity = calc_range(vec!["AA"], vec![], vec![]);
+        assert!(
+            matches!(equity, Err(RangeGameError::InvalidNbRange(1))),
+            "-> fails: {:?}",
+            equity
+        );
+        let equity = calc_range(vec!["AA", "KK"], vec![1, 2], vec![]);
+        assert!(
+            matches!(equity, Err(RangeGameError::InvalidNbTableCard(2))),
+            "-> fails: {:?}",
+            equity
+        );
+        let equity = calc_range(vec!["AA", "KK"], vec![1, 2, 60], vec![]);
+        assert!(
+            matches!(equity, Err(RangeGameError::InvalidTableCard(60))),
+            "-> fails: {:?}",
+            equity
+        );
+        let equity = calc_range(vec!["AA", "KK"], vec![1, 2, 3], vec![3]);
+        assert!(
+            matches!(equity, Err(RangeGameError::NotDistinctCards(..))),
+            "-> fails: {:?}",
+            equity
+        );
+        // all aces on the table or dead
+        let equity = calc_range(vec!["AA", "KK"], vec![48, 49, 50], vec![51]);
+        assert!(
+            matches!(equity, Err(RangeGameError::EmptyRange(0, _))),
+            "-> fails: {:?}",
+            equity
+        );
+        let equity = calc_range(vec!["AsAh", "AhAd"], vec![], vec![]);
+        assert!(
+            matches!(equity, Err(RangeGameError::NoDistinctCombos(_))),
+            "-> fails: {:?}",
+            equity
+        );
+
+        // the villain combos almost always hold the hero cards
+        let mut villain = Range::new();
+        for c in (0..50).filter(|c| *c != 0 && *c != 1) {
+            villain.insert([51, c], 1.0).unwrap();
+            villain.insert([50, c], 1.0).unwrap();
+        }
+        villain.insert([0, 1], 1e-9).unwrap();
+        let equity = calc_equity_range(
+            arc_t7.clone(),
+            vec![range("AsAh"), villain],
+            vec![],
+            vec![],
+            McStop::nb_game(1000),
+            None,
+        );
+        assert!(
+            matches!(equity, Err(RangeGameError::ConflictingCombos(_))),
+            "-> fails: {:?}",
+            equity
+        );
+    }
+}
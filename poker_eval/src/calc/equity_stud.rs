@@ -0,0 +1,744 @@
+//! # Seven-card Stud equity calculation
+//! In Stud, there is no shared board: each player has 7 private cards, dealt street by street, see [StudStreet].
+//!
+//! The known cards of each player - face-up cards, or all its cards for hero - and the dead cards, e.g. folded face-up cards, are given.
+//! The other cards of each player, dealt but unknown or still to come, are drawn from the rest of the deck:
+//! - [calc_equity_stud_det]: deterministically, i.e. exhaustively, over all deals
+//! - [calc_equity_stud_monte_carlo]: in monte carlo mode
+//! - [calc_equity_stud_hilo_det] and [calc_equity_stud_hilo_monte_carlo]: same for Stud/8, see [equity_hilo](crate::calc::equity_hilo)
+//!
+//! Each player hand is ranked with [get_rank](crate::eval::seven::get_rank).
+//! The number of deals grows fast with the number of unknown cards, so exhaustive mode is practical from 5th street heads-up.
+
+//!   # Example
+//!
+//! ```
+//! use poker_eval::eval::seven::build_tables;
+//! use poker_eval::calc::equity_stud::{calc_equity_stud_det, StudStreet};
+//!
+//! // you need create Arc<TableSeven> arc_t7 beforehand once
+//! let arc_t7 = build_tables(false);
+//!
+//! // then you can call calc_equity_stud_det multiple times
+//! let equity = calc_equity_stud_det(
+//!     // clone of Arc<TableSeven>
+//!     arc_t7.clone(),
+//!     // players cards: hero (Ah Ad) Kc 7s 2d 3h vs (?? ??) Qh Qs 9c 9d
+//!     vec![vec![50, 49, 44, 23, 1, 6], vec![42, 43, 28, 29]],
+//!     // dead cards: Qd Ac 9s
+//!     vec![41, 48, 31],
+//!     // street
+//!     StudStreet::Sixth,
+//!     // verbose
+//!     false,
+//! );
+//! println!("equity = {:?}", equity);
+//! ```
+
+use rand::{rngs::SmallRng, SeedableRng};
+use std::{collections::HashSet, sync::Arc, thread, time::Instant};
+
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::equity_det::{update_eqty, HandEquity};
+use super::equity_hilo::{get_rank_hilo_seven, update_eqty_hilo, HiLoEquity};
+use super::equity_mc::{calc_chunks, MAX_NB_GAME};
+use crate::{
+    eval::{
+        low::TableLow,
+        seven::{get_rank, TableSeven},
+    },
+    keys::{Variant, DECK_SIZE},
+};
+
+/// Max number of players
+pub const MAX_NB_PLAYER_STUD: usize = 8;
+
+/// ## Stud street
+/// Number of cards dealt to each player: 2 down and 1 up on 3rd street, up to 7 on 7th street.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum StudStreet {
+    Third = 3,
+    Fourth = 4,
+    Fifth = 5,
+    Sixth = 6,
+    Seventh = 7,
+}
+
+impl StudStreet {
+    /// Number of cards dealt to each player
+    pub fn nb_card(self) -> usize {
+        self as usize
+    }
+}
+
+/// ## Stud game description error
+/// This error type is used to describe the errors that can occur when describing a Stud game.
+#[derive(Error, Debug)]
+pub enum StudGameError {
+    // player
+    /// Invalid number of players
+    #[error("invalid nb players: {0} - must be between 2 and 8")]
+    InvalidNbPlayer(u32),
+    /// Invalid player
+    #[error("invalid player {0}: {1:?} - at most {2} cards must be provided on this street")]
+    InvalidPlayer(u32, Vec<u32>, u32),
+    /// Invalid player card
+    #[error("invalid player card: {1} for player {0} - must be between 0 and 51")]
+    InvalidPlayerCard(u32, u32),
+    // dead
+    /// Invalid dead card
+    #[error("invalid dead card: {0} - must be between 0 and 51")]
+    InvalidDeadCard(u32),
+    // both
+    /// Card not in the deck of the variant, e.g. a 2 in short-deck
+    #[error("invalid card: {0} - not in the {1} deck")]
+    CardNotInDeck(u32, Variant),
+    /// Not distinct cards
+    #[error("players: {0:?} dead: {1:?} - all cards must be distinct")]
+    NotDistinctCards(Vec<Vec<u32>>, Vec<u32>),
+    /// Not enough cards left in the deck
+    #[error("not enough cards: {0} to deal - {1} left in the deck")]
+    NotEnoughCards(u32, u32),
+    // monte carlo
+    /// Invalid number of games
+    #[error("invalid nb games: {0} - must be between 1 and 1000000000")]
+    InvalidNbGame(u32),
+}
+
+/// ## Calculate equity of Stud hands deterministically ie. exhaustively
+/// Over all deals of the unknown cards of all players.
+pub fn calc_equity_stud_det(
+    t7: Arc<TableSeven>,
+    player_cards: Vec<Vec<u32>>,
+    dead_cards: Vec<u32>,
+    street: StudStreet,
+    verbose: bool,
+) -> Result<Vec<HandEquity>, StudGameError> {
+    let start = Instant::now();
+
+    let deck = check_game(&player_cards, &dead_cards, street, t7.t5.variant)?;
+
+    let init = HandEquity { win: 0.0, tie: 0.0 };
+    let (eqty, n_game) = calc_eqty_deals(&player_cards, deck, init, move |eqty, hands, rank| {
+        for (p, hand) in hands.iter().enumerate() {
+            rank[p] = get_rank(&t7, *hand);
+        }
+        update_eqty(eqty, rank);
+    });
+
+    let equity = eqty
+        .iter()
+        .map(|e| HandEquity {
+            win: e.win / (n_game as f64),
+            tie: e.tie / (n_game as f64),
+        })
+        .collect::<Vec<HandEquity>>();
+
+    let end = Instant::now();
+
+    if verbose {
+        println!("calc_equity_stud_det runtime: {:?} nb_deal = {}", end - start, n_game);
+    }
+
+    Ok(equity)
+}
+
+/// ## Calculate equity of Stud hands in monte carlo mode
+/// Over `nb_game` random deals of the unknown cards of all players.  
+/// The deals are drawn in chunks spread over the threads, as in [calc_equity_monte_carlo](super::equity_mc::calc_equity_monte_carlo),
+/// so the same `seed` gives identical results on any machine.
+pub fn calc_equity_stud_monte_carlo(
+    t7: Arc<TableSeven>,
+    player_cards: Vec<Vec<u32>>,
+    dead_cards: Vec<u32>,
+    street: StudStreet,
+    nb_game: u32,
+    seed: Option<u64>,
+) -> Result<Vec<HandEquity>, StudGameError> {
+    let deck = check_game(&player_cards, &dead_cards, street, t7.t5.variant)?;
+    match nb_game {
+        1..=MAX_NB_GAME => (),
+        _ => return Err(StudGameError::InvalidNbGame(nb_game)),
+    }
+
+    let init = HandEquity { win: 0.0, tie: 0.0 };
+    let eqty = calc_eqty_samples(&player_cards, deck, nb_game, seed, init, move |eqty, hands, rank| {
+        for (p, hand) in hands.iter().enumerate() {
+            rank[p] = get_rank(&t7, *hand);
+        }
+        update_eqty(eqty, rank);
+    });
+
+    let equity = eqty
+        .iter()
+        .map(|e| HandEquity {
+            win: e.win / (nb_game as f64),
+            tie: e.tie / (nb_game as f64),
+        })
+        .collect::<Vec<HandEquity>>();
+
+    Ok(equity)
+}
+
+/// ## Calculate Stud/8 equity deterministically ie. exhaustively
+/// Same as [calc_equity_stud_det], the pot being split between the best high hand and the best 8-or-better low hand.
+pub fn calc_equity_stud_hilo_det(
+    t7: Arc<TableSeven>,
+    tl: Arc<TableLow>,
+    player_cards: Vec<Vec<u32>>,
+    dead_cards: Vec<u32>,
+    street: StudStreet,
+    verbose: bool,
+) -> Result<Vec<HiLoEquity>, StudGameError> {
+    let start = Instant::now();
+
+    let deck = check_game(&player_cards, &dead_cards, street, t7.t5.variant)?;
+
+    let nb_player = player_cards.len();
+    let mut rank_low = vec![None; nb_player];
+    let (eqty, n_game) = calc_eqty_deals(&player_cards, deck, HiLoEquity::default(), move |eqty, hands, rank| {
+        for (p, hand) in hands.iter().enumerate() {
+            (rank[p], rank_low[p]) = get_rank_hilo_seven(&t7, &tl, *hand);
+        }
+        update_eqty_hilo(eqty, rank, &rank_low);
+    });
+
+    let equity = normalize_hilo(&eqty, n_game);
+
+    let end = Instant::now();
+
+    if verbose {
+        println!(
+            "calc_equity_stud_hilo_det runtime: {:?} nb_deal = {}",
+            end - start,
+            n_game
+        );
+    }
+
+    Ok(equity)
+}
+
+/// ## Calculate Stud/8 equity in monte carlo mode
+/// Same as [calc_equity_stud_monte_carlo], the pot being split between the best high hand and the best 8-or-better low hand.
+pub fn calc_equity_stud_hilo_monte_carlo(
+    t7: Arc<TableSeven>,
+    tl: Arc<TableLow>,
+    player_cards: Vec<Vec<u32>>,
+    dead_cards: Vec<u32>,
+    street: StudStreet,
+    nb_game: u32,
+    seed: Option<u64>,
+) -> Result<Vec<HiLoEquity>, StudGameError> {
+    let deck = check_game(&player_cards, &dead_cards, street, t7.t5.variant)?;
+    match nb_game {
+        1..=MAX_NB_GAME => (),
+        _ => return Err(StudGameError::InvalidNbGame(nb_game)),
+    }
+
+    let nb_player = player_cards.len();
+    let mut rank_low = vec![None; nb_player];
+    let eqty = calc_eqty_samples(
+        &player_cards,
+        deck,
+        nb_game,
+        seed,
+        HiLoEquity::default(),
+        move |eqty, hands, rank| {
+            for (p, hand) in hands.iter().enumerate() {
+                (rank[p], rank_low[p]) = get_rank_hilo_seven(&t7, &tl, *hand);
+            }
+            update_eqty_hilo(eqty, rank, &rank_low);
+        },
+    );
+
+    Ok(normalize_hilo(&eqty, nb_game as u64))
+}
+
+fn normalize_hilo(eqty: &[HiLoEquity], n_game: u64) -> Vec<HiLoEquity> {
+    eqty.iter()
+        .map(|e| HiLoEquity {
+            high: e.high / (n_game as f64),
+            low: e.low / (n_game as f64),
+            scoop: e.scoop / (n_game as f64),
+        })
+        .collect()
+}
+
+/// Check game description and return the deck, i.e. the variant cards not in player or dead cards
+fn check_game(
+    player_cards: &[Vec<u32>],
+    dead_cards: &[u32],
+    street: StudStreet,
+    variant: Variant,
+) -> Result<Vec<usize>, StudGameError> {
+    let deck_size = DECK_SIZE as u32;
+    let nb_player = player_cards.len();
+
+    // start check input
+    match nb_player {
+        2..=MAX_NB_PLAYER_STUD => (),
+        _ => return Err(StudGameError::InvalidNbPlayer(nb_player as u32)),
+    }
+
+    for (i, p) in player_cards.iter().enumerate() {
+        if p.len() > street.nb_card() {
+            return Err(StudGameError::InvalidPlayer(
+                i as u32,
+                p.clone(),
+                street.nb_card() as u32,
+            ));
+        }
+        for c in p.iter() {
+            match *c {
+                x if (x < deck_size) => (),
+                _ => return Err(StudGameError::InvalidPlayerCard(i as u32, *c)),
+            }
+        }
+    }
+
+    for c in dead_cards.iter() {
+        match *c {
+            x if (x < deck_size) => (),
+            _ => return Err(StudGameError::InvalidDeadCard(*c)),
+        }
+    }
+
+    let all_cards = player_cards.iter().flatten().chain(dead_cards.iter());
+    if let Some(c) = all_cards.clone().find(|c| !variant.contains(**c as usize)) {
+        return Err(StudGameError::CardNotInDeck(*c, variant));
+    }
+
+    let all_cards_set = all_cards.map(|c| *c as usize).collect::<HashSet<usize>>();
+    let nb_card = player_cards.iter().map(|p| p.len()).sum::<usize>() + dead_cards.len();
+    if nb_card != all_cards_set.len() {
+        return Err(StudGameError::NotDistinctCards(
+            player_cards.to_vec(),
+            dead_cards.to_vec(),
+        ));
+    }
+
+    let deck = variant
+        .deck()
+        .into_iter()
+        .filter(|c| !all_cards_set.contains(c))
+        .collect::<Vec<usize>>();
+
+    let nb_missing = 7 * nb_player - player_cards.iter().map(|p| p.len()).sum::<usize>();
+    match nb_missing <= deck.len() {
+        true => Ok(deck),
+        false => Err(StudGameError::NotEnoughCards(nb_missing as u32, deck.len() as u32)),
+    }
+    // end check input
+}
+
+/// Players hands with their known cards first
+fn known_hands(player_cards: &[Vec<u32>]) -> Vec<[usize; 7]> {
+    player_cards
+        .iter()
+        .map(|p| {
+            let mut hand = [0usize; 7];
+            for (j, c) in p.iter().enumerate() {
+                hand[j] = *c as usize;
+            }
+            hand
+        })
+        .collect()
+}
+
+/// Equity sums and number of games, over all deals - one thread per slice of the first unknown card
+fn calc_eqty_deals<E, F>(player_cards: &[Vec<u32>], deck: Vec<usize>, init: E, update: F) -> (Vec<E>, u64)
+where
+    E: Copy + Send + 'static + std::ops::AddAssign,
+    F: FnMut(&mut Vec<E>, &[[usize; 7]], &mut Vec<u32>) + Clone + Send + 'static,
+{
+    let nb_player = player_cards.len();
+    let hands = known_hands(player_cards);
+    let nb_known = player_cards.iter().map(|p| p.len()).collect::<Vec<usize>>();
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+    let deck = Arc::new(deck);
+    let mut handles = vec![];
+
+    for t in 0..n_thread {
+        let mut hands_ = hands.clone();
+        let nb_known_ = nb_known.clone();
+        let deck_ = Arc::clone(&deck);
+        let mut update_ = update.clone();
+
+        let handle = thread::spawn(move || {
+            let mut eqty_ = vec![init; nb_player];
+            let mut rank_ = vec![0; nb_player];
+            let mut used = vec![false; deck_.len()];
+            let mut n_game_ = 0;
+            let split = (t, n_thread);
+            deal(
+                0,
+                nb_known_[0],
+                0,
+                true,
+                &mut hands_,
+                &nb_known_,
+                &deck_,
+                &mut used,
+                split,
+                &mut |hands| {
+                    update_(&mut eqty_, hands, &mut rank_);
+                    n_game_ += 1;
+                },
+            );
+            (eqty_, n_game_)
+        });
+        handles.push(handle);
+    }
+
+    let mut eqty = vec![init; nb_player];
+    let mut n_game = 0;
+    for handle in handles {
+        let (eqty_, n_game_) = handle.join().unwrap();
+        for p in 0..nb_player {
+            eqty[p] += eqty_[p];
+        }
+        n_game += n_game_;
+    }
+
+    (eqty, n_game)
+}
+
+/// Deal the unknown cards of player `p` from card `j` on, using deck cards from `start` on, then of the next players.
+/// The first unknown card is only dealt from the deck indices of the `split` (thread, nb thread).
+#[allow(clippy::too_many_arguments)]
+fn deal(
+    p: usize,
+    j: usize,
+    start: usize,
+    first: bool,
+    hands: &mut Vec<[usize; 7]>,
+    nb_known: &[usize],
+    deck: &[usize],
+    used: &mut Vec<bool>,
+    split: (usize, usize),
+    f: &mut dyn FnMut(&[[usize; 7]]),
+) {
+    if j == 7 {
+        match p + 1 < hands.len() {
+            true => deal(p + 1, nb_known[p + 1], 0, first, hands, nb_known, deck, used, split, f),
+            false => {
+                // all cards known: only one thread plays the single deal
+                if !first || split.0 == 0 {
+                    f(hands)
+                }
+            }
+        }
+        return;
+    }
+    for i in start..deck.len() {
+        if used[i] || (first && i % split.1 != split.0) {
+            continue;
+        }
+        used[i] = true;
+        hands[p][j] = deck[i];
+        deal(p, j + 1, i + 1, false, hands, nb_known, deck, used, split, f);
+        used[i] = false;
+    }
+}
+
+/// Equity sums over `nb_game` random deals - drawn in chunks spread over threads
+fn calc_eqty_samples<E, F>(
+    player_cards: &[Vec<u32>],
+    deck: Vec<usize>,
+    nb_game: u32,
+    seed: Option<u64>,
+    init: E,
+    update: F,
+) -> Vec<E>
+where
+    E: Copy + Send + Sync + std::ops::AddAssign,
+    F: FnMut(&mut Vec<E>, &[[usize; 7]], &mut Vec<u32>) + Clone + Sync,
+{
+    let nb_player = player_cards.len();
+    let hands = known_hands(player_cards);
+    let nb_known = player_cards.iter().map(|p| p.len()).collect::<Vec<usize>>();
+    let nb_rnd_cards = 7 * nb_player - nb_known.iter().sum::<usize>();
+    let deck = deck.iter().map(|c| *c as u32).collect::<Vec<u32>>();
+
+    let n_thread = thread::available_parallelism().unwrap().get();
+    let mut rng = match seed {
+        Some(x) => SmallRng::seed_from_u64(x),
+        None => SmallRng::from_entropy(),
+    };
+
+    let arr_eqty = calc_chunks(&mut rng, &deck, nb_rnd_cards, nb_game, n_thread, |sampler, n_game| {
+        let mut hands_ = hands.clone();
+        let mut update_ = update.clone();
+        let mut eqty_ = vec![init; nb_player];
+        let mut rank_ = vec![0; nb_player];
+
+        for _g in 0..n_game {
+            let rnd_cards = sampler.draw();
+            let mut r = 0;
+            for (p, hand) in hands_.iter_mut().enumerate() {
+                for c in hand[nb_known[p]..].iter_mut() {
+                    *c = rnd_cards[r] as usize;
+                    r += 1;
+                }
+            }
+            update_(&mut eqty_, &hands_, &mut rank_);
+        }
+        eqty_
+    });
+
+    let mut eqty = vec![init; nb_player];
+    for eqty_ in arr_eqty.iter() {
+        for p in 0..nb_player {
+            eqty[p] += eqty_[p];
+        }
+    }
+    eqty
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use super::{
+        calc_equity_stud_det, calc_equity_stud_hilo_det, calc_equity_stud_monte_carlo, StudGameError, StudStreet,
+    };
+    use crate::calc::equity_det::update_eqty;
+    use crate::calc::equity_mc::MAX_NB_GAME;
+    use crate::calc::equity_det::HandEquity;
+    use crate::calc::equity_hilo::{get_rank_hilo_seven, update_eqty_hilo, HiLoEquity};
+    use crate::eval::low;
+    use crate::eval::seven::{build_tables, get_rank_seven};
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_stud_normal() {
+        is_normal::<StudStreet>();
+        is_normal::<StudGameError>();
+    }
+
+    #[test]
+    fn calc_equity_stud_det_sixth() {
+        let arc_t7 = build_tables(false);
+
+        // 6th street, one card each to come
+        let players = vec![vec![50, 49, 44, 23, 1, 6], vec![42, 43, 28, 29, 12, 17]];
+        let dead = vec![41, 48, 31, 0];
+
+        // brute force, ranked with get_rank_seven
+        let mut want = vec![HandEquity { win: 0.0, tie: 0.0 }; 2];
+        let mut n_game = 0.0;
+        let is_out = |c: &u32| dead.contains(c) || players.iter().any(|p| p.contains(c));
+        for c1 in (0..52).filter(|c| !is_out(c)) {
+            for c2 in (0..52).filter(|c| !is_out(c) && *c != c1) {
+                let rank = [(0, c1), (1, c2)]
+                    .map(|(p, c)| {
+                        let hand = [players[p].as_slice(), &[c]]
+                            .concat()
+                            .iter()
+                            .map(|x| *x as usize)
+                            .collect::<Vec<usize>>();
+                        get_rank_seven(&arc_t7.t5, hand.try_into().unwrap())
+                    })
+                    .to_vec();
+                update_eqty(&mut want, &rank);
+                n_game += 1.0;
+            }
+        }
+
+        let equity = calc_equity_stud_det(arc_t7.clone(), players.clone(), dead.clone(), StudStreet::Sixth, true);
+        assert!(equity.is_ok(), "-> fails: players={:?}, dead={:?}", players, dead);
+
+        let precision = 1e-9;
+        for (e, w) in equity.unwrap().iter().zip(want.iter()) {
+            assert!(
+                (e.win - w.win / n_game).abs() < precision && (e.tie - w.tie / n_game).abs() < precision,
+                "-> fails: players={:?}, dead={:?}\nfound:equity={:?}, want:equity={:?}",
+                players,
+                dead,
+                e,
+                w
+            );
+        }
+    }
+
+    #[test]
+    fn calc_equity_stud_det_seventh() {
+        let arc_t7 = build_tables(false);
+
+        // 7th street, all cards known: Ah Ad Kc 7s 2d 3c Ks vs Qh Qs 9c 9d 5c 6d 8h
+        let players = vec![vec![50, 49, 44, 23, 1, 4, 47], vec![42, 43, 28, 29, 12, 17, 26]];
+        let equity = calc_equity_stud_det(arc_t7.clone(), players.clone(), vec![], StudStreet::Seventh, false).unwrap();
+        assert_eq!(
+            (equity[0].win, equity[1].win),
+            (1.0, 0.0),
+            "-> fails: equity={:?}",
+            equity
+        );
+    }
+
+    #[test]
+    fn calc_equity_stud_mc() {
+        let arc_t7 = build_tables(false);
+
+        // 5th street, with unknown hole cards for the second player
+        let players = vec![vec![50, 49, 44, 23, 1], vec![42, 43, 28]];
+        let dead = vec![41, 48, 31];
+
+        let equity_det =
+            calc_equity_stud_det(arc_t7.clone(), players.clone(), dead.clone(), StudStreet::Fifth, true).unwrap();
+        let equity_mc = calc_equity_stud_monte_carlo(
+            arc_t7.clone(),
+            players.clone(),
+            dead.clone(),
+            StudStreet::Fifth,
+            200_000,
+            Some(0),
+        )
+        .unwrap();
+
+        let precision = 1e-2;
+        for (d, m) in equity_det.iter().zip(equity_mc.iter()) {
+            assert!(
+                (d.win - m.win).abs() < precision && (d.tie - m.tie).abs() < precision,
+                "-> fails: players={:?}, dead={:?}\nfound:equity={:?}, want:equity={:?}",
+                players,
+                dead,
+                m,
+                d
+            );
+        }
+    }
+
+    #[test]
+    fn calc_equity_stud_mc_few_games() {
+        let arc_t7 = build_tables(false);
+
+        let players = vec![vec![50, 49, 44, 23, 1], vec![42, 43, 28]];
+        let dead = vec![41, 48, 31];
+
+        let mc = |nb_game: u32, seed: Option<u64>| {
+            calc_equity_stud_monte_carlo(
+                arc_t7.clone(),
+                players.clone(),
+                dead.clone(),
+                StudStreet::Fifth,
+                nb_game,
+                seed,
+            )
+        };
+
+        assert!(matches!(mc(0, None), Err(StudGameError::InvalidNbGame(0))));
+        assert!(matches!(
+            mc(MAX_NB_GAME + 1, None),
+            Err(StudGameError::InvalidNbGame(x)) if x == MAX_NB_GAME + 1
+        ));
+
+        for nb_game in [1, 3] {
+            let equity = mc(nb_game, None).unwrap();
+            let sum = equity.iter().map(|e| e.win + e.tie).sum::<f64>();
+            assert!(
+                equity.iter().all(|e| e.win.is_finite() && e.tie.is_finite()) && (sum - 1.0).abs() < 1e-9,
+                "-> fails: nb_game={}\nfound:equity={:?}",
+                nb_game,
+                equity
+            );
+        }
+
+        // same seed, same results
+        let (equity_a, equity_b) = (mc(10_000, Some(42)).unwrap(), mc(10_000, Some(42)).unwrap());
+        assert!(
+            equity_a
+                .iter()
+                .zip(equity_b.iter())
+                .all(|(a, b)| a.win == b.win && a.tie == b.tie),
+            "-> fails: seed=42\nfound:equity={:?}, want:equity={:?}",
+            equity_b,
+            equity_a
+        );
+    }
+
+    #[test]
+    fn calc_equity_stud_hilo() {
+        let arc_t7 = build_tables(false);
+        let arc_tl = Arc::new(low::build_tables(false));
+
+        // 6th street: Ah 2d 4c 7s 9h Kd vs Qh Qs 3c 5d 6h Jc
+        let players = vec![vec![50, 1, 8, 23, 30, 45], vec![42, 43, 4, 13, 18, 36]];
+        let dead = vec![];
+
+        let mut want = vec![HiLoEquity::default(); 2];
+        let mut n_game = 0.0;
+        let is_out = |c: &u32| players.iter().any(|p| p.contains(c));
+        for c1 in (0..52).filter(|c| !is_out(c)) {
+            for c2 in (0..52).filter(|c| !is_out(c) && *c != c1) {
+                let (rank_high, rank_low): (Vec<u32>, Vec<Option<u32>>) = [(0, c1), (1, c2)]
+                    .iter()
+                    .map(|(p, c)| {
+                        let hand = [players[*p].as_slice(), &[*c]]
+                            .concat()
+                            .iter()
+                            .map(|x| *x as usize)
+                            .collect::<Vec<usize>>();
+                        get_rank_hilo_seven(&arc_t7, &arc_tl, hand.try_into().unwrap())
+                    })
+                    .unzip();
+                update_eqty_hilo(&mut want, &rank_high, &rank_low);
+                n_game += 1.0;
+            }
+        }
+
+        let equity = calc_equity_stud_hilo_det(
+            arc_t7.clone(),
+            arc_tl.clone(),
+            players.clone(),
+            dead.clone(),
+            StudStreet::Sixth,
+            false,
+        )
+        .unwrap();
+
+        let precision = 1e-9;
+        for (e, w) in equity.iter().zip(want.iter()) {
+            assert!(
+                (e.high - w.high / n_game).abs() < precision
+                    && (e.low - w.low / n_game).abs() < precision
+                    && (e.scoop - w.scoop / n_game).abs() < precision,
+                "-> fails: players={:?}\nfound:equity={:?}, want:equity={:?}",
+                players,
+                e,
+                w
+            );
+        }
+        assert!((equity.iter().map(|e| e.pot()).sum::<f64>() - 1.0).abs() < precision);
+    }
+
+    #[test]
+    fn calc_equity_stud_invalid() {
+        let arc_t7 = build_tables(false);
+
+        for (players, dead, street) in [
+            // one player
+            (vec![vec![0, 1, 2]], vec![], StudStreet::Third),
+            // too many cards for the street
+            (vec![vec![0, 1, 2, 3], vec![4, 5, 6]], vec![], StudStreet::Third),
+            // invalid card
+            (vec![vec![0, 1, 52], vec![4, 5, 6]], vec![], StudStreet::Third),
+            // invalid dead card
+            (vec![vec![0, 1, 2], vec![4, 5, 6]], vec![60], StudStreet::Third),
+            // dead card also a player card
+            (vec![vec![0, 1, 2], vec![4, 5, 6]], vec![6], StudStreet::Third),
+            // 8 players and 10 dead cards: 56 cards to deal
+            (vec![vec![]; 8], (0..10).collect(), StudStreet::Third),
+        ] {
+            let equity = calc_equity_stud_det(arc_t7.clone(), players.clone(), dead.clone(), street, false);
+            assert!(equity.is_err(), "-> fails: players={:?}, dead={:?}", players, dead);
+        }
+    }
+}
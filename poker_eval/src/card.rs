@@ -0,0 +1,594 @@
+//! ## Typed cards
+//! Thin typed layer over the integer card encoding of module [keys](crate::keys):
+//! - [Face]: card face, from 2 to A
+//! - [Suit]: card suit, among Clubs, Diamonds, Hearts, Spades
+//! - [Card]: one of the 52 deck cards
+//! - [Hand]: set of distinct cards
+//!
+//! Card number `n` is `NB_SUIT * face + suit`, same as `keys::Keys::card_face` and `keys::Keys::card_suit`.
+//! All types convert to and from this integer encoding so existing functions remain usable.
+//! Values are checked at construction time, so an invalid card cannot exist.
+//!
+//! All types also parse from and format to text, e.g. `"As"`, `"AsKd"` or `"Ah Kh Qh"`:
+//! - faces: `2`..`9`, `T` or `10`, `J`, `Q`, `K`, `A` - case insensitive
+//! - suits: `c`, `d`, `h`, `s` - case insensitive - or `♣`, `♦`, `♥`, `♠`
+//! - hands: cards concatenated and/or separated by spaces or commas
+//!
+//! ```
+//! use poker_eval::card::{Card, Hand};
+//!
+//! let card: Card = "a♠".parse().unwrap();
+//! assert_eq!(card.to_string(), "As");
+//!
+//! let board: Hand = "Ah Kh, 10h".parse().unwrap();
+//! assert_eq!(board.to_string(), "Ah Kh Th");
+//!
+//! assert!("AsKx".parse::<Hand>().is_err());
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::keys::{DECK_SIZE, FACE, NB_FACE, NB_SUIT, SUIT, SUIT_SYMBOL};
+
+/// ## Card error
+/// This error type is used to describe the errors that can occur when building typed cards.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CardError {
+    /// Invalid face
+    #[error("invalid face: {0} - must be between 0 and 12")]
+    InvalidFace(usize),
+    /// Invalid suit
+    #[error("invalid suit: {0} - must be between 0 and 3")]
+    InvalidSuit(usize),
+    /// Invalid card
+    #[error("invalid card: {0} - must be between 0 and 51")]
+    InvalidCard(usize),
+    /// Duplicate card
+    #[error("duplicate card: {0} - all cards must be distinct")]
+    DuplicateCard(usize),
+    /// Invalid number of cards
+    #[error("invalid nb cards: {1} - must be {0}")]
+    InvalidNbCard(usize, usize),
+}
+
+/// ## Card parsing error
+/// This error type is used to describe the errors that can occur when parsing cards from text.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    /// Invalid face token
+    #[error("invalid face: '{0}' - must be among 2-9, T or 10, J, Q, K, A")]
+    InvalidFace(String),
+    /// Invalid suit token
+    #[error("invalid suit: '{0}' - must be among c, d, h, s or ♣, ♦, ♥, ♠")]
+    InvalidSuit(String),
+    /// Invalid card token
+    #[error("invalid card: '{0}' - must be a face followed by a suit, e.g. 'As'")]
+    InvalidCard(String),
+    /// Duplicate card token
+    #[error("duplicate card: '{0}' - all cards must be distinct")]
+    DuplicateCard(String),
+}
+
+/// ## Card face
+/// From 0 (2) to 12 (A), same order as [FACE](crate::keys::FACE).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Face(u8);
+
+impl Face {
+    pub const TWO: Face = Face(0);
+    pub const THREE: Face = Face(1);
+    pub const FOUR: Face = Face(2);
+    pub const FIVE: Face = Face(3);
+    pub const SIX: Face = Face(4);
+    pub const SEVEN: Face = Face(5);
+    pub const EIGHT: Face = Face(6);
+    pub const NINE: Face = Face(7);
+    pub const TEN: Face = Face(8);
+    pub const JACK: Face = Face(9);
+    pub const QUEEN: Face = Face(10);
+    pub const KING: Face = Face(11);
+    pub const ACE: Face = Face(12);
+
+    /// Build face from its index, between 0 and 12
+    pub fn new(f: usize) -> Result<Face, CardError> {
+        match f {
+            x if x < NB_FACE => Ok(Face(x as u8)),
+            _ => Err(CardError::InvalidFace(f)),
+        }
+    }
+
+    /// Face index, between 0 and 12
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// All faces, from 2 to A
+    pub fn all() -> impl Iterator<Item = Face> {
+        (0..NB_FACE as u8).map(Face)
+    }
+
+    /// Face name, e.g. "Six"
+    pub fn name(self) -> &'static str {
+        FACE_NAME[self.index()]
+    }
+
+    /// Face name plural, e.g. "Sixes"
+    pub fn plural(self) -> &'static str {
+        FACE_PLURAL[self.index()]
+    }
+}
+
+const FACE_NAME: [&str; NB_FACE] = [
+    "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine", "Ten", "Jack", "Queen", "King", "Ace",
+];
+
+const FACE_PLURAL: [&str; NB_FACE] = [
+    "Twos", "Threes", "Fours", "Fives", "Sixes", "Sevens", "Eights", "Nines", "Tens", "Jacks", "Queens", "Kings",
+    "Aces",
+];
+
+impl fmt::Display for Face {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", FACE[self.index()])
+    }
+}
+
+impl FromStr for Face {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Face, ParseCardError> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some('1'), Some('0'), None) => Ok(Face::TEN),
+            (Some(c), None, None) => match FACE.iter().position(|x| *x == c.to_ascii_uppercase()) {
+                Some(i) => Ok(Face(i as u8)),
+                None => Err(ParseCardError::InvalidFace(s.to_string())),
+            },
+            _ => Err(ParseCardError::InvalidFace(s.to_string())),
+        }
+    }
+}
+
+/// ## Card suit
+/// From 0 (Clubs) to 3 (Spades), same order as [SUIT](crate::keys::SUIT).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Suit(u8);
+
+impl Suit {
+    pub const CLUBS: Suit = Suit(0);
+    pub const DIAMONDS: Suit = Suit(1);
+    pub const HEARTS: Suit = Suit(2);
+    pub const SPADES: Suit = Suit(3);
+
+    /// Build suit from its index, between 0 and 3
+    pub fn new(s: usize) -> Result<Suit, CardError> {
+        match s {
+            x if x < NB_SUIT => Ok(Suit(x as u8)),
+            _ => Err(CardError::InvalidSuit(s)),
+        }
+    }
+
+    /// Suit index, between 0 and 3
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// All suits, from Clubs to Spades
+    pub fn all() -> impl Iterator<Item = Suit> {
+        (0..NB_SUIT as u8).map(Suit)
+    }
+}
+
+/// Lowercase, e.g. `s` for Spades
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", SUIT[self.index()].to_ascii_lowercase())
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Suit, ParseCardError> {
+        let mut chars = s.chars();
+        let found = match (chars.next(), chars.next()) {
+            (Some(c), None) => SUIT
+                .iter()
+                .position(|x| *x == c.to_ascii_uppercase())
+                .or_else(|| SUIT_SYMBOL.iter().position(|x| *x == c)),
+            _ => None,
+        };
+        match found {
+            Some(i) => Ok(Suit(i as u8)),
+            None => Err(ParseCardError::InvalidSuit(s.to_string())),
+        }
+    }
+}
+
+/// ## Card
+/// One of the 52 deck cards, stored as its card number between 0 and 51.
+/// Cards are ordered by face then suit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Card(u8);
+
+impl Card {
+    /// Build card from face and suit
+    pub fn new(face: Face, suit: Suit) -> Card {
+        Card(NB_SUIT as u8 * face.0 + suit.0)
+    }
+
+    /// Card face
+    pub fn face(self) -> Face {
+        Face(self.0 / NB_SUIT as u8)
+    }
+
+    /// Card suit
+    pub fn suit(self) -> Suit {
+        Suit(self.0 % NB_SUIT as u8)
+    }
+
+    /// Card number, between 0 and 51
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// All 52 cards, in card number order
+    pub fn all() -> impl Iterator<Item = Card> {
+        (0..DECK_SIZE as u8).map(Card)
+    }
+}
+
+impl TryFrom<usize> for Card {
+    type Error = CardError;
+
+    fn try_from(n: usize) -> Result<Card, CardError> {
+        match n {
+            x if x < DECK_SIZE => Ok(Card(x as u8)),
+            _ => Err(CardError::InvalidCard(n)),
+        }
+    }
+}
+
+impl TryFrom<u32> for Card {
+    type Error = CardError;
+
+    fn try_from(n: u32) -> Result<Card, CardError> {
+        Card::try_from(n as usize)
+    }
+}
+
+/// Face then lowercase suit, e.g. `As`
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.face(), self.suit())
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Card, ParseCardError> {
+        match parse_cards(s)?.as_slice() {
+            [c] => Ok(*c),
+            _ => Err(ParseCardError::InvalidCard(s.to_string())),
+        }
+    }
+}
+
+impl From<Card> for usize {
+    fn from(c: Card) -> usize {
+        c.index()
+    }
+}
+
+impl From<Card> for u32 {
+    fn from(c: Card) -> u32 {
+        c.0 as u32
+    }
+}
+
+/// ## Hand
+/// Set of distinct cards, stored as a 52-bit mask.
+/// Cards are iterated in card number order.
+///
+/// ## Example
+/// ```
+/// use poker_eval::card::Hand;
+/// use poker_eval::eval::seven::{build_tables, get_rank};
+///
+/// let t7 = build_tables(false);
+///
+/// let hand = Hand::try_from([5u32, 4, 18, 31, 34, 48, 22].as_slice()).unwrap();
+/// let rank = get_rank(&t7, hand.to_array().unwrap());
+/// assert_eq!(rank, 1689);
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Hand(u64);
+
+impl Hand {
+    /// Empty hand
+    pub fn new() -> Hand {
+        Hand(0)
+    }
+
+    /// Add a card - fails if already in hand
+    pub fn insert(&mut self, card: Card) -> Result<(), CardError> {
+        if self.contains(card) {
+            return Err(CardError::DuplicateCard(card.index()));
+        }
+        self.0 |= 1 << card.0;
+        Ok(())
+    }
+
+    /// Remove a card - returns whether it was in hand
+    pub fn remove(&mut self, card: Card) -> bool {
+        let found = self.contains(card);
+        self.0 &= !(1 << card.0);
+        found
+    }
+
+    /// Whether card is in hand
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & (1 << card.0) != 0
+    }
+
+    /// Number of cards
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Whether hand has no card
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over cards in card number order
+    pub fn iter(&self) -> impl Iterator<Item = Card> + '_ {
+        Card::all().filter(|c| self.contains(*c))
+    }
+
+    /// Card numbers, in card number order
+    pub fn to_vec(&self) -> Vec<u32> {
+        self.iter().map(u32::from).collect()
+    }
+
+    /// Card numbers as fixed size array - as expected by eg. [get_rank](crate::eval::seven::get_rank)
+    pub fn to_array<const N: usize>(&self) -> Result<[usize; N], CardError> {
+        let mut arr = [0; N];
+        if self.len() != N {
+            return Err(CardError::InvalidNbCard(N, self.len()));
+        }
+        for (i, c) in self.iter().enumerate() {
+            arr[i] = c.index();
+        }
+        Ok(arr)
+    }
+}
+
+/// Cards from highest to lowest separated by a space, e.g. `Ah Kh Th`
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cards = self.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+        cards.reverse();
+        write!(f, "{}", cards.join(" "))
+    }
+}
+
+impl FromStr for Hand {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Hand, ParseCardError> {
+        let mut hand = Hand::new();
+        for c in parse_cards(s)? {
+            if hand.insert(c).is_err() {
+                return Err(ParseCardError::DuplicateCard(c.to_string()));
+            }
+        }
+        Ok(hand)
+    }
+}
+
+impl TryFrom<&[Card]> for Hand {
+    type Error = CardError;
+
+    fn try_from(cards: &[Card]) -> Result<Hand, CardError> {
+        let mut hand = Hand::new();
+        for c in cards.iter() {
+            hand.insert(*c)?;
+        }
+        Ok(hand)
+    }
+}
+
+impl TryFrom<&[u32]> for Hand {
+    type Error = CardError;
+
+    fn try_from(cards: &[u32]) -> Result<Hand, CardError> {
+        let mut hand = Hand::new();
+        for c in cards.iter() {
+            hand.insert(Card::try_from(*c)?)?;
+        }
+        Ok(hand)
+    }
+}
+
+impl TryFrom<&[usize]> for Hand {
+    type Error = CardError;
+
+    fn try_from(cards: &[usize]) -> Result<Hand, CardError> {
+        let mut hand = Hand::new();
+        for c in cards.iter() {
+            hand.insert(Card::try_from(*c)?)?;
+        }
+        Ok(hand)
+    }
+}
+
+/// ## Parse a list of cards
+/// Cards are kept in input order, e.g. to distinguish flop, turn and river.  
+/// They may be concatenated and/or separated by spaces or commas: `"AsKd"`, `"Ah Kh Qh"`, `"10s,9s"`.  
+/// Distinctness is not checked - see [Hand] for that.
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    let mut cards = Vec::new();
+
+    for token in s
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+    {
+        let chars = token.char_indices().collect::<Vec<(usize, char)>>();
+        let mut i = 0;
+        while i < chars.len() {
+            // face is 1 char, or 2 for "10"
+            let face_len = match (chars[i].1, chars.get(i + 1)) {
+                ('1', Some((_, '0'))) => 2,
+                _ => 1,
+            };
+            let start = chars[i].0;
+            let end = chars.get(i + face_len + 1).map_or(token.len(), |(j, _)| *j);
+            let card_token = &token[start..end];
+
+            if i + face_len >= chars.len() {
+                return Err(ParseCardError::InvalidCard(card_token.to_string()));
+            }
+            let split = chars[i + face_len].0;
+            let face = token[start..split]
+                .parse::<Face>()
+                .map_err(|_| ParseCardError::InvalidCard(card_token.to_string()))?;
+            let suit = token[split..end]
+                .parse::<Suit>()
+                .map_err(|_| ParseCardError::InvalidCard(card_token.to_string()))?;
+            cards.push(Card::new(face, suit));
+
+            i += face_len + 1;
+        }
+    }
+
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{parse_cards, Card, CardError, Face, Hand, ParseCardError, Suit};
+    use crate::keys;
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_card_normal() {
+        is_normal::<Face>();
+        is_normal::<Suit>();
+        is_normal::<Card>();
+        is_normal::<Hand>();
+    }
+
+    #[test]
+    fn card_layout() {
+        let pk = keys::build();
+
+        for (n, c) in Card::all().enumerate() {
+            assert_eq!(c.index(), n);
+            assert_eq!(c.face().index(), pk.card_face[n]);
+            assert_eq!(c.suit().index(), pk.card_suit[n]);
+            assert_eq!(Card::new(c.face(), c.suit()), c);
+            assert_eq!(Card::try_from(n as u32), Ok(c));
+            assert_eq!(u32::from(c), n as u32);
+        }
+        assert_eq!(Card::new(Face::ACE, Suit::SPADES).index(), 51);
+        assert_eq!(Card::new(Face::TWO, Suit::CLUBS).index(), 0);
+    }
+
+    #[test]
+    fn card_invalid() {
+        assert_eq!(Face::new(13), Err(CardError::InvalidFace(13)));
+        assert_eq!(Suit::new(4), Err(CardError::InvalidSuit(4)));
+        assert_eq!(Card::try_from(52u32), Err(CardError::InvalidCard(52)));
+    }
+
+    #[test]
+    fn hand_cards() {
+        let mut hand = Hand::try_from([50usize, 6, 0].as_slice()).unwrap();
+        assert_eq!(hand.len(), 3);
+        assert_eq!(hand.to_vec(), vec![0, 6, 50]);
+        assert_eq!(
+            hand.insert(Card::try_from(6u32).unwrap()),
+            Err(CardError::DuplicateCard(6))
+        );
+        assert_eq!(hand.to_array::<5>(), Err(CardError::InvalidNbCard(5, 3)));
+        assert!(hand.remove(Card::try_from(6u32).unwrap()));
+        assert_eq!(hand.to_array::<2>(), Ok([0, 50]));
+        assert_eq!(Hand::try_from([1u32, 1].as_slice()), Err(CardError::DuplicateCard(1)));
+    }
+
+    #[test]
+    fn card_parse() {
+        let pk = keys::build();
+
+        for c in Card::all() {
+            assert_eq!(c.to_string().parse::<Card>(), Ok(c));
+            assert_eq!(c.to_string().to_uppercase().parse::<Card>(), Ok(c));
+            assert_eq!(pk.card_sy[&c.index()].parse::<Card>(), Ok(c));
+        }
+
+        let ace_spades = Card::new(Face::ACE, Suit::SPADES);
+        let ten_spades = Card::new(Face::TEN, Suit::SPADES);
+        for (s, c) in [
+            ("As", ace_spades),
+            ("AS", ace_spades),
+            ("as", ace_spades),
+            ("a♠", ace_spades),
+            ("Ts", ten_spades),
+            ("10s", ten_spades),
+            ("10♠", ten_spades),
+        ] {
+            assert_eq!(s.parse::<Card>(), Ok(c), "-> fails: s={}", s);
+        }
+
+        for (s, e) in [
+            ("", ParseCardError::InvalidCard("".to_string())),
+            ("A", ParseCardError::InvalidCard("A".to_string())),
+            ("Ax", ParseCardError::InvalidCard("Ax".to_string())),
+            ("1s", ParseCardError::InvalidCard("1s".to_string())),
+            ("AsKd", ParseCardError::InvalidCard("AsKd".to_string())),
+        ] {
+            assert_eq!(s.parse::<Card>(), Err(e), "-> fails: s={}", s);
+        }
+    }
+
+    #[test]
+    fn hand_parse() {
+        let cards = parse_cards("Ah Kh, Qh").unwrap();
+        assert_eq!(
+            cards.iter().map(|c| c.index()).collect::<Vec<usize>>(),
+            vec![50, 46, 42]
+        );
+        assert_eq!(parse_cards("AsKd").unwrap(), parse_cards("As Kd").unwrap());
+        assert_eq!(parse_cards("10s9s").unwrap(), parse_cards("Ts,9s").unwrap());
+        assert_eq!(parse_cards("  ").unwrap(), vec![]);
+
+        let hand = "2c Ah 10d".parse::<Hand>().unwrap();
+        assert_eq!(hand.to_vec(), vec![0, 33, 50]);
+        assert_eq!(hand.to_string(), "Ah Td 2c");
+        assert_eq!(hand.to_string().parse::<Hand>(), Ok(hand));
+
+        assert_eq!(
+            "AsKdQx".parse::<Hand>(),
+            Err(ParseCardError::InvalidCard("Qx".to_string()))
+        );
+        assert_eq!(
+            "As Kd Q".parse::<Hand>(),
+            Err(ParseCardError::InvalidCard("Q".to_string()))
+        );
+        assert_eq!(
+            "As Kd as".parse::<Hand>(),
+            Err(ParseCardError::DuplicateCard("As".to_string()))
+        );
+    }
+}
@@ -1,5 +1,15 @@
 //! Hand equity evalutation, based on module [keys](crate::keys)
 
+pub mod accumulator;
+pub mod batch;
 pub mod five;
+pub mod low;
+pub mod omaha;
+pub mod packed;
+pub mod persist;
+pub mod rank;
 pub mod seven;
+pub mod six;
+#[cfg(feature = "static-tables")]
+pub mod tables;
 pub mod target;
@@ -0,0 +1,215 @@
+//! ## Incremental hand evaluation
+//! [HandAccumulator] holds the partial key sums of a hand, so cards are added one at a time in O(1),
+//! and the rank is looked up once the hand has 5, 6 or 7 cards.
+//!
+//! In enumeration loops, the hole cards and the board prefix are summed once, then extended for each runout,
+//! instead of summing all 7 card keys for every hand as [get_rank](crate::eval::seven::get_rank) does.
+//!
+//! ```
+//! use poker_eval::eval::accumulator::HandAccumulator;
+//! use poker_eval::eval::seven::build_tables;
+//!
+//! let t7 = build_tables(false);
+//!
+//! let hole = HandAccumulator::from_cards(&[5, 4]);
+//! let flop = HandAccumulator::from_cards(&[18, 31, 34]);
+//! let turn = flop.with(48);
+//!
+//! assert_eq!((hole + flop).rank(&t7), 1550);
+//! assert_eq!((hole + turn).rank(&t7), 1689);
+//! assert_eq!((hole + turn.with(22)).rank(&t7), 1689);
+//! ```
+
+use std::ops::Add;
+
+use crate::eval::seven::TableSeven;
+use crate::keys::{FACE_FIVE_KEY, FACE_SEVEN_KEY, FLUSH_SEVEN_KEY, NB_SUIT, SUIT_BIT_SHIFT, SUIT_KEY, SUIT_MASK};
+
+/// ## Partial hand key sums
+/// + `key`: sum of 7-card face and suit keys, as in [Keys::card_face_key](crate::keys::Keys::card_face_key)
+/// + `face_five_key`: sum of 5-card face keys - only used for 5-card hands
+/// + `flush_key[suit]`: sum of 7-card flush keys of the cards of this suit
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct HandAccumulator {
+    key: u32,
+    face_five_key: u32,
+    flush_key: [u32; NB_SUIT],
+    nb_card: u32,
+}
+
+impl HandAccumulator {
+    /// Empty hand
+    pub const fn new() -> HandAccumulator {
+        HandAccumulator {
+            key: 0,
+            face_five_key: 0,
+            flush_key: [0; NB_SUIT],
+            nb_card: 0,
+        }
+    }
+
+    /// Hand from cards
+    pub fn from_cards(cards: &[usize]) -> HandAccumulator {
+        let mut acc = HandAccumulator::new();
+        for card in cards.iter() {
+            acc.push(*card);
+        }
+        acc
+    }
+
+    /// ## Add a card
+    /// Cards must be distinct and at most 7.
+    #[inline]
+    pub fn push(&mut self, card: usize) {
+        let (face, suit) = (card / NB_SUIT, card % NB_SUIT);
+        self.key += (FACE_SEVEN_KEY[face] << SUIT_BIT_SHIFT) + SUIT_KEY[suit];
+        self.face_five_key += FACE_FIVE_KEY[face];
+        self.flush_key[suit] += FLUSH_SEVEN_KEY[face];
+        self.nb_card += 1;
+    }
+
+    /// Copy with a card added
+    #[inline]
+    pub fn with(mut self, card: usize) -> HandAccumulator {
+        self.push(card);
+        self
+    }
+
+    /// Number of cards
+    pub fn len(&self) -> usize {
+        self.nb_card as usize
+    }
+
+    /// True if no card
+    pub fn is_empty(&self) -> bool {
+        self.nb_card == 0
+    }
+
+    /// ## Hand rank
+    /// Same as [get_rank_five](crate::eval::five::get_rank_five), [get_rank_six](crate::eval::six::get_rank_six) or [get_rank](crate::eval::seven::get_rank), by number of cards.
+    ///
+    /// Panics if the hand has not 5, 6 or 7 cards.
+    #[inline]
+    pub fn rank(&self, t7: &TableSeven) -> u32 {
+        match self.nb_card {
+            7 => self.rank_seven(t7),
+            6 => self.rank_six(t7),
+            5 => self.rank_five(t7),
+            n => panic!("invalid nb cards: {} - must be 5, 6 or 7", n),
+        }
+    }
+
+    /// 7-card hand rank - same lookups as [get_rank](crate::eval::seven::get_rank)
+    #[inline]
+    pub fn rank_seven(&self, t7: &TableSeven) -> u32 {
+        let hand_suit = t7.flush_suit[(self.key & SUIT_MASK) as usize];
+        if hand_suit == -1 {
+            t7.face_rank.get(self.key >> SUIT_BIT_SHIFT)
+        } else {
+            t7.flush_rank[self.flush_key[hand_suit as usize] as usize] as u32
+        }
+    }
+
+    /// 6-card hand rank - same lookups as [get_rank_six](crate::eval::six::get_rank_six)
+    #[inline]
+    pub fn rank_six(&self, t7: &TableSeven) -> u32 {
+        let hand_suit = t7.flush_six_suit[(self.key & SUIT_MASK) as usize];
+        if hand_suit == -1 {
+            t7.face_six_rank.get(self.key >> SUIT_BIT_SHIFT)
+        } else {
+            t7.flush_rank[self.flush_key[hand_suit as usize] as usize] as u32
+        }
+    }
+
+    /// 5-card hand rank - same result as [get_rank_five](crate::eval::five::get_rank_five)
+    ///
+    /// The suit key sums of 5 cards are unique, so the hand is a flush if it is 5 times a suit key.
+    /// The flush table `flush_rank` covers 5 cards of the same suit.
+    #[inline]
+    pub fn rank_five(&self, t7: &TableSeven) -> u32 {
+        let hand_suit_key = self.key & SUIT_MASK;
+        match (0..NB_SUIT).find(|s| hand_suit_key == 5 * SUIT_KEY[*s]) {
+            Some(s) => t7.flush_rank[self.flush_key[s] as usize] as u32,
+            None => t7.t5.face_five_rank.get(self.face_five_key),
+        }
+    }
+}
+
+/// Union of two hands with distinct cards, e.g. hole cards + board
+impl Add for HandAccumulator {
+    type Output = HandAccumulator;
+
+    #[inline]
+    fn add(self, other: HandAccumulator) -> HandAccumulator {
+        HandAccumulator {
+            key: self.key + other.key,
+            face_five_key: self.face_five_key + other.face_five_key,
+            flush_key: [0, 1, 2, 3].map(|s| self.flush_key[s] + other.flush_key[s]),
+            nb_card: self.nb_card + other.nb_card,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::seq::SliceRandom;
+
+    use super::HandAccumulator;
+    use crate::eval::five::get_rank_five;
+    use crate::eval::seven::{build_tables, get_rank};
+    use crate::eval::six::get_rank_six;
+    use crate::keys::DECK_SIZE;
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_accumulator_normal() {
+        is_normal::<HandAccumulator>();
+    }
+
+    #[test]
+    fn eval_accumulator() {
+        let t7 = build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+
+        for _ in 0..100_000 {
+            deck.shuffle(&mut rng);
+            let c = &deck[0..7];
+
+            let hole = HandAccumulator::from_cards(&c[0..2]);
+            let flop = HandAccumulator::from_cards(&c[2..5]);
+            let turn = flop.with(c[5]);
+            let river = turn.with(c[6]);
+
+            assert_eq!(
+                (hole + flop).rank(&t7),
+                get_rank_five(&t7.t5, c[0..5].try_into().unwrap()),
+                "-> fails: cards={:?}",
+                c
+            );
+            assert_eq!(
+                (hole + turn).rank(&t7),
+                get_rank_six(&t7, c[0..6].try_into().unwrap()),
+                "-> fails: cards={:?}",
+                c
+            );
+            assert_eq!(
+                (hole + river).rank(&t7),
+                get_rank(&t7, c.try_into().unwrap()),
+                "-> fails: cards={:?}",
+                c
+            );
+            assert_eq!(hole + river, HandAccumulator::from_cards(c));
+            assert_eq!((hole + river).len(), 7);
+        }
+
+        // royal flush of each suit
+        for s in 0..4 {
+            let c = [48 + s, 44 + s, 40 + s, 36 + s, 32 + s];
+            let acc = HandAccumulator::from_cards(&c);
+            assert_eq!(acc.rank(&t7), get_rank_five(&t7.t5, c), "-> fails: cards={:?}", c);
+            assert_eq!(acc.rank(&t7), 7461);
+        }
+    }
+}
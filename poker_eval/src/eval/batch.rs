@@ -0,0 +1,315 @@
+//! ## Batch 7-card hand evaluation
+//! Evaluate many 7-card hands in one call, e.g. for endpoint `/rank-seven` of poker_server:
+//! - [get_rank_batch]: hands as an array of structs `&[[usize; 7]]`
+//! - [get_rank_batch_soa]: hands as a struct of arrays [HandBatch], one column per card
+//! - [get_rank_batch_soa_scalar]: same, always on the scalar path, e.g. to compare with AVX2
+//!
+//! Same result as [get_rank] for each hand.
+//!
+//! [get_rank_batch] evaluates the hands one by one, so it is as fast as a loop over [get_rank].
+//! [get_rank_batch_soa] processes the hands by chunks of [CHUNK], in passes over the chunk:
+//! + sum the card keys, one card column at a time: independent per hand, so auto-vectorized by the compiler
+//! + lookup the rank in the packed face table, for all hands - the face key of any 7 cards is valid
+//! + lookup the flush suit, and for flush hands only, the rank in the flush table
+//!
+//! On x86_64 with AVX2 available at runtime, the sums are computed 8 hands at a time with `std::arch` gathers.
+//! Else the scalar path is used.
+//!
+//! Benchmark `just bench-batch`, 10M random hands, release build, single core - best of 5 runs:
+//!
+//! | function                           |   M hands/s |
+//! | ---------------------------------- | ----------: |
+//! | loop over [get_rank]               |   90 to 107 |
+//! | [get_rank_batch]                   |   95 to 114 |
+//! | [get_rank_batch_soa_scalar]        |  120 to 125 |
+//! | [get_rank_batch_soa], AVX2         |  183 to 196 |
+//!
+//! ```
+//! use poker_eval::eval::batch::{get_rank_batch, get_rank_batch_soa, HandBatch};
+//! use poker_eval::eval::seven::build_tables;
+//!
+//! let t7 = build_tables(false);
+//! let hands = [[5, 4, 18, 31, 34, 48, 22], [8, 29, 4, 11, 32, 18, 19]];
+//!
+//! let mut ranks = [0; 2];
+//! get_rank_batch(&t7, &hands, &mut ranks);
+//! assert_eq!(ranks, [1689, 4231]);
+//!
+//! let batch = HandBatch::try_from(&hands[..]).unwrap();
+//! let mut ranks = [0; 2];
+//! get_rank_batch_soa(&t7, &batch, &mut ranks);
+//! assert_eq!(ranks, [1689, 4231]);
+//! ```
+
+use std::iter::zip;
+
+use crate::card::CardError;
+use crate::eval::seven::{get_rank, TableSeven};
+use crate::keys::DECK_SIZE;
+
+/// Number of hands per chunk
+pub const CHUNK: usize = 64;
+
+/// ## Batch of 7-card hands as struct of arrays
+/// cards[i][j] = card i of hand j
+///
+/// Cards are checked on insertion, so they are valid indices in the lookup tables, and distinct in each hand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HandBatch {
+    cards: [Vec<u32>; 7],
+}
+
+impl HandBatch {
+    /// Empty batch
+    pub fn new() -> HandBatch {
+        HandBatch::default()
+    }
+
+    /// Empty batch with capacity for `n` hands
+    pub fn with_capacity(n: usize) -> HandBatch {
+        HandBatch {
+            cards: std::array::from_fn(|_| Vec::with_capacity(n)),
+        }
+    }
+
+    /// Add a hand - fails if a card is not in the deck or is repeated
+    pub fn push(&mut self, c: [usize; 7]) -> Result<(), CardError> {
+        let mut used = 0u64;
+        for card in c.iter() {
+            if *card >= DECK_SIZE {
+                return Err(CardError::InvalidCard(*card));
+            }
+            if used & 1 << card != 0 {
+                return Err(CardError::DuplicateCard(*card));
+            }
+            used |= 1 << card;
+        }
+        for (col, card) in zip(self.cards.iter_mut(), c) {
+            col.push(card as u32);
+        }
+        Ok(())
+    }
+
+    /// Number of hands
+    pub fn len(&self) -> usize {
+        self.cards[0].len()
+    }
+
+    /// True if no hand
+    pub fn is_empty(&self) -> bool {
+        self.cards[0].is_empty()
+    }
+
+    /// Hand `j`
+    pub fn hand(&self, j: usize) -> [usize; 7] {
+        self.cards.each_ref().map(|col| col[j] as usize)
+    }
+}
+
+impl TryFrom<&[[usize; 7]]> for HandBatch {
+    type Error = CardError;
+
+    fn try_from(hands: &[[usize; 7]]) -> Result<HandBatch, CardError> {
+        let mut batch = HandBatch::with_capacity(hands.len());
+        for c in hands.iter() {
+            batch.push(*c)?;
+        }
+        Ok(batch)
+    }
+}
+
+/// ## Evaluate 7-card hand ranks by batch
+/// ranks[j] = get_rank(t7, hands[j]) - see [get_rank]
+///
+/// Panics if `hands` and `ranks` have different lengths.
+pub fn get_rank_batch(t7: &TableSeven, hands: &[[usize; 7]], ranks: &mut [u32]) {
+    assert_eq!(hands.len(), ranks.len(), "hands and ranks must have the same length");
+
+    for (rank, c) in zip(ranks.iter_mut(), hands) {
+        *rank = get_rank(t7, *c);
+    }
+}
+
+/// ## Evaluate 7-card hand ranks by batch, as struct of arrays
+/// ranks[j] = get_rank(t7, batch.hand(j)) - see [get_rank]
+///
+/// Panics if `batch` and `ranks` have different lengths.
+pub fn get_rank_batch_soa(t7: &TableSeven, batch: &HandBatch, ranks: &mut [u32]) {
+    #[cfg(target_arch = "x86_64")]
+    let avx2 = is_x86_feature_detected!("avx2");
+    #[cfg(not(target_arch = "x86_64"))]
+    let avx2 = false;
+
+    rank_batch_soa(t7, batch, ranks, avx2);
+}
+
+/// ## Evaluate 7-card hand ranks by batch, as struct of arrays, without AVX2
+/// Same as [get_rank_batch_soa], the key sums being always scalar.
+///
+/// Panics if `batch` and `ranks` have different lengths.
+pub fn get_rank_batch_soa_scalar(t7: &TableSeven, batch: &HandBatch, ranks: &mut [u32]) {
+    rank_batch_soa(t7, batch, ranks, false);
+}
+
+/// Ranks by chunks - `avx2` must be true only if AVX2 is available
+fn rank_batch_soa(t7: &TableSeven, batch: &HandBatch, ranks: &mut [u32], avx2: bool) {
+    assert_eq!(batch.len(), ranks.len(), "batch and ranks must have the same length");
+
+    let card_face_key = &t7.t5.pk.card_face_key;
+    let mut keys = [0; CHUNK];
+
+    for (k, ranks) in ranks.chunks_mut(CHUNK).enumerate() {
+        let start = k * CHUNK;
+        let cols = batch.cards.each_ref().map(|col| &col[start..start + ranks.len()]);
+        let keys = &mut keys[..ranks.len()];
+
+        #[cfg(target_arch = "x86_64")]
+        if avx2 {
+            // SAFETY: avx2 is available, and all cards are < DECK_SIZE by HandBatch::push
+            unsafe { sum_keys_avx2(card_face_key, cols, keys) };
+        } else {
+            sum_keys(card_face_key, cols, keys);
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            debug_assert!(!avx2);
+            sum_keys(card_face_key, cols, keys);
+        }
+
+        lookup_ranks(t7, keys, |j| batch.hand(start + j), ranks);
+    }
+}
+
+/// keys[j] = sum of card keys of hand j - scalar, auto-vectorized
+fn sum_keys(card_face_key: &[u32; DECK_SIZE], cols: [&[u32]; 7], keys: &mut [u32]) {
+    keys.fill(0);
+    for col in cols.iter() {
+        for (key, card) in zip(keys.iter_mut(), col.iter()) {
+            *key += card_face_key[*card as usize];
+        }
+    }
+}
+
+/// keys[j] = sum of card keys of hand j - 8 hands at a time with AVX2 gathers
+///
+/// # Safety
+/// AVX2 must be available, and all cards must be < DECK_SIZE.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn sum_keys_avx2(card_face_key: &[u32; DECK_SIZE], cols: [&[u32]; 7], keys: &mut [u32]) {
+    use std::arch::x86_64::{
+        __m256i, _mm256_add_epi32, _mm256_i32gather_epi32, _mm256_loadu_si256, _mm256_setzero_si256,
+        _mm256_storeu_si256,
+    };
+
+    let n = keys.len();
+    let base = card_face_key.as_ptr() as *const i32;
+
+    let mut j = 0;
+    while j + 8 <= n {
+        let mut acc = _mm256_setzero_si256();
+        for col in cols.iter() {
+            let idx = _mm256_loadu_si256(col.as_ptr().add(j) as *const __m256i);
+            acc = _mm256_add_epi32(acc, _mm256_i32gather_epi32::<4>(base, idx));
+        }
+        _mm256_storeu_si256(keys.as_mut_ptr().add(j) as *mut __m256i, acc);
+        j += 8;
+    }
+
+    for (key, j) in zip(keys[j..].iter_mut(), j..n) {
+        *key = cols.iter().map(|col| card_face_key[col[j] as usize]).sum();
+    }
+}
+
+/// ranks[j] = rank of hand j from its key - hand(j) is only used for flush hands
+fn lookup_ranks(t7: &TableSeven, keys: &[u32], hand: impl Fn(usize) -> [usize; 7], ranks: &mut [u32]) {
+    let card_flush_key = &t7.t5.pk.card_flush_key;
+    let card_suit = &t7.t5.pk.card_suit;
+    let suit_mask = t7.t5.pk.suit_mask;
+    let suit_bit_shift = t7.t5.pk.suit_bit_shift;
+
+    // branchless: the face key of any 7 cards is in the face table
+    for (key, rank) in zip(keys.iter(), ranks.iter_mut()) {
+        *rank = t7.face_rank.get(key >> suit_bit_shift);
+    }
+
+    for (j, (key, rank)) in zip(keys.iter(), ranks.iter_mut()).enumerate() {
+        let hand_suit = t7.flush_suit[(key & suit_mask) as usize];
+        if hand_suit != -1 {
+            let hand_flush_key = hand(j)
+                .iter()
+                .filter(|card| card_suit[**card] == hand_suit as usize)
+                .map(|card| card_flush_key[*card])
+                .sum::<u32>();
+            *rank = t7.flush_rank[hand_flush_key as usize] as u32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::seq::SliceRandom;
+
+    use super::{get_rank_batch, get_rank_batch_soa, get_rank_batch_soa_scalar, HandBatch, CHUNK};
+    use crate::card::CardError;
+    use crate::eval::seven::{build_tables, get_rank};
+    use crate::keys::DECK_SIZE;
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_batch_normal() {
+        is_normal::<HandBatch>();
+    }
+
+    #[test]
+    fn eval_batch() {
+        let t7 = build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+
+        // not a multiple of CHUNK nor of 8
+        let n = 100 * CHUNK + 13;
+        let hands = (0..n)
+            .map(|_| {
+                deck.shuffle(&mut rng);
+                deck[0..7].try_into().unwrap()
+            })
+            .collect::<Vec<[usize; 7]>>();
+        let ranks_want = hands.iter().map(|c| get_rank(&t7, *c)).collect::<Vec<u32>>();
+
+        let mut ranks = vec![0; n];
+        get_rank_batch(&t7, &hands, &mut ranks);
+        assert_eq!(ranks, ranks_want, "-> fails: get_rank_batch");
+
+        let batch = HandBatch::try_from(hands.as_slice()).unwrap();
+        assert_eq!(batch.len(), n);
+        assert_eq!(batch.hand(7), hands[7]);
+
+        let mut ranks = vec![0; n];
+        get_rank_batch_soa(&t7, &batch, &mut ranks);
+        assert_eq!(ranks, ranks_want, "-> fails: get_rank_batch_soa");
+
+        let mut ranks = vec![0; n];
+        get_rank_batch_soa_scalar(&t7, &batch, &mut ranks);
+        assert_eq!(ranks, ranks_want, "-> fails: get_rank_batch_soa_scalar");
+    }
+
+    #[test]
+    fn batch_invalid() {
+        let mut batch = HandBatch::new();
+        assert!(matches!(
+            batch.push([0, 1, 2, 3, 4, 5, 52]),
+            Err(CardError::InvalidCard(52))
+        ));
+        assert!(matches!(
+            batch.push([0, 1, 2, 3, 4, 5, 3]),
+            Err(CardError::DuplicateCard(3))
+        ));
+        assert!(matches!(
+            HandBatch::try_from([[0, 1, 2, 3, 4, 5, 6], [7, 7, 8, 9, 10, 11, 12]].as_slice()),
+            Err(CardError::DuplicateCard(7))
+        ));
+        assert!(batch.is_empty());
+    }
+}
@@ -1,11 +1,14 @@
 //! ## 5-card hand evaluation
 //! Contains the following functions:
 //! - [build_tables]: build the lookup tables for 5-card hand evaluation
+//! - [build_tables_variant]: same for a game [Variant], e.g. short-deck
 //! - [get_rank_five]: get the rank of a 5-card hand
 
+use super::packed::PackedTable;
+use super::rank::HandCategory;
 use super::target::HandStats;
-use crate::keys;
-use std::{collections::HashMap, iter::zip, time::Instant};
+use crate::keys::{self, Variant};
+use std::{iter::zip, time::Instant};
 
 #[cfg(feature = "serde")]
 use serde::Serialize;
@@ -19,17 +22,19 @@ pub struct TableFive {
     // keys
     pub pk: keys::Keys,
     // flush_five_rank[hand_flush_key] = rank
-    pub flush_five_rank: Vec<u32>,
-    // face_five_rank[hand_face_key] = rank
-    pub face_five_rank: Vec<u32>,
+    pub flush_five_rank: Vec<u16>,
+    // face_five_rank.get(hand_face_key) = rank - packed, see [packed](crate::eval::packed)
+    pub face_five_rank: PackedTable,
     // hand_faces[i] = [f1, f2, f3, f4, f5]
-    pub hand_faces: Vec<[usize; 5]>,
-    // hand_type[i] = "high-card", "one-pair", etc.
-    pub hand_type: Vec<String>,
+    pub hand_faces: Vec<[u8; 5]>,
+    // hand_type[i] = HighCard, OnePair, etc.
+    pub hand_type: Vec<HandCategory>,
     // number of hand ranks
     pub nb_hand_five_rank: u32,
-    // hand stats
-    pub hands: HashMap<String, HandStats>,
+    // hands[category as usize] = hand stats
+    pub hands: Vec<HandStats>,
+    // game variant
+    pub variant: Variant,
 }
 
 /// ## Build lookup tables for 5-card hand evaluation
@@ -54,23 +59,39 @@ pub struct TableFive {
 /// Thus all possible 5-card hands are assigned a rank.
 ///
 pub fn build_tables(verbose: bool) -> TableFive {
+    build_tables_variant(Variant::Standard, verbose)
+}
+
+/// ## Build lookup tables for 5-card hand evaluation of a game variant
+/// Same as [build_tables], for the faces of the variant deck and in its hand type value order:
+/// + [Variant::Standard]: as [build_tables]
+/// + [Variant::ShortDeck]: faces 6 to A, A-6-7-8-9 is the lowest straight (and straight flush), flush beats full house
+///
+/// The keys are the same, so [get_rank_five] is unchanged.
+/// `hands` is still indexed by [HandCategory], and `hand_type` gives the category of a rank for all variants.
+pub fn build_tables_variant(variant: Variant, verbose: bool) -> TableFive {
     let start = Instant::now();
 
     let pk = keys::build();
     let face_key = pk.face_five_key;
     let flush_key = pk.flush_five_key;
     let nb_face = pk.nb_face;
+    // lowest face
+    let f0 = variant.min_face();
 
     let mut t5 = TableFive {
         pk: pk.clone(),
         flush_five_rank: vec![0; pk.max_flush_five_key as usize + 1],
-        face_five_rank: vec![0; pk.max_face_five_key as usize + 1],
+        face_five_rank: PackedTable::default(),
         hand_faces: Vec::new(),
         hand_type: Vec::new(),
         nb_hand_five_rank: 0,
-        hands: HashMap::new(),
+        hands: Vec::new(),
+        variant,
     };
 
+    let mut face_five_rank = Vec::new();
+
     let mut rank = 0;
 
     // High Card
@@ -80,17 +101,17 @@ pub fn build_tables(verbose: bool) -> TableFive {
         nb_hand: 0,
         nb_occur: 0,
     };
-    for (f1, &k1) in zip(4.., face_key[4..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., face_key[0..f1].iter()) {
-            for (f3, &k3) in zip(0.., face_key[0..f2].iter()) {
-                for (f4, &k4) in zip(0.., face_key[0..f3].iter()) {
-                    for (f5, &k5) in zip(0.., face_key[0..f4].iter()) {
-                        // No straights, including A2345
-                        if !((f1 - f5 == 4) || (f1 == 12 && f2 == 3)) {
+    for (f1, &k1) in zip(f0 + 4.., face_key[f0 + 4..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., face_key[f0..f1].iter()) {
+            for (f3, &k3) in zip(f0.., face_key[f0..f2].iter()) {
+                for (f4, &k4) in zip(f0.., face_key[f0..f3].iter()) {
+                    for (f5, &k5) in zip(f0.., face_key[f0..f4].iter()) {
+                        // No straights, including A2345 - A6789 in short deck
+                        if !((f1 - f5 == 4) || (f1 == 12 && f2 == f0 + 3)) {
                             let hand_face_key = k1 + k2 + k3 + k4 + k5;
-                            t5.face_five_rank[hand_face_key as usize] = rank;
-                            t5.hand_faces.push([f1, f2, f3, f4, f5]);
-                            t5.hand_type.push("high-card".to_string());
+                            face_five_rank.push((hand_face_key, rank));
+                            t5.hand_faces.push([f1, f2, f3, f4, f5].map(|f| f as u8));
+                            t5.hand_type.push(HandCategory::HighCard);
                             rank += 1;
                         }
                     }
@@ -100,7 +121,7 @@ pub fn build_tables(verbose: bool) -> TableFive {
     }
     hand_high_card.max_rank = rank - 1;
     hand_high_card.nb_hand = hand_high_card.max_rank - hand_high_card.min_rank + 1;
-    t5.hands.insert("high-card".to_string(), hand_high_card);
+    t5.hands.push(hand_high_card);
 
     // One Pair
     let mut hand_one_pair = HandStats {
@@ -109,16 +130,16 @@ pub fn build_tables(verbose: bool) -> TableFive {
         nb_hand: 0,
         nb_occur: 0,
     };
-    for (f1, &k1) in zip(0.., face_key[0..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., face_key[0..nb_face].iter()) {
-            for (f3, &k3) in zip(0.., face_key[0..f2].iter()) {
-                for (f4, &k4) in zip(0.., face_key[0..f3].iter()) {
+    for (f1, &k1) in zip(f0.., face_key[f0..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., face_key[f0..nb_face].iter()) {
+            for (f3, &k3) in zip(f0.., face_key[f0..f2].iter()) {
+                for (f4, &k4) in zip(f0.., face_key[f0..f3].iter()) {
                     // No Three of a Kind
                     if !((f1 == f2) || (f1 == f3) || (f1 == f4)) {
                         let hand_face_key = 2 * k1 + k2 + k3 + k4;
-                        t5.face_five_rank[hand_face_key as usize] = rank;
-                        t5.hand_faces.push([f1, f1, f2, f3, f4]);
-                        t5.hand_type.push("one-pair".to_string());
+                        face_five_rank.push((hand_face_key, rank));
+                        t5.hand_faces.push([f1, f1, f2, f3, f4].map(|f| f as u8));
+                        t5.hand_type.push(HandCategory::OnePair);
                         rank += 1;
                     }
                 }
@@ -127,7 +148,7 @@ pub fn build_tables(verbose: bool) -> TableFive {
     }
     hand_one_pair.max_rank = rank - 1;
     hand_one_pair.nb_hand = hand_one_pair.max_rank - hand_one_pair.min_rank + 1;
-    t5.hands.insert("one-pair".to_string(), hand_one_pair);
+    t5.hands.push(hand_one_pair);
 
     // Two Pairs
     let mut hand_two_pairs = HandStats {
@@ -136,15 +157,15 @@ pub fn build_tables(verbose: bool) -> TableFive {
         nb_hand: 0,
         nb_occur: 0,
     };
-    for (f1, &k1) in zip(0.., face_key[0..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., face_key[0..f1].iter()) {
-            for (f3, &k3) in zip(0.., face_key[0..nb_face].iter()) {
+    for (f1, &k1) in zip(f0.., face_key[f0..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., face_key[f0..f1].iter()) {
+            for (f3, &k3) in zip(f0.., face_key[f0..nb_face].iter()) {
                 // No Three of a Kind
                 if !((f1 == f3) || (f2 == f3)) {
                     let hand_face_key = 2 * k1 + 2 * k2 + k3;
-                    t5.face_five_rank[hand_face_key as usize] = rank;
-                    t5.hand_faces.push([f1, f1, f2, f2, f3]);
-                    t5.hand_type.push("two-pairs".to_string());
+                    face_five_rank.push((hand_face_key, rank));
+                    t5.hand_faces.push([f1, f1, f2, f2, f3].map(|f| f as u8));
+                    t5.hand_type.push(HandCategory::TwoPairs);
                     rank += 1;
                 }
             }
@@ -152,7 +173,7 @@ pub fn build_tables(verbose: bool) -> TableFive {
     }
     hand_two_pairs.max_rank = rank - 1;
     hand_two_pairs.nb_hand = hand_two_pairs.max_rank - hand_two_pairs.min_rank + 1;
-    t5.hands.insert("two-pairs".to_string(), hand_two_pairs);
+    t5.hands.push(hand_two_pairs);
 
     // Three of a kind
     let mut hand_three_of_a_kind = HandStats {
@@ -161,15 +182,15 @@ pub fn build_tables(verbose: bool) -> TableFive {
         nb_hand: 0,
         nb_occur: 0,
     };
-    for (f1, &k1) in zip(0.., face_key[0..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., face_key[0..nb_face].iter()) {
-            for (f3, &k3) in zip(0.., face_key[0..f2].iter()) {
+    for (f1, &k1) in zip(f0.., face_key[f0..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., face_key[f0..nb_face].iter()) {
+            for (f3, &k3) in zip(f0.., face_key[f0..f2].iter()) {
                 // No Four of a Kind
                 if !((f1 == f2) || (f1 == f3)) {
                     let hand_face_key = 3 * k1 + k2 + k3;
-                    t5.face_five_rank[hand_face_key as usize] = rank;
-                    t5.hand_faces.push([f1, f1, f1, f2, f3]);
-                    t5.hand_type.push("three-of-a-kind".to_string());
+                    face_five_rank.push((hand_face_key, rank));
+                    t5.hand_faces.push([f1, f1, f1, f2, f3].map(|f| f as u8));
+                    t5.hand_type.push(HandCategory::ThreeOfAKind);
                     rank += 1;
                 }
             }
@@ -177,7 +198,7 @@ pub fn build_tables(verbose: bool) -> TableFive {
     }
     hand_three_of_a_kind.max_rank = rank - 1;
     hand_three_of_a_kind.nb_hand = hand_three_of_a_kind.max_rank - hand_three_of_a_kind.min_rank + 1;
-    t5.hands.insert("three-of-a-kind".to_string(), hand_three_of_a_kind);
+    t5.hands.push(hand_three_of_a_kind);
 
     // Straight
     let mut hand_straight = HandStats {
@@ -188,79 +209,94 @@ pub fn build_tables(verbose: bool) -> TableFive {
     };
 
     // Low Straight
-    let f1 = 3;
+    let f1 = f0 + 3;
     let f5 = 12;
     let hand_face_key = face_key[f1] + face_key[f1 - 1] + face_key[f1 - 2] + face_key[f1 - 3] + face_key[f5];
-    t5.face_five_rank[hand_face_key as usize] = rank;
-    t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f5]);
-    t5.hand_type.push("straight".to_string());
+    face_five_rank.push((hand_face_key, rank));
+    t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f5].map(|f| f as u8));
+    t5.hand_type.push(HandCategory::Straight);
     rank += 1;
 
     // Other Straight
-    for f1 in 4..nb_face {
+    for f1 in f0 + 4..nb_face {
         let hand_face_key = face_key[f1] + face_key[f1 - 1] + face_key[f1 - 2] + face_key[f1 - 3] + face_key[f1 - 4];
-        t5.face_five_rank[hand_face_key as usize] = rank;
-        t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f1 - 4]);
-        t5.hand_type.push("straight".to_string());
+        face_five_rank.push((hand_face_key, rank));
+        t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f1 - 4].map(|f| f as u8));
+        t5.hand_type.push(HandCategory::Straight);
         rank += 1;
     }
 
     hand_straight.max_rank = rank - 1;
     hand_straight.nb_hand = hand_straight.max_rank - hand_straight.min_rank + 1;
-    t5.hands.insert("straight".to_string(), hand_straight);
+    t5.hands.push(hand_straight);
 
-    // Flush
-    let mut hand_flush = HandStats {
-        min_rank: rank,
-        max_rank: rank,
-        nb_hand: 0,
-        nb_occur: 0,
+    // Flush and Full House - in value order of the variant
+    let order = match variant {
+        Variant::Standard => [HandCategory::Flush, HandCategory::FullHouse],
+        Variant::ShortDeck => [HandCategory::FullHouse, HandCategory::Flush],
     };
-    for (f1, &k1) in zip(4.., flush_key[4..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., flush_key[0..f1].iter()) {
-            for (f3, &k3) in zip(0.., flush_key[0..f2].iter()) {
-                for (f4, &k4) in zip(0.., flush_key[0..f3].iter()) {
-                    for (f5, &k5) in zip(0.., flush_key[0..f4].iter()) {
-                        // No straights, including A2345
-                        if !((f1 - f5 == 4) || (f1 == 12 && f2 == 3)) {
-                            let hand_flush_key = k1 + k2 + k3 + k4 + k5;
-                            t5.flush_five_rank[hand_flush_key as usize] = rank;
-                            t5.hand_faces.push([f1, f2, f3, f4, f5]);
-                            t5.hand_type.push("flush".to_string());
+    for category in order {
+        match category {
+            HandCategory::Flush => {
+                let mut hand_flush = HandStats {
+                    min_rank: rank,
+                    max_rank: rank,
+                    nb_hand: 0,
+                    nb_occur: 0,
+                };
+                for (f1, &k1) in zip(f0 + 4.., flush_key[f0 + 4..nb_face].iter()) {
+                    for (f2, &k2) in zip(f0.., flush_key[f0..f1].iter()) {
+                        for (f3, &k3) in zip(f0.., flush_key[f0..f2].iter()) {
+                            for (f4, &k4) in zip(f0.., flush_key[f0..f3].iter()) {
+                                for (f5, &k5) in zip(f0.., flush_key[f0..f4].iter()) {
+                                    // No straights, including A2345 - A6789 in short deck
+                                    if !((f1 - f5 == 4) || (f1 == 12 && f2 == f0 + 3)) {
+                                        let hand_flush_key = k1 + k2 + k3 + k4 + k5;
+                                        t5.flush_five_rank[hand_flush_key as usize] = rank as u16;
+                                        t5.hand_faces.push([f1, f2, f3, f4, f5].map(|f| f as u8));
+                                        t5.hand_type.push(HandCategory::Flush);
+                                        rank += 1;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                hand_flush.max_rank = rank - 1;
+                hand_flush.nb_hand = hand_flush.max_rank - hand_flush.min_rank + 1;
+                t5.hands.push(hand_flush);
+            }
+            HandCategory::FullHouse => {
+                let mut hand_full_house = HandStats {
+                    min_rank: rank,
+                    max_rank: rank,
+                    nb_hand: 0,
+                    nb_occur: 0,
+                };
+                for (f1, &k1) in zip(f0.., face_key[f0..nb_face].iter()) {
+                    for (f2, &k2) in zip(f0.., face_key[f0..nb_face].iter()) {
+                        // No Four of a Kind
+                        if !(f1 == f2) {
+                            let hand_face_key = 3 * k1 + 2 * k2;
+                            face_five_rank.push((hand_face_key, rank));
+                            t5.hand_faces.push([f1, f1, f1, f2, f2].map(|f| f as u8));
+                            t5.hand_type.push(HandCategory::FullHouse);
                             rank += 1;
                         }
                     }
                 }
+                hand_full_house.max_rank = rank - 1;
+                hand_full_house.nb_hand = hand_full_house.max_rank - hand_full_house.min_rank + 1;
+                t5.hands.push(hand_full_house);
             }
+            _ => unreachable!(),
         }
     }
-
-    hand_flush.max_rank = rank - 1;
-    hand_flush.nb_hand = hand_flush.max_rank - hand_flush.min_rank + 1;
-    t5.hands.insert("flush".to_string(), hand_flush);
-
-    // Full House
-    let mut hand_full_house = HandStats {
-        min_rank: rank,
-        max_rank: rank,
-        nb_hand: 0,
-        nb_occur: 0,
-    };
-    for (f1, &k1) in zip(0.., face_key[0..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., face_key[0..nb_face].iter()) {
-            // No Four of a Kind
-            if !(f1 == f2) {
-                let hand_face_key = 3 * k1 + 2 * k2;
-                t5.face_five_rank[hand_face_key as usize] = rank;
-                t5.hand_faces.push([f1, f1, f1, f2, f2]);
-                t5.hand_type.push("full-house".to_string());
-                rank += 1;
-            }
-        }
+    // hands[category as usize]
+    if variant == Variant::ShortDeck {
+        t5.hands.swap(HandCategory::Flush as usize, HandCategory::FullHouse as usize);
     }
-    hand_full_house.max_rank = rank - 1;
-    hand_full_house.nb_hand = hand_full_house.max_rank - hand_full_house.min_rank + 1;
-    t5.hands.insert("full-house".to_string(), hand_full_house);
 
     // Four of a Kind
     let mut hand_four_of_a_kind = HandStats {
@@ -269,21 +305,21 @@ pub fn build_tables(verbose: bool) -> TableFive {
         nb_hand: 0,
         nb_occur: 0,
     };
-    for (f1, &k1) in zip(0.., face_key[0..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., face_key[0..nb_face].iter()) {
+    for (f1, &k1) in zip(f0.., face_key[f0..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., face_key[f0..nb_face].iter()) {
             // No Five of a Kind
             if !(f1 == f2) {
                 let hand_face_key = 4 * k1 + k2;
-                t5.face_five_rank[hand_face_key as usize] = rank;
-                t5.hand_faces.push([f1, f1, f1, f1, f2]);
-                t5.hand_type.push("four-of-a-kind".to_string());
+                face_five_rank.push((hand_face_key, rank));
+                t5.hand_faces.push([f1, f1, f1, f1, f2].map(|f| f as u8));
+                t5.hand_type.push(HandCategory::FourOfAKind);
                 rank += 1;
             }
         }
     }
     hand_four_of_a_kind.max_rank = rank - 1;
     hand_four_of_a_kind.nb_hand = hand_four_of_a_kind.max_rank - hand_four_of_a_kind.min_rank + 1;
-    t5.hands.insert("four-of-a-kind".to_string(), hand_four_of_a_kind);
+    t5.hands.push(hand_four_of_a_kind);
 
     // Straight Flush
     let mut hand_straight_flush = HandStats {
@@ -294,34 +330,35 @@ pub fn build_tables(verbose: bool) -> TableFive {
     };
 
     // Low Straight Flush
-    let f1 = 3;
+    let f1 = f0 + 3;
     let f5 = 12;
     let hand_flush_key = flush_key[f1] + flush_key[f1 - 1] + flush_key[f1 - 2] + flush_key[f1 - 3] + flush_key[f5];
-    t5.flush_five_rank[hand_flush_key as usize] = rank;
-    t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f5]);
-    t5.hand_type.push("straight-flush".to_string());
+    t5.flush_five_rank[hand_flush_key as usize] = rank as u16;
+    t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f5].map(|f| f as u8));
+    t5.hand_type.push(HandCategory::StraightFlush);
     rank += 1;
 
     // Other Straight Flush
-    for f1 in 4..nb_face {
+    for f1 in f0 + 4..nb_face {
         let hand_flush_key =
             flush_key[f1] + flush_key[f1 - 1] + flush_key[f1 - 2] + flush_key[f1 - 3] + flush_key[f1 - 4];
-        t5.flush_five_rank[hand_flush_key as usize] = rank;
-        t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f1 - 4]);
-        t5.hand_type.push("straight-flush".to_string());
+        t5.flush_five_rank[hand_flush_key as usize] = rank as u16;
+        t5.hand_faces.push([f1, f1 - 1, f1 - 2, f1 - 3, f1 - 4].map(|f| f as u8));
+        t5.hand_type.push(HandCategory::StraightFlush);
         rank += 1;
     }
 
     hand_straight_flush.max_rank = rank - 1;
     hand_straight_flush.nb_hand = hand_straight_flush.max_rank - hand_straight_flush.min_rank + 1;
-    t5.hands.insert("straight-flush".to_string(), hand_straight_flush);
+    t5.hands.push(hand_straight_flush);
 
+    t5.face_five_rank = PackedTable::build(&face_five_rank);
     t5.nb_hand_five_rank = rank;
 
     let end = Instant::now();
 
     if verbose {
-        println!("five::build_tables runtime = {:?}", (end - start));
+        println!("five::build_tables runtime = {:?} variant = {}", (end - start), variant);
         println!("nb_hand_five_rank = {}", t5.nb_hand_five_rank);
     }
 
@@ -368,11 +405,11 @@ pub fn get_rank_five(t5: &TableFive, c: [usize; 5]) -> u32 {
     if (s1 == s2) && (s1 == s3) && (s1 == s4) && (s1 == s5) {
         // Flush
         let hand_flush_key = flush_key[f1] + flush_key[f2] + flush_key[f3] + flush_key[f4] + flush_key[f5];
-        rank = t5.flush_five_rank[hand_flush_key as usize];
+        rank = t5.flush_five_rank[hand_flush_key as usize] as u32;
     } else {
         // Not Flush
         let hand_face_key = face_key[f1] + face_key[f2] + face_key[f3] + face_key[f4] + face_key[f5];
-        rank = t5.face_five_rank[hand_face_key as usize];
+        rank = t5.face_five_rank.get(hand_face_key);
     }
     return rank;
 }
@@ -380,7 +417,9 @@ pub fn get_rank_five(t5: &TableFive, c: [usize; 5]) -> u32 {
 #[cfg(test)]
 mod tests {
 
-    use super::{build_tables, get_rank_five, TableFive};
+    use super::{build_tables, build_tables_variant, get_rank_five, TableFive};
+    use crate::eval::rank::HandCategory;
+    use crate::keys::Variant;
     use crate::util::is_normal;
 
     #[test]
@@ -450,4 +489,63 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn eval_five_short_deck() {
+        let t5 = build_tables_variant(Variant::ShortDeck, true);
+        assert_eq!(t5.nb_hand_five_rank, 1404);
+
+        // expected number of 5-card hands from the 36-card deck
+        let want = [
+            (HandCategory::HighCard, 122_400),
+            (HandCategory::OnePair, 193_536),
+            (HandCategory::TwoPairs, 36_288),
+            (HandCategory::ThreeOfAKind, 16_128),
+            (HandCategory::Straight, 6_120),
+            (HandCategory::FullHouse, 1_728),
+            (HandCategory::Flush, 480),
+            (HandCategory::FourOfAKind, 288),
+            (HandCategory::StraightFlush, 24),
+        ];
+
+        let deck = Variant::ShortDeck.deck();
+        let mut nb_occur = [0u32; 9];
+        let mut min_rank = [u32::MAX; 9];
+        for (i1, &c1) in deck.iter().enumerate() {
+            for (i2, &c2) in deck[..i1].iter().enumerate() {
+                for (i3, &c3) in deck[..i2].iter().enumerate() {
+                    for (i4, &c4) in deck[..i3].iter().enumerate() {
+                        for &c5 in deck[..i4].iter() {
+                            let rank = get_rank_five(&t5, [c1, c2, c3, c4, c5]);
+                            let category = t5.hand_type[rank as usize] as usize;
+                            nb_occur[category] += 1;
+                            min_rank[category] = min_rank[category].min(rank);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut prev_rank = None;
+        for (category, nb) in want {
+            let c = category as usize;
+            assert_eq!(nb_occur[c], nb, "-> fails: category={:?}", category);
+            assert_eq!(min_rank[c], t5.hands[c].min_rank, "-> fails: category={:?}", category);
+            // categories are in value order of the variant
+            assert!(prev_rank < Some(min_rank[c]), "-> fails: category={:?}", category);
+            prev_rank = Some(t5.hands[c].max_rank);
+        }
+
+        // A6789 is the lowest straight, above trips and below 6789T
+        let low_straight = get_rank_five(&t5, [48, 17, 20, 24, 29]);
+        assert_eq!(low_straight, t5.hands[HandCategory::Straight as usize].min_rank);
+        assert!(low_straight < get_rank_five(&t5, [17, 20, 24, 29, 32]));
+
+        // A6789 suited is the lowest straight flush
+        let low_straight_flush = get_rank_five(&t5, [48, 16, 20, 24, 28]);
+        assert_eq!(
+            low_straight_flush,
+            t5.hands[HandCategory::StraightFlush as usize].min_rank
+        );
+    }
 }
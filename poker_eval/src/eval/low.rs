@@ -0,0 +1,401 @@
+//! ## Lowball hand evaluation
+//! Low hands, where the lowest hand wins, e.g. for Razz, 2-7 Triple Draw or the low half of split games:
+//! + Ace-to-Five: aces are low, straights and flushes do not count, the best hand is A2345
+//! + Deuce-to-Seven: aces are high, straights and flushes count, the best hand is 75432 not suited
+//!
+//! The lookup keys are the same key sums as in [five](crate::eval::five) and [seven](crate::eval::seven).
+//! Only the rank assignment differs: hands are ranked from the worst low to the best low.
+//! So, as for high hands, the greater the rank the better the hand.
+//!
+//! Contains the following:
+//! - [build_tables]: build the lowball lookup tables
+//! - [rank_a5_low] and [rank_a5_low_seven]: Ace-to-Five low rank of 5 or 7 cards
+//! - [rank_27_low] and [rank_27_low_seven]: Deuce-to-Seven low rank of 5 or 7 cards
+//! - [is_eight_or_better]: the 8-or-better qualifier for an Ace-to-Five low rank
+//!
+//! ```
+//! use poker_eval::eval::low::{build_tables, is_eight_or_better, rank_27_low, rank_a5_low, rank_a5_low_seven};
+//!
+//! let tl = build_tables(false);
+//!
+//! // 5d 4c 3h 2c As - the wheel, best Ace-to-Five low
+//! assert_eq!(rank_a5_low(&tl, [13, 8, 6, 0, 51]), 6174);
+//!
+//! // 7c 5d 4c 3h 2c - best Deuce-to-Seven low
+//! assert_eq!(rank_27_low(&tl, [20, 13, 8, 6, 0]), 7461);
+//!
+//! // Razz: Kc Kd 8h 6s 4c 3d Ah - 8-6-4-3-A qualifies
+//! let rank = rank_a5_low_seven(&tl, [44, 45, 26, 19, 8, 5, 50]);
+//! assert!(is_eight_or_better(rank));
+//! ```
+
+use std::{collections::HashMap, time::Instant};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::packed::PackedTable;
+use super::rank::HandCategory;
+use crate::keys::{FACE_FIVE_KEY, FACE_SEVEN_KEY, FLUSH_FIVE_KEY, MAX_FLUSH_FIVE_KEY, NB_FACE, NB_SUIT};
+
+/// Number of Ace-to-Five low ranks
+pub const NB_A5_LOW_RANK: u32 = 6175;
+/// Number of Deuce-to-Seven low ranks
+pub const NB_27_LOW_RANK: u32 = 7462;
+/// Min Ace-to-Five low rank of an 8-or-better low, i.e. rank of 87654
+pub const A5_EIGHT_OR_BETTER_MIN_RANK: u32 = 6119;
+
+/// ## Lookup tables for lowball hand evaluation
+/// + build in function [build_tables]
+/// + used in functions [rank_a5_low], [rank_a5_low_seven], [rank_27_low] and [rank_27_low_seven]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TableLow {
+    // a5_five_rank.get(hand_face_five_key) = Ace-to-Five rank
+    pub a5_five_rank: PackedTable,
+    // a5_seven_rank.get(hand_face_seven_key) = Ace-to-Five rank of the best 5 of 7 cards
+    pub a5_seven_rank: PackedTable,
+    // d27_face_rank.get(hand_face_five_key) = Deuce-to-Seven rank, not flush
+    pub d27_face_rank: PackedTable,
+    // d27_flush_rank[hand_flush_five_key] = Deuce-to-Seven rank, flush
+    pub d27_flush_rank: Vec<u16>,
+}
+
+/// Lowball game
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Lowball {
+    AceToFive,
+    DeuceToSeven,
+}
+
+/// ## Value of a 5-card hand, as a high hand
+/// For the face order of the lowball game: the greater the value, the worse the low.
+/// + category: straights and flushes only for Deuce-to-Seven
+/// + faces: by count then face, both descending
+fn high_value(game: Lowball, faces: &[usize], flush: bool) -> (HandCategory, [usize; 5]) {
+    let mut v = faces
+        .iter()
+        .map(|f| match game {
+            // ace is the lowest face
+            Lowball::AceToFive => (f + 1) % NB_FACE,
+            Lowball::DeuceToSeven => *f,
+        })
+        .collect::<Vec<usize>>();
+
+    let mut count = [0usize; NB_FACE];
+    for f in v.iter() {
+        count[*f] += 1;
+    }
+    v.sort_by_key(|f| std::cmp::Reverse((count[*f], *f)));
+    let pattern = v.iter().map(|f| count[*f]).collect::<Vec<usize>>();
+
+    let straight = game == Lowball::DeuceToSeven && pattern[0] == 1 && v[0] - v[4] == 4;
+    let flush = game == Lowball::DeuceToSeven && flush;
+
+    let category = match (pattern.as_slice(), straight, flush) {
+        ([4, 4, 4, 4, 1], _, _) => HandCategory::FourOfAKind,
+        ([3, 3, 3, 2, 2], _, _) => HandCategory::FullHouse,
+        ([3, 3, 3, 1, 1], _, _) => HandCategory::ThreeOfAKind,
+        ([2, 2, 2, 2, 1], _, _) => HandCategory::TwoPairs,
+        ([2, 2, 1, 1, 1], _, _) => HandCategory::OnePair,
+        (_, true, true) => HandCategory::StraightFlush,
+        (_, false, true) => HandCategory::Flush,
+        (_, true, false) => HandCategory::Straight,
+        (_, false, false) => HandCategory::HighCard,
+    };
+
+    (category, v.try_into().unwrap())
+}
+
+/// All face multisets of `k` faces, at most 4 of each, in non increasing order
+fn face_multisets(k: usize) -> Vec<Vec<usize>> {
+    let mut all = vec![vec![]];
+    for _ in 0..k {
+        let mut next = vec![];
+        for faces in all.iter() {
+            let f_max = faces.last().copied().unwrap_or(NB_FACE - 1);
+            for f in 0..=f_max {
+                if faces.iter().filter(|x| **x == f).count() < 4 {
+                    let mut faces_ = faces.clone();
+                    faces_.push(f);
+                    next.push(faces_);
+                }
+            }
+        }
+        all = next;
+    }
+    all
+}
+
+/// ## Build lookup tables for lowball hand evaluation
+/// All 5-card hands are gone through and sorted by their value as high hands, for the face order of each game.
+/// Then their rank is their position, from the worst low to the best low.
+///
+/// For Ace-to-Five, suits do not count, so the rank of 7 cards - the best 5 of them - only depends on their faces.
+/// It is precomputed for all 7-card face multisets.
+pub fn build_tables(verbose: bool) -> TableLow {
+    let start = Instant::now();
+
+    let five = face_multisets(5);
+
+    // Ace-to-Five
+    let mut a5 = five
+        .iter()
+        .map(|faces| {
+            let key = faces.iter().map(|f| FACE_FIVE_KEY[*f]).sum::<u32>();
+            (high_value(Lowball::AceToFive, faces, false), key)
+        })
+        .collect::<Vec<_>>();
+    // worst low first
+    a5.sort_by_key(|x| std::cmp::Reverse(x.0));
+    let a5_five_rank = a5
+        .iter()
+        .enumerate()
+        .map(|(rank, (_, key))| (*key, rank as u32))
+        .collect::<HashMap<u32, u32>>();
+
+    let mut a5_seven_rank = vec![];
+    for faces in face_multisets(7) {
+        let key = faces.iter().map(|f| FACE_SEVEN_KEY[*f]).sum::<u32>();
+        let mut max_rank = 0;
+        for i1 in 0..7 {
+            for i2 in 0..i1 {
+                let sub_key = (0..7)
+                    .filter(|i| *i != i1 && *i != i2)
+                    .map(|i| FACE_FIVE_KEY[faces[i]])
+                    .sum::<u32>();
+                max_rank = max_rank.max(a5_five_rank[&sub_key]);
+            }
+        }
+        a5_seven_rank.push((key, max_rank));
+    }
+
+    // Deuce-to-Seven
+    // (value, key, flush) - the key is a flush key for flushes
+    let mut d27 = vec![];
+    for faces in five.iter() {
+        let key = faces.iter().map(|f| FACE_FIVE_KEY[*f]).sum::<u32>();
+        d27.push((high_value(Lowball::DeuceToSeven, faces, false), key, false));
+        if faces.windows(2).all(|w| w[0] != w[1]) {
+            let key = faces.iter().map(|f| FLUSH_FIVE_KEY[*f]).sum::<u32>();
+            d27.push((high_value(Lowball::DeuceToSeven, faces, true), key, true));
+        }
+    }
+    // worst low first
+    d27.sort_by_key(|x| std::cmp::Reverse(x.0));
+
+    let mut d27_face_rank = vec![];
+    let mut d27_flush_rank = vec![0; MAX_FLUSH_FIVE_KEY as usize + 1];
+    for (rank, (_, key, flush)) in d27.iter().enumerate() {
+        match flush {
+            true => d27_flush_rank[*key as usize] = rank as u16,
+            false => d27_face_rank.push((*key, rank as u32)),
+        }
+    }
+
+    let tl = TableLow {
+        a5_five_rank: PackedTable::build(&a5_five_rank.into_iter().collect::<Vec<(u32, u32)>>()),
+        a5_seven_rank: PackedTable::build(&a5_seven_rank),
+        d27_face_rank: PackedTable::build(&d27_face_rank),
+        d27_flush_rank,
+    };
+
+    let end = Instant::now();
+
+    if verbose {
+        println!("low::build_tables runtime = {:?}", (end - start));
+        println!("nb_a5_low_rank = {}", a5.len());
+        println!("nb_27_low_rank = {}", d27.len());
+    }
+
+    tl
+}
+
+/// Get the Ace-to-Five low rank of a 5-card hand
+/// ## Arguments
+/// * `tl`: [TableLow] - must be precalculated
+/// * `c`: 5 cards all distinct integers from 0 to nb_face*nb_suit
+///
+/// From 0 for KKKKQ to 6174 for A2345.
+pub fn rank_a5_low(tl: &TableLow, c: [usize; 5]) -> u32 {
+    let hand_face_key = c.iter().map(|c| FACE_FIVE_KEY[c / NB_SUIT]).sum::<u32>();
+    tl.a5_five_rank.get(hand_face_key)
+}
+
+/// Get the Ace-to-Five low rank of a 7-card hand
+/// That is the rank of its best 5 cards, see [rank_a5_low].
+pub fn rank_a5_low_seven(tl: &TableLow, c: [usize; 7]) -> u32 {
+    let hand_face_key = c.iter().map(|c| FACE_SEVEN_KEY[c / NB_SUIT]).sum::<u32>();
+    tl.a5_seven_rank.get(hand_face_key)
+}
+
+/// Get the Deuce-to-Seven low rank of a 5-card hand
+/// ## Arguments
+/// * `tl`: [TableLow] - must be precalculated
+/// * `c`: 5 cards all distinct integers from 0 to nb_face*nb_suit
+///
+/// From 0 for AKQJT suited to 7461 for 75432 not suited.
+pub fn rank_27_low(tl: &TableLow, c: [usize; 5]) -> u32 {
+    let suit = c[0] % NB_SUIT;
+    if c.iter().all(|c| c % NB_SUIT == suit) {
+        let hand_flush_key = c.iter().map(|c| FLUSH_FIVE_KEY[c / NB_SUIT]).sum::<u32>();
+        tl.d27_flush_rank[hand_flush_key as usize] as u32
+    } else {
+        let hand_face_key = c.iter().map(|c| FACE_FIVE_KEY[c / NB_SUIT]).sum::<u32>();
+        tl.d27_face_rank.get(hand_face_key)
+    }
+}
+
+/// Get the Deuce-to-Seven low rank of a 7-card hand
+/// That is the best [rank_27_low] over its 21 5-card subsets.
+pub fn rank_27_low_seven(tl: &TableLow, c: [usize; 7]) -> u32 {
+    let mut max_rank = 0;
+    for i1 in 0..7 {
+        for i2 in 0..i1 {
+            let mut sub = [0usize; 5];
+            let mut j = 0;
+            for (i, card) in c.iter().enumerate() {
+                if i != i1 && i != i2 {
+                    sub[j] = *card;
+                    j += 1;
+                }
+            }
+            max_rank = max_rank.max(rank_27_low(tl, sub));
+        }
+    }
+    max_rank
+}
+
+/// ## 8-or-better qualifier
+/// True if the Ace-to-Five low rank is 5 distinct faces of 8 or lower, ace being low.
+pub fn is_eight_or_better(rank_a5: u32) -> bool {
+    rank_a5 >= A5_EIGHT_OR_BETTER_MIN_RANK
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::seq::SliceRandom;
+
+    use super::{
+        build_tables, is_eight_or_better, rank_27_low, rank_27_low_seven, rank_a5_low, rank_a5_low_seven, TableLow,
+        A5_EIGHT_OR_BETTER_MIN_RANK, NB_27_LOW_RANK, NB_A5_LOW_RANK,
+    };
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_tl_normal() {
+        is_normal::<TableLow>();
+    }
+
+    #[test]
+    fn eval_a5_low() {
+        let tl = build_tables(true);
+
+        // best and worst
+        assert_eq!(rank_a5_low(&tl, [13, 8, 6, 0, 51]), NB_A5_LOW_RANK - 1);
+        assert_eq!(rank_a5_low(&tl, [44, 45, 46, 47, 40]), 0);
+        // 87654 is the worst 8-or-better
+        assert_eq!(rank_a5_low(&tl, [24, 20, 16, 12, 8]), A5_EIGHT_OR_BETTER_MIN_RANK);
+
+        for (better, worse) in [
+            // 6432A beats 65432: straights do not count
+            ([16, 9, 5, 0, 51], [16, 13, 9, 5, 0]),
+            // 5432A suited beats 6432A: flushes do not count
+            ([12, 8, 4, 0, 48], [16, 9, 5, 0, 51]),
+            // KQJT9 beats AA234: pairs count
+            ([44, 40, 36, 32, 28], [48, 49, 0, 4, 8]),
+            // AA234 beats 22345: ace is low
+            ([48, 49, 0, 4, 8], [0, 1, 4, 8, 12]),
+        ] {
+            assert!(
+                rank_a5_low(&tl, better) > rank_a5_low(&tl, worse),
+                "-> fails: better={:?} worse={:?}",
+                better,
+                worse
+            );
+        }
+
+        // 56 8-or-better lows
+        let mut qualified = (0..NB_A5_LOW_RANK).filter(|r| is_eight_or_better(*r)).count();
+        assert_eq!(qualified, 56);
+
+        qualified = 0;
+        let low_faces = [12, 0, 1, 2, 3, 4, 5, 6];
+        for i1 in 0..8 {
+            for i2 in 0..i1 {
+                for i3 in 0..i2 {
+                    for i4 in 0..i3 {
+                        for i5 in 0..i4 {
+                            let c = [i1, i2, i3, i4, i5].map(|i| low_faces[i] * 4);
+                            if is_eight_or_better(rank_a5_low(&tl, c)) {
+                                qualified += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(qualified, 56);
+    }
+
+    #[test]
+    fn eval_27_low() {
+        let tl = build_tables(false);
+
+        // best and worst
+        assert_eq!(rank_27_low(&tl, [20, 13, 8, 6, 0]), NB_27_LOW_RANK - 1);
+        assert_eq!(rank_27_low(&tl, [48, 44, 40, 36, 32]), 0);
+
+        for (better, worse) in [
+            // 75432 beats 75432 suited: flushes count
+            ([20, 13, 8, 6, 0], [20, 12, 8, 4, 0]),
+            // 87432 beats 65432: straights count
+            ([24, 21, 8, 4, 1], [16, 13, 8, 4, 0]),
+            // A5432 is ace high, not a straight
+            ([48, 13, 8, 4, 1], [0, 1, 4, 8, 12]),
+            // KQJT8 beats A5432: ace is high
+            ([44, 40, 36, 32, 25], [48, 13, 8, 4, 1]),
+            // 65432 straight beats 65432 suited
+            ([16, 13, 8, 4, 0], [16, 12, 8, 4, 0]),
+        ] {
+            assert!(
+                rank_27_low(&tl, better) > rank_27_low(&tl, worse),
+                "-> fails: better={:?} worse={:?}",
+                better,
+                worse
+            );
+        }
+    }
+
+    #[test]
+    fn eval_low_seven() {
+        let tl = build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..52).collect::<Vec<usize>>();
+
+        for _ in 0..10_000 {
+            deck.shuffle(&mut rng);
+            let c: [usize; 7] = deck[0..7].try_into().unwrap();
+
+            // brute force
+            let mut best_a5 = 0;
+            let mut best_27 = 0;
+            for c1 in 0..7 {
+                for c2 in 0..c1 {
+                    let sub: [usize; 5] = (0..7)
+                        .filter(|i| *i != c1 && *i != c2)
+                        .map(|i| c[i])
+                        .collect::<Vec<usize>>()
+                        .try_into()
+                        .unwrap();
+                    best_a5 = best_a5.max(rank_a5_low(&tl, sub));
+                    best_27 = best_27.max(rank_27_low(&tl, sub));
+                }
+            }
+
+            assert_eq!(rank_a5_low_seven(&tl, c), best_a5, "-> fails: cards={:?}", c);
+            assert_eq!(rank_27_low_seven(&tl, c), best_27, "-> fails: cards={:?}", c);
+        }
+    }
+}
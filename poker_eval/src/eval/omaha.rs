@@ -0,0 +1,391 @@
+//! ## Omaha hand evaluation
+//! In Omaha, a hand is made of exactly 2 hole cards and 3 board cards:
+//! + PLO4: 4 hole cards, i.e. 6 hole pairs
+//! + PLO5: 5 hole cards, i.e. 10 hole pairs
+//! + board: 3, 4 or 5 cards, i.e. 1, 4 or 10 board triples
+//!
+//! The rank is the best [get_rank_five](crate::eval::five::get_rank_five) rank over all hole pairs and board triples.
+//!
+//! The key sums of the hole pairs and of the board triples are precomputed, in [OmahaHole] and [OmahaBoard],
+//! with their common suit if any. So each of the up to 100 combinations costs one addition and one lookup.
+//! Typically in an equity calculation, the hole is built once per player, and the board once per runout.
+//!
+//! Contains the following:
+//! - [OmahaHole]: precomputed hole pairs, with the high rank and the 8-or-better low rank
+//! - [OmahaBoard]: precomputed board triples
+//! - [get_rank_omaha]: get the rank of an Omaha hand from its cards - checked
+//!
+//! ```
+//! use poker_eval::eval::five::build_tables;
+//! use poker_eval::eval::omaha::{get_rank_omaha, OmahaBoard, OmahaHole};
+//!
+//! let t5 = build_tables(false);
+//!
+//! // Ah Kh 2c 3d on 5h 8h Jh 2s 2d - a heart flush, not trips
+//! let rank = get_rank_omaha(&t5, &[50, 46, 0, 5], &[14, 26, 38, 3, 1]).unwrap();
+//! assert_eq!(rank, 7078);
+//!
+//! // same from precomputed hole and board
+//! let hole = OmahaHole::new(&[50, 46, 0, 5]).unwrap();
+//! let board = OmahaBoard::new(&[14, 26, 38, 3, 1]).unwrap();
+//! assert_eq!(hole.rank(&t5, &board), rank);
+//! ```
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::eval::five::TableFive;
+use crate::eval::low::{is_eight_or_better, TableLow};
+use crate::keys::{DECK_SIZE, FACE_FIVE_KEY, FLUSH_FIVE_KEY, NB_SUIT};
+
+/// Max number of hole pairs - PLO5
+pub const MAX_HOLE_PAIR: usize = 10;
+/// Max number of board triples - 5 board cards
+pub const MAX_BOARD_TRIPLE: usize = 10;
+
+/// ## Omaha hand error
+/// This error type is used to describe the errors that can occur when describing an Omaha hand.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OmahaError {
+    /// Invalid number of hole cards
+    #[error("invalid nb hole cards: {0} - must be 4 or 5")]
+    InvalidNbHoleCard(usize),
+    /// Invalid number of board cards
+    #[error("invalid nb board cards: {0} - must be 3, 4 or 5")]
+    InvalidNbBoardCard(usize),
+    /// Invalid card
+    #[error("invalid card: {0} - must be between 0 and 51")]
+    InvalidCard(usize),
+    /// Not distinct cards
+    #[error("hole: {0:?} board: {1:?} - all cards must be distinct")]
+    NotDistinctCards(Vec<usize>, Vec<usize>),
+}
+
+/// ## Partial hand
+/// Key sums of 2 hole cards or 3 board cards
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+struct PartialHand {
+    // sum of 5-card face keys
+    face_key: u32,
+    // sum of 5-card flush keys
+    flush_key: u32,
+    // common suit of the cards, or -1
+    suit: i8,
+}
+
+impl PartialHand {
+    fn new(cards: &[usize]) -> PartialHand {
+        let suit = cards[0] % NB_SUIT;
+        PartialHand {
+            face_key: cards.iter().map(|c| FACE_FIVE_KEY[c / NB_SUIT]).sum(),
+            flush_key: cards.iter().map(|c| FLUSH_FIVE_KEY[c / NB_SUIT]).sum(),
+            suit: match cards.iter().all(|c| c % NB_SUIT == suit) {
+                true => suit as i8,
+                false => -1,
+            },
+        }
+    }
+}
+
+/// ## Omaha hole cards
+/// All pairs of 4 or 5 hole cards
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OmahaHole {
+    pairs: [PartialHand; MAX_HOLE_PAIR],
+    nb_pair: usize,
+}
+
+impl OmahaHole {
+    /// ## Build hole pairs
+    /// Fails if not 4 or 5 distinct valid cards.
+    pub fn new(hole: &[usize]) -> Result<OmahaHole, OmahaError> {
+        match hole.len() {
+            4 | 5 => (),
+            n => return Err(OmahaError::InvalidNbHoleCard(n)),
+        }
+        check_cards(hole, &[])?;
+
+        Ok(OmahaHole::from_cards(hole))
+    }
+
+    /// ## Build hole pairs - not checked
+    /// Same as [OmahaHole::new], for cards known to be valid, e.g. in equity calculation loops.
+    ///
+    /// Panics if more than 5 cards.
+    pub fn from_cards(hole: &[usize]) -> OmahaHole {
+        let mut pairs = [PartialHand::default(); MAX_HOLE_PAIR];
+        let mut nb_pair = 0;
+        for i1 in 0..hole.len() {
+            for i2 in 0..i1 {
+                pairs[nb_pair] = PartialHand::new(&[hole[i1], hole[i2]]);
+                nb_pair += 1;
+            }
+        }
+
+        OmahaHole { pairs, nb_pair }
+    }
+
+    /// ## Get the rank of the Omaha hand
+    /// Best rank over all hole pairs and board triples.
+    ///
+    /// The hole and board cards must be distinct - not checked, see [get_rank_omaha].
+    #[inline]
+    pub fn rank(&self, t5: &TableFive, board: &OmahaBoard) -> u32 {
+        let mut max_rank = 0;
+        for t in board.triples[..board.nb_triple].iter() {
+            for p in self.pairs[..self.nb_pair].iter() {
+                let rank = if t.suit >= 0 && t.suit == p.suit {
+                    t5.flush_five_rank[(t.flush_key + p.flush_key) as usize] as u32
+                } else {
+                    t5.face_five_rank.get(t.face_key + p.face_key)
+                };
+                max_rank = max_rank.max(rank);
+            }
+        }
+        max_rank
+    }
+
+    /// ## Get the 8-or-better low rank of the Omaha hand
+    /// Best Ace-to-Five low rank over all hole pairs and board triples, see [low](crate::eval::low).
+    /// None if it does not qualify, i.e. no 2 hole cards and 3 board cards make 5 distinct faces of 8 or lower.
+    ///
+    /// The hole and board cards must be distinct - not checked.
+    #[inline]
+    pub fn rank_low(&self, tl: &TableLow, board: &OmahaBoard) -> Option<u32> {
+        let mut max_rank = 0;
+        for t in board.triples[..board.nb_triple].iter() {
+            for p in self.pairs[..self.nb_pair].iter() {
+                max_rank = max_rank.max(tl.a5_five_rank.get(t.face_key + p.face_key));
+            }
+        }
+        match is_eight_or_better(max_rank) {
+            true => Some(max_rank),
+            false => None,
+        }
+    }
+}
+
+/// ## Omaha board cards
+/// All triples of 3, 4 or 5 board cards
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OmahaBoard {
+    triples: [PartialHand; MAX_BOARD_TRIPLE],
+    nb_triple: usize,
+}
+
+impl OmahaBoard {
+    /// ## Build board triples
+    /// Fails if not 3, 4 or 5 distinct valid cards.
+    pub fn new(board: &[usize]) -> Result<OmahaBoard, OmahaError> {
+        match board.len() {
+            3..=5 => (),
+            n => return Err(OmahaError::InvalidNbBoardCard(n)),
+        }
+        check_cards(&[], board)?;
+
+        Ok(OmahaBoard::from_cards(board))
+    }
+
+    /// ## Build board triples - not checked
+    /// Same as [OmahaBoard::new], for cards known to be valid, e.g. in equity calculation loops.
+    ///
+    /// Panics if more than 5 cards.
+    pub fn from_cards(board: &[usize]) -> OmahaBoard {
+        let mut triples = [PartialHand::default(); MAX_BOARD_TRIPLE];
+        let mut nb_triple = 0;
+        for i1 in 0..board.len() {
+            for i2 in 0..i1 {
+                for i3 in 0..i2 {
+                    triples[nb_triple] = PartialHand::new(&[board[i1], board[i2], board[i3]]);
+                    nb_triple += 1;
+                }
+            }
+        }
+
+        OmahaBoard { triples, nb_triple }
+    }
+}
+
+/// ## Get the rank of an Omaha hand
+/// + `hole`: 4 or 5 cards
+/// + `board`: 3, 4 or 5 cards
+///
+/// All cards must be distinct.
+pub fn get_rank_omaha(t5: &TableFive, hole: &[usize], board: &[usize]) -> Result<u32, OmahaError> {
+    let hole_ = OmahaHole::new(hole)?;
+    let board_ = OmahaBoard::new(board)?;
+    check_cards(hole, board)?;
+
+    Ok(hole_.rank(t5, &board_))
+}
+
+/// Check cards are valid and distinct
+fn check_cards(hole: &[usize], board: &[usize]) -> Result<(), OmahaError> {
+    if let Some(c) = hole.iter().chain(board.iter()).find(|c| **c >= DECK_SIZE) {
+        return Err(OmahaError::InvalidCard(*c));
+    }
+    let all_cards = hole.iter().chain(board.iter()).collect::<HashSet<&usize>>();
+    match all_cards.len() == hole.len() + board.len() {
+        true => Ok(()),
+        false => Err(OmahaError::NotDistinctCards(hole.to_vec(), board.to_vec())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::seq::SliceRandom;
+
+    use super::{get_rank_omaha, OmahaBoard, OmahaError, OmahaHole};
+    use crate::eval::five::{build_tables, get_rank_five, TableFive};
+    use crate::eval::low;
+    use crate::keys::DECK_SIZE;
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_omaha_normal() {
+        is_normal::<OmahaHole>();
+        is_normal::<OmahaBoard>();
+        is_normal::<OmahaError>();
+    }
+
+    /// Best rank over all 2 hole cards and 3 board cards, with get_rank_five
+    fn get_rank_omaha_brute_force(t5: &TableFive, hole: &[usize], board: &[usize]) -> u32 {
+        let mut max_rank = 0;
+        for h1 in 0..hole.len() {
+            for h2 in 0..h1 {
+                for b1 in 0..board.len() {
+                    for b2 in 0..b1 {
+                        for b3 in 0..b2 {
+                            let c = [hole[h1], hole[h2], board[b1], board[b2], board[b3]];
+                            max_rank = max_rank.max(get_rank_five(t5, c));
+                        }
+                    }
+                }
+            }
+        }
+        max_rank
+    }
+
+    #[test]
+    fn eval_omaha() {
+        let t5 = build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+
+        for nb_hole in [4, 5] {
+            for nb_board in [3, 4, 5] {
+                for _ in 0..20_000 {
+                    deck.shuffle(&mut rng);
+                    let (hole, board) = (&deck[0..nb_hole], &deck[nb_hole..nb_hole + nb_board]);
+
+                    let rank = get_rank_omaha(&t5, hole, board);
+                    let rank_want = get_rank_omaha_brute_force(&t5, hole, board);
+                    assert_eq!(rank, Ok(rank_want), "-> fails: hole={:?}, board={:?}", hole, board);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eval_omaha_samples() {
+        let t5 = build_tables(false);
+
+        for (hole, board, rank_want) in [
+            // Ah Kh 2c 3d on 5h 8h Jh 2s 2d: heart flush, not trips
+            (vec![50, 46, 0, 5], vec![14, 26, 38, 3, 1], 7078),
+            // As Ks Qs Js on Ts 9s 8s: straight flush with 2 spades only, QJT98
+            (vec![51, 47, 43, 39], vec![35, 31, 27], 7459),
+            // Ah 2c 3d 4h 5s on Kh Qh Jh: one heart flush AKQJ4
+            (vec![50, 0, 5, 10, 15], vec![46, 42, 38], 7134),
+            // 7c 7d 2h 3s on 7h 7s Kd Kc 9c: quads, K kicker
+            (vec![20, 21, 2, 7], vec![22, 23, 45, 44, 28], 7366),
+        ] {
+            let rank = get_rank_omaha(&t5, &hole, &board);
+            assert_eq!(
+                rank,
+                Ok(rank_want),
+                "-> fails: hole={:?}, board={:?}\nfound:rank={:?}, want:rank={}",
+                hole,
+                board,
+                rank,
+                rank_want
+            );
+            assert_eq!(rank_want, get_rank_omaha_brute_force(&t5, &hole, &board));
+        }
+    }
+
+    #[test]
+    fn eval_omaha_low() {
+        let tl = low::build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+
+        for nb_hole in [4, 5] {
+            for nb_board in [3, 4, 5] {
+                for _ in 0..20_000 {
+                    deck.shuffle(&mut rng);
+                    let (hole, board) = (&deck[0..nb_hole], &deck[nb_hole..nb_hole + nb_board]);
+
+                    // brute force
+                    let mut max_rank = 0;
+                    for h1 in 0..hole.len() {
+                        for h2 in 0..h1 {
+                            for b1 in 0..board.len() {
+                                for b2 in 0..b1 {
+                                    for b3 in 0..b2 {
+                                        let c = [hole[h1], hole[h2], board[b1], board[b2], board[b3]];
+                                        max_rank = max_rank.max(low::rank_a5_low(&tl, c));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    let rank_want = Some(max_rank).filter(|r| low::is_eight_or_better(*r));
+
+                    let rank = OmahaHole::new(hole)
+                        .unwrap()
+                        .rank_low(&tl, &OmahaBoard::new(board).unwrap());
+                    assert_eq!(rank, rank_want, "-> fails: hole={:?}, board={:?}", hole, board);
+                }
+            }
+        }
+
+        // Ac 2d Kh Kd on 3c 4d 8s Qh Qs: 8432A
+        let hole = OmahaHole::new(&[48, 1, 46, 45]).unwrap();
+        let rank = hole.rank_low(&tl, &OmahaBoard::new(&[4, 9, 27, 42, 43]).unwrap());
+        assert_eq!(rank, Some(low::rank_a5_low(&tl, [48, 1, 4, 9, 27])));
+
+        // A 2 3 4 in hand, one low board card only: no low
+        let hole = OmahaHole::new(&[48, 1, 6, 11]).unwrap();
+        let rank = hole.rank_low(&tl, &OmahaBoard::new(&[16, 44, 40, 36, 32]).unwrap());
+        assert_eq!(rank, None);
+    }
+
+    #[test]
+    fn omaha_invalid() {
+        let t5 = build_tables(false);
+
+        assert_eq!(
+            get_rank_omaha(&t5, &[0, 1, 2], &[4, 5, 6]),
+            Err(OmahaError::InvalidNbHoleCard(3))
+        );
+        assert_eq!(
+            get_rank_omaha(&t5, &[0, 1, 2, 3, 7, 8], &[4, 5, 6]),
+            Err(OmahaError::InvalidNbHoleCard(6))
+        );
+        assert_eq!(
+            get_rank_omaha(&t5, &[0, 1, 2, 3], &[4, 5]),
+            Err(OmahaError::InvalidNbBoardCard(2))
+        );
+        assert_eq!(
+            get_rank_omaha(&t5, &[0, 1, 2, 52], &[4, 5, 6]),
+            Err(OmahaError::InvalidCard(52))
+        );
+        assert_eq!(
+            get_rank_omaha(&t5, &[0, 1, 2, 3], &[4, 5, 3]),
+            Err(OmahaError::NotDistinctCards(vec![0, 1, 2, 3], vec![4, 5, 3]))
+        );
+    }
+}
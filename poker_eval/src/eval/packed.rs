@@ -0,0 +1,176 @@
+//! ## Packed lookup table
+//! Compact storage for the sparse face lookup tables, e.g. [TableSeven::face_rank](crate::eval::seven::TableSeven::face_rank).
+//!
+//! The face keys are unique sums, hence spread over a wide range with mostly unused slots:
+//! for 7 cards, 49,205 keys up to 7,825,759.
+//! So instead of a dense table, the keys are split in rows of [ROW_SIZE] consecutive keys,
+//! and the rows are overlaid in a single `rank` array, each at an offset where its used slots are free (row displacement):
+//! + `rank[row_offset[key >> ROW_BITS] + (key & ROW_MASK)] = rank`
+//!
+//! The rows are placed largest first, each at the first offset that fits.
+//! Ranks are stored as `u16`, as there are 7462 of them.
+//!
+//! The lookup costs one more indirection than a dense table, but the whole table fits in the CPU cache.
+//! For 7 cards: 267 kB instead of 31.3 MB - see the crate README for memory and throughput numbers.
+//!
+//! A key that is not a valid hand key returns an arbitrary rank.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Number of bits of a row
+pub const ROW_BITS: u32 = 8;
+/// Number of keys in a row
+pub const ROW_SIZE: usize = 1 << ROW_BITS;
+/// Mask of the column bits of a key
+pub const ROW_MASK: u32 = (1 << ROW_BITS) - 1;
+
+/// ## Packed lookup table
+/// + build in function [PackedTable::build]
+/// + used in function [PackedTable::get]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PackedTable {
+    /// row_offset[key >> ROW_BITS] = offset of the row in `rank`
+    pub row_offset: Vec<u32>,
+    /// rank[row_offset + (key & ROW_MASK)] = rank
+    pub rank: Vec<u16>,
+}
+
+impl PackedTable {
+    /// ## Build packed table
+    /// From all (key, rank) pairs - keys must be distinct.
+    pub fn build(entries: &[(u32, u32)]) -> PackedTable {
+        let max_key = entries.iter().map(|(key, _)| *key).max().unwrap_or(0);
+        let nb_row = (max_key >> ROW_BITS) as usize + 1;
+
+        let mut rows: Vec<Vec<(usize, u16)>> = vec![Vec::new(); nb_row];
+        for (key, rank) in entries.iter() {
+            let rank = u16::try_from(*rank).expect("rank must fit in u16");
+            rows[(key >> ROW_BITS) as usize].push(((key & ROW_MASK) as usize, rank));
+        }
+        for row in rows.iter_mut() {
+            row.sort();
+        }
+
+        // largest rows first
+        let mut order = (0..nb_row).filter(|r| !rows[*r].is_empty()).collect::<Vec<usize>>();
+        order.sort_by_key(|r| std::cmp::Reverse(rows[*r].len()));
+
+        let mut row_offset = vec![0; nb_row];
+        let mut rank = Vec::new();
+        // used[i / 64] bit i % 64 = slot i is used
+        let mut used: Vec<u64> = Vec::new();
+
+        // all slots before first_free are used
+        let mut first_free = 0;
+        // rows with the same columns do not fit before the last one
+        let mut last_slot: HashMap<Vec<usize>, usize> = HashMap::new();
+
+        for r in order {
+            first_free = next_free(&used, first_free);
+
+            // slot of the first column - 64 candidates at a time
+            let cols = rows[r].iter().map(|(col, _)| *col).collect::<Vec<usize>>();
+            let first_col = cols[0];
+            let start = last_slot.get(&cols).map_or(first_free, |slot| slot + 1);
+            let mut slot = start.max(first_col);
+            loop {
+                let mut fit = !0;
+                for col in cols.iter() {
+                    fit &= free_bits(&used, slot - first_col + col);
+                }
+                if fit != 0 {
+                    slot += fit.trailing_zeros() as usize;
+                    break;
+                }
+                slot += 64;
+            }
+            let offset = slot - first_col;
+            last_slot.insert(cols, slot);
+
+            let end = offset + ROW_SIZE;
+            if rank.len() < end {
+                rank.resize(end, 0);
+                used.resize(end.div_ceil(64), 0);
+            }
+            for (col, v) in rows[r].iter() {
+                used[(offset + col) / 64] |= 1 << ((offset + col) % 64);
+                rank[offset + col] = *v;
+            }
+            row_offset[r] = offset as u32;
+        }
+
+        // any column of any row is in bounds
+        rank.resize(rank.len().max(ROW_SIZE), 0);
+
+        PackedTable { row_offset, rank }
+    }
+
+    /// ## Get rank from key
+    #[inline]
+    pub fn get(&self, key: u32) -> u32 {
+        lookup(&self.row_offset, &self.rank, key)
+    }
+
+    /// Memory size in bytes
+    pub fn size_of(&self) -> usize {
+        self.row_offset.len() * std::mem::size_of::<u32>() + self.rank.len() * std::mem::size_of::<u16>()
+    }
+}
+
+/// Free bits of slots i to i + 63
+fn free_bits(used: &[u64], i: usize) -> u64 {
+    let free = |w: usize| used.get(w).map_or(!0, |x| !x);
+    match i % 64 {
+        0 => free(i / 64),
+        b => (free(i / 64) >> b) | (free(i / 64 + 1) << (64 - b)),
+    }
+}
+
+/// First free slot from slot i
+fn next_free(used: &[u64], mut i: usize) -> usize {
+    while let Some(w) = used.get(i / 64) {
+        let free = !w >> (i % 64);
+        if free != 0 {
+            return i + free.trailing_zeros() as usize;
+        }
+        i = (i / 64 + 1) * 64;
+    }
+    i
+}
+
+/// ## Lookup rank in packed arrays
+/// Same as [PackedTable::get], for arrays stored elsewhere, e.g. module `eval::tables` with feature `static-tables`.
+#[inline]
+pub fn lookup(row_offset: &[u32], rank: &[u16], key: u32) -> u32 {
+    let offset = row_offset[(key >> ROW_BITS) as usize];
+    rank[(offset + (key & ROW_MASK)) as usize] as u32
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{PackedTable, ROW_SIZE};
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_packed_normal() {
+        is_normal::<PackedTable>();
+    }
+
+    #[test]
+    fn packed_get() {
+        let entries = (0..10_000u32)
+            .map(|i| (i * i % 1_000_003, i % 7462))
+            .collect::<Vec<(u32, u32)>>();
+        let table = PackedTable::build(&entries);
+
+        for (key, rank) in entries.iter() {
+            assert_eq!(table.get(*key), *rank, "-> fails: key={}", key);
+        }
+        assert!(table.rank.len() < 2 * entries.len() + ROW_SIZE, "-> fails: len={}", table.rank.len());
+    }
+}
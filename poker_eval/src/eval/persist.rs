@@ -0,0 +1,400 @@
+//! ## Persist lookup tables
+//! Save and load the 7-card lookup tables, to skip [seven::build_tables](crate::eval::seven::build_tables) in short-lived processes:
+//! - [TableSeven::save]: write the tables to a binary file
+//! - [TableSeven::load]: read the tables from a binary file
+//!
+//! File format, all integers little endian:
+//! + magic `b"PKR7"`
+//! + format version: u32
+//! + keys fingerprint: u64 - hash of all key constants in [keys](crate::keys) and of the packed row size
+//! + game variant: u32 - 0 standard, 1 short-deck, see [Variant]
+//! + packed tables `face_rank`, `face_six_rank`: each as `row_offset` then `rank`
+//! + tables `flush_rank`, `flush_suit`, `flush_six_suit`
+//!
+//! Each table or array is stored as length u64 then values u32/u16/i8.
+//! + checksum: u64 - hash of all previous bytes
+//!
+//! The 5-card tables are small and fast to build, so they are rebuilt at load time instead of being stored, for the stored variant.
+//! The file is read in one go, which costs a fraction of the build time.
+//! It is not memory-mapped: the packed tables take about 500 kB, so mapping would save little and need an unsafe dependency.
+//!
+//! ```no_run
+//! use poker_eval::eval::seven::{build_tables, TableSeven};
+//!
+//! let t7 = build_tables(false);
+//! t7.save("t7.bin").unwrap();
+//!
+//! let t7 = TableSeven::load("t7.bin").unwrap();
+//! ```
+
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use thiserror::Error;
+
+use crate::eval::five;
+use crate::eval::packed::{self, PackedTable};
+use crate::eval::seven::TableSeven;
+use crate::keys::{self, Variant};
+
+/// File magic
+const MAGIC: [u8; 4] = *b"PKR7";
+/// File format version - to increment when the format changes
+pub const FORMAT_VERSION: u32 = 3;
+
+/// ## Table file error
+/// This error type is used to describe the errors that can occur when saving or loading lookup tables.
+#[derive(Error, Debug)]
+pub enum TableFileError {
+    /// Io error
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Not a table file
+    #[error("invalid magic: {0:?} - not a table file")]
+    InvalidMagic(Vec<u8>),
+    /// Unsupported format version
+    #[error("invalid format version: {0} - must be {FORMAT_VERSION}")]
+    InvalidVersion(u32),
+    /// Built with other keys
+    #[error("invalid keys fingerprint: {0:#x} - must be {1:#x}, file was built with other keys")]
+    InvalidKeys(u64, u64),
+    /// Unknown game variant
+    #[error("invalid variant: {0} - must be 0 (standard) or 1 (short-deck)")]
+    InvalidVariant(u32),
+    /// Truncated file
+    #[error("truncated file: {0} bytes")]
+    Truncated(usize),
+    /// Corrupted file
+    #[error("invalid checksum: {0:#x} - must be {1:#x}, file is corrupted")]
+    InvalidChecksum(u64, u64),
+    /// Table of the wrong length for the keys
+    #[error("invalid table length: {0} has {1} values - must be {2}")]
+    InvalidLength(&'static str, usize, usize),
+    /// Packed row out of the ranks
+    #[error("invalid row offset: {1} in {0} - rows must fit in the {2} ranks")]
+    InvalidOffset(&'static str, u32, usize),
+}
+
+impl TableSeven {
+    /// ## Save lookup tables to file
+    /// See [module](crate::eval::persist) doc for the format.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TableFileError> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&keys_fingerprint().to_le_bytes());
+        bytes.extend_from_slice(&variant_code(self.t5.variant).to_le_bytes());
+
+        for table in [&self.face_rank, &self.face_six_rank] {
+            write_table(&mut bytes, table.row_offset.iter().map(|x| x.to_le_bytes()), table.row_offset.len());
+            write_table(&mut bytes, table.rank.iter().map(|x| x.to_le_bytes()), table.rank.len());
+        }
+        write_table(&mut bytes, self.flush_rank.iter().map(|x| x.to_le_bytes()), self.flush_rank.len());
+        for table in [&self.flush_suit, &self.flush_six_suit] {
+            write_table(&mut bytes, table.iter().map(|x| x.to_le_bytes()), table.len());
+        }
+
+        let checksum = fnv1a(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        file.write_all(&bytes)?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// ## Load lookup tables from file
+    /// Fails if the file is not a table file, has another format version, was built with other keys or is corrupted.  
+    /// The length of each table is checked against the keys, so that any lookup of a valid hand is in bounds.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Arc<TableSeven>, TableFileError> {
+        let bytes = fs::read(path)?;
+        let mut reader = Reader { bytes: &bytes, pos: 0 };
+
+        let magic = reader.take(4)?;
+        if magic != MAGIC {
+            return Err(TableFileError::InvalidMagic(magic.to_vec()));
+        }
+        let version = reader.read_u32()?;
+        if version != FORMAT_VERSION {
+            return Err(TableFileError::InvalidVersion(version));
+        }
+        let fingerprint = reader.read_u64()?;
+        if fingerprint != keys_fingerprint() {
+            return Err(TableFileError::InvalidKeys(fingerprint, keys_fingerprint()));
+        }
+        let variant = match reader.read_u32()? {
+            0 => Variant::Standard,
+            1 => Variant::ShortDeck,
+            code => return Err(TableFileError::InvalidVariant(code)),
+        };
+
+        let face_rank = reader.read_packed()?;
+        let face_six_rank = reader.read_packed()?;
+        let flush_rank = reader.read_table(u16::from_le_bytes)?;
+        let flush_suit = reader.read_table(i8::from_le_bytes)?;
+        let flush_six_suit = reader.read_table(i8::from_le_bytes)?;
+
+        let checksum_want = fnv1a(&bytes[..reader.pos]);
+        let checksum = reader.read_u64()?;
+        if checksum != checksum_want {
+            return Err(TableFileError::InvalidChecksum(checksum, checksum_want));
+        }
+
+        let t5 = five::build_tables_variant(variant, false);
+        let pk = &t5.pk;
+        check_packed("face_rank", &face_rank, pk.max_face_seven_key)?;
+        check_packed("face_six_rank", &face_six_rank, pk.max_face_six_key)?;
+        check_length("flush_rank", flush_rank.len(), pk.max_flush_seven_key as usize + 1)?;
+        check_length("flush_suit", flush_suit.len(), pk.max_suit_key as usize + 1)?;
+        check_length("flush_six_suit", flush_six_suit.len(), pk.max_suit_key as usize + 1)?;
+
+        let t7 = TableSeven {
+            t5,
+            face_rank,
+            flush_rank,
+            flush_suit,
+            face_six_rank,
+            flush_six_suit,
+        };
+
+        Ok(Arc::new(t7))
+    }
+}
+
+/// Hash of all key constants and of the packed row size - tables built with others are invalid
+pub fn keys_fingerprint() -> u64 {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&keys::SUIT_BIT_SHIFT.to_le_bytes());
+    bytes.extend_from_slice(&packed::ROW_BITS.to_le_bytes());
+    for key in keys::SUIT_KEY
+        .iter()
+        .chain(keys::FLUSH_FIVE_KEY.iter())
+        .chain(keys::FLUSH_SEVEN_KEY.iter())
+        .chain(keys::FACE_FIVE_KEY.iter())
+        .chain(keys::FACE_SEVEN_KEY.iter())
+    {
+        bytes.extend_from_slice(&key.to_le_bytes());
+    }
+    fnv1a(&bytes)
+}
+
+/// Check the length of a table
+fn check_length(name: &'static str, len: usize, len_want: usize) -> Result<(), TableFileError> {
+    match len == len_want {
+        true => Ok(()),
+        false => Err(TableFileError::InvalidLength(name, len, len_want)),
+    }
+}
+
+/// Check a packed table: one row per `ROW_SIZE` keys up to `max_key`, each row within the ranks
+fn check_packed(name: &'static str, table: &PackedTable, max_key: u32) -> Result<(), TableFileError> {
+    check_length(name, table.row_offset.len(), (max_key >> packed::ROW_BITS) as usize + 1)?;
+    match table
+        .row_offset
+        .iter()
+        .find(|offset| **offset as usize + packed::ROW_SIZE > table.rank.len())
+    {
+        None => Ok(()),
+        Some(offset) => Err(TableFileError::InvalidOffset(name, *offset, table.rank.len())),
+    }
+}
+
+/// Variant code in file
+fn variant_code(variant: Variant) -> u32 {
+    match variant {
+        Variant::Standard => 0,
+        Variant::ShortDeck => 1,
+    }
+}
+
+/// FNV-1a 64-bit hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn write_table<const N: usize>(bytes: &mut Vec<u8>, values: impl Iterator<Item = [u8; N]>, len: usize) {
+    bytes.reserve(8 + N * len);
+    bytes.extend_from_slice(&(len as u64).to_le_bytes());
+    for v in values {
+        bytes.extend_from_slice(&v);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TableFileError> {
+        if n > self.bytes.len() - self.pos {
+            return Err(TableFileError::Truncated(self.bytes.len()));
+        }
+        let slice = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, TableFileError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, TableFileError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn read_table<T, const N: usize>(&mut self, f: fn([u8; N]) -> T) -> Result<Vec<T>, TableFileError> {
+        let len = self.read_u64()? as usize;
+        let data = self.take(len.saturating_mul(N))?;
+        Ok(data.chunks_exact(N).map(|x| f(x.try_into().unwrap())).collect())
+    }
+
+    fn read_packed(&mut self) -> Result<PackedTable, TableFileError> {
+        let row_offset = self.read_table(u32::from_le_bytes)?;
+        let rank = self.read_table(u16::from_le_bytes)?;
+        Ok(PackedTable { row_offset, rank })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+
+    use super::{fnv1a, TableFileError};
+    use crate::eval::five;
+    use crate::eval::seven::{build_tables, build_tables_variant, TableSeven};
+    use crate::keys::Variant;
+
+    #[test]
+    fn save_load() {
+        let t7 = build_tables(false);
+        let path = std::env::temp_dir().join(format!("poker_eval_t7_{}.bin", std::process::id()));
+
+        t7.save(&path).unwrap();
+        let t7_loaded = TableSeven::load(&path).unwrap();
+
+        assert_eq!(t7_loaded.face_rank, t7.face_rank);
+        assert_eq!(t7_loaded.flush_rank, t7.flush_rank);
+        assert_eq!(t7_loaded.flush_suit, t7.flush_suit);
+        assert_eq!(t7_loaded.face_six_rank, t7.face_six_rank);
+        assert_eq!(t7_loaded.flush_six_suit, t7.flush_six_suit);
+        assert_eq!(t7_loaded.t5.face_five_rank, t7.t5.face_five_rank);
+
+        let bytes = fs::read(&path).unwrap();
+
+        // corrupted
+        let mut bytes_ = bytes.clone();
+        bytes_[100] ^= 1;
+        fs::write(&path, &bytes_).unwrap();
+        assert!(matches!(
+            TableSeven::load(&path),
+            Err(TableFileError::InvalidChecksum(..))
+        ));
+
+        // truncated
+        fs::write(&path, &bytes[..1000]).unwrap();
+        assert!(matches!(TableSeven::load(&path), Err(TableFileError::Truncated(1000))));
+
+        // other keys - with valid checksum
+        let mut bytes_ = bytes.clone();
+        bytes_[8] ^= 1;
+        let n = bytes_.len() - 8;
+        let checksum = fnv1a(&bytes_[..n]);
+        bytes_[n..].copy_from_slice(&checksum.to_le_bytes());
+        fs::write(&path, &bytes_).unwrap();
+        assert!(matches!(TableSeven::load(&path), Err(TableFileError::InvalidKeys(..))));
+
+        // other version
+        let mut bytes_ = bytes.clone();
+        bytes_[4] = 99;
+        fs::write(&path, &bytes_).unwrap();
+        assert!(matches!(
+            TableSeven::load(&path),
+            Err(TableFileError::InvalidVersion(99))
+        ));
+
+        // unknown variant - with valid checksum
+        let mut bytes_ = bytes.clone();
+        bytes_[16] = 7;
+        let n = bytes_.len() - 8;
+        let checksum = fnv1a(&bytes_[..n]);
+        bytes_[n..].copy_from_slice(&checksum.to_le_bytes());
+        fs::write(&path, &bytes_).unwrap();
+        assert!(matches!(TableSeven::load(&path), Err(TableFileError::InvalidVariant(7))));
+
+        // not a table file
+        fs::write(&path, b"hello world").unwrap();
+        assert!(matches!(TableSeven::load(&path), Err(TableFileError::InvalidMagic(..))));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_invalid_length() {
+        let t7 = build_tables(false);
+        let path = std::env::temp_dir().join(format!("poker_eval_t7_length_{}.bin", std::process::id()));
+
+        // saved with a valid checksum, but tables too short for the keys
+        let save_load = |flush_rank_len: usize, rank_len: usize| {
+            let mut face_rank = t7.face_rank.clone();
+            face_rank.rank.truncate(rank_len);
+            let t7_ = TableSeven {
+                t5: five::build_tables(false),
+                face_rank,
+                flush_rank: t7.flush_rank[..flush_rank_len].to_vec(),
+                flush_suit: t7.flush_suit.clone(),
+                face_six_rank: t7.face_six_rank.clone(),
+                flush_six_suit: t7.flush_six_suit.clone(),
+            };
+            t7_.save(&path).unwrap();
+            TableSeven::load(&path)
+        };
+
+        let (n_flush, n_rank) = (t7.flush_rank.len(), t7.face_rank.rank.len());
+        let t7_loaded = save_load(n_flush - 1, n_rank);
+        assert!(
+            matches!(
+                t7_loaded,
+                Err(TableFileError::InvalidLength("flush_rank", n, n_)) if n == n_flush - 1 && n_ == n_flush
+            ),
+            "-> fails: {:?}",
+            t7_loaded.err()
+        );
+        let t7_loaded = save_load(n_flush, n_rank - 1);
+        assert!(
+            matches!(t7_loaded, Err(TableFileError::InvalidOffset("face_rank", _, n)) if n == n_rank - 1),
+            "-> fails: {:?}",
+            t7_loaded.err()
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_load_short_deck() {
+        let t7 = build_tables_variant(Variant::ShortDeck, false);
+        let path = std::env::temp_dir().join(format!("poker_eval_t7_short_{}.bin", std::process::id()));
+
+        t7.save(&path).unwrap();
+        let t7_loaded = TableSeven::load(&path).unwrap();
+
+        assert_eq!(t7_loaded.t5.variant, Variant::ShortDeck);
+        assert_eq!(t7_loaded.face_rank, t7.face_rank);
+        assert_eq!(t7_loaded.t5.face_five_rank, t7.t5.face_five_rank);
+        assert_eq!(t7_loaded.t5.flush_five_rank, t7.t5.flush_five_rank);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
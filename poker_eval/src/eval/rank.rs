@@ -0,0 +1,205 @@
+//! ## Hand rank
+//! Typed wrapper around the `u32` rank returned by [get_rank_five](crate::eval::five::get_rank_five) and [get_rank](crate::eval::seven::get_rank):
+//! - [HandCategory]: hand type, from high card to straight flush
+//! - [HandRank]: rank, ordered like the `u32` rank, with its category and a human description
+//!
+//! Category lookups use the table `t5.hand_type`, so they hold for all variants, e.g. short-deck where flush beats full house.
+//!
+//! ```
+//! use poker_eval::eval::rank::{HandCategory, HandRank};
+//! use poker_eval::eval::seven::{build_tables, get_rank};
+//!
+//! let t7 = build_tables(false);
+//!
+//! let rank = HandRank::from(get_rank(&t7, [5, 4, 18, 31, 34, 48, 22]));
+//! assert_eq!(rank.category(&t7.t5), HandCategory::OnePair);
+//! assert_eq!(rank.describe(&t7.t5), "One pair, Threes");
+//! ```
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::card::Face;
+use crate::eval::five::TableFive;
+use crate::eval::target::STATS_FIVE;
+
+/// ## Hand category
+/// In value order, same as [STATS_FIVE](crate::eval::target::STATS_FIVE).
+/// This is the order of [Variant::Standard](crate::keys::Variant) - in short-deck, flush beats full house, so compare ranks rather than categories.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum HandCategory {
+    HighCard,
+    OnePair,
+    TwoPairs,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+impl HandCategory {
+    /// All categories, in value order
+    pub const ALL: [HandCategory; 9] = [
+        HandCategory::HighCard,
+        HandCategory::OnePair,
+        HandCategory::TwoPairs,
+        HandCategory::ThreeOfAKind,
+        HandCategory::Straight,
+        HandCategory::Flush,
+        HandCategory::FullHouse,
+        HandCategory::FourOfAKind,
+        HandCategory::StraightFlush,
+    ];
+
+    /// Category of a hand rank, for the variant of the tables
+    pub fn from_rank(rank: u32, t5: &TableFive) -> HandCategory {
+        t5.hand_type[rank as usize]
+    }
+
+    /// Hand type as used in [STATS_FIVE](crate::eval::target::STATS_FIVE), e.g. "full-house"
+    pub fn as_str(self) -> &'static str {
+        STATS_FIVE[self as usize].0
+    }
+
+    /// Category name, e.g. "Full house"
+    pub fn name(self) -> &'static str {
+        match self {
+            HandCategory::HighCard => "High card",
+            HandCategory::OnePair => "One pair",
+            HandCategory::TwoPairs => "Two pairs",
+            HandCategory::ThreeOfAKind => "Three of a kind",
+            HandCategory::Straight => "Straight",
+            HandCategory::Flush => "Flush",
+            HandCategory::FullHouse => "Full house",
+            HandCategory::FourOfAKind => "Four of a kind",
+            HandCategory::StraightFlush => "Straight flush",
+        }
+    }
+}
+
+impl fmt::Display for HandCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// ## Hand rank
+/// The higher the better, same as the `u32` rank.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HandRank(pub u32);
+
+impl HandRank {
+    /// Rank value
+    pub fn value(self) -> u32 {
+        self.0
+    }
+
+    /// Hand category, for the variant of the tables
+    pub fn category(self, t5: &TableFive) -> HandCategory {
+        HandCategory::from_rank(self.0, t5)
+    }
+
+    /// Hand faces, best first, e.g. `[K, K, K, 7, 7]` for Kings full of Sevens
+    pub fn faces(self, t5: &TableFive) -> [Face; 5] {
+        t5.hand_faces[self.0 as usize].map(|f| Face::new(f as usize).unwrap())
+    }
+
+    /// Human description, e.g. "Full house, Kings full of Sevens" - for all variants
+    pub fn describe(self, t5: &TableFive) -> String {
+        let [f1, _, f3, f4, _] = self.faces(t5);
+        let category = self.category(t5);
+
+        let detail = match category {
+            HandCategory::HighCard => f1.name().to_string(),
+            HandCategory::OnePair | HandCategory::ThreeOfAKind | HandCategory::FourOfAKind => f1.plural().to_string(),
+            HandCategory::TwoPairs => format!("{} and {}", f1.plural(), f3.plural()),
+            HandCategory::FullHouse => format!("{} full of {}", f1.plural(), f4.plural()),
+            HandCategory::Straight | HandCategory::Flush | HandCategory::StraightFlush => {
+                format!("{} high", f1.name())
+            }
+        };
+
+        format!("{}, {}", category, detail)
+    }
+}
+
+impl From<u32> for HandRank {
+    fn from(rank: u32) -> HandRank {
+        HandRank(rank)
+    }
+}
+
+impl From<HandRank> for u32 {
+    fn from(rank: HandRank) -> u32 {
+        rank.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{HandCategory, HandRank};
+    use crate::eval::five::{build_tables, build_tables_variant, get_rank_five};
+    use crate::eval::target::STATS_FIVE;
+    use crate::keys::Variant;
+    use crate::util::is_normal;
+
+    #[test]
+    fn check_hand_rank_normal() {
+        is_normal::<HandCategory>();
+        is_normal::<HandRank>();
+    }
+
+    #[test]
+    fn hand_category() {
+        let t5 = build_tables(false);
+
+        for (i, (ht, hs)) in STATS_FIVE.iter().enumerate() {
+            assert_eq!(HandCategory::ALL[i].as_str(), *ht);
+            assert_eq!(HandCategory::from_rank(hs.min_rank, &t5), HandCategory::ALL[i]);
+            assert_eq!(HandCategory::from_rank(hs.max_rank, &t5), HandCategory::ALL[i]);
+            assert_eq!(HandRank(hs.min_rank).category(&t5), HandCategory::ALL[i]);
+        }
+
+        // short-deck: flush beats full house, and fewer ranks
+        let t5 = build_tables_variant(Variant::ShortDeck, false);
+        let full_house = HandRank(get_rank_five(&t5, [46, 45, 44, 21, 20]));
+        let flush = HandRank(get_rank_five(&t5, [50, 34, 22, 38, 26]));
+        assert_eq!(full_house.category(&t5), HandCategory::FullHouse);
+        assert_eq!(flush.category(&t5), HandCategory::Flush);
+        assert!(flush > full_house);
+        let top = HandRank(t5.nb_hand_five_rank - 1);
+        assert_eq!(top.category(&t5), HandCategory::StraightFlush);
+    }
+
+    #[test]
+    fn hand_describe() {
+        let t5 = build_tables(false);
+
+        for (c, desc) in [
+            ([50, 36, 22, 13, 0], "High card, Ace"),
+            ([46, 44, 22, 13, 0], "One pair, Kings"),
+            ([46, 44, 22, 21, 0], "Two pairs, Kings and Sevens"),
+            ([22, 21, 20, 46, 0], "Three of a kind, Sevens"),
+            ([12, 8, 4, 0, 49], "Straight, Five high"),
+            ([50, 46, 42, 38, 34], "Straight flush, Ace high"),
+            ([50, 34, 22, 10, 2], "Flush, Ace high"),
+            ([46, 45, 44, 21, 20], "Full house, Kings full of Sevens"),
+            ([48, 49, 50, 51, 0], "Four of a kind, Aces"),
+        ] {
+            let rank = HandRank(get_rank_five(&t5, c));
+            assert_eq!(rank.describe(&t5), desc, "-> fails: cards={:?}", c);
+        }
+
+        let full_house = HandRank(get_rank_five(&t5, [46, 45, 44, 21, 20]));
+        let flush = HandRank(get_rank_five(&t5, [50, 34, 22, 10, 2]));
+        assert!(full_house > flush);
+        assert!(full_house.category(&t5) > flush.category(&t5));
+    }
+}
@@ -1,8 +1,10 @@
 //! ## 7-card hand evaluation
 //! Contains the following functions:
 //! - [build_tables]: build the lookup tables for seven cards hand evaluation
+//! - [build_tables_variant]: same for a game [Variant], e.g. short-deck
 //! - [get_rank_seven]: slow evaluate the rank of a 7-card hand - used in [build_tables]
 //! - [get_rank]: fast evaluate the rank of a 7-card hand -- used in [calc](crate::calc)
+//! - [best_five]: find the best 5-card hand among 7 cards, e.g. to show a showdown result
 
 use std::iter::zip;
 use std::sync::Arc;
@@ -11,7 +13,10 @@ use std::time::Instant;
 #[cfg(feature = "serde")]
 use serde::Serialize;
 
-use crate::eval::five;
+use crate::eval::packed::PackedTable;
+use crate::eval::rank::HandRank;
+use crate::eval::{five, six};
+use crate::keys::Variant;
 
 /// ## Lookup tables for 7-card hand evaluation
 /// + build in function [build_tables]
@@ -21,12 +26,16 @@ use crate::eval::five;
 pub struct TableSeven {
     /// Lookup table for 5-card hand evaluation
     pub t5: five::TableFive,
-    /// face_rank[sum of face keys] = rank
-    pub face_rank: Vec<u32>,
+    /// face_rank.get(sum of face keys) = rank - packed, see [packed](crate::eval::packed)
+    pub face_rank: PackedTable,
     /// flush_rank[sum of flush keys] = rank
-    pub flush_rank: Vec<u32>,
+    pub flush_rank: Vec<u16>,
     /// flush_suit[sum of suit keys] = suit
-    pub flush_suit: Vec<i32>,
+    pub flush_suit: Vec<i8>,
+    /// face_six_rank.get(sum of face keys) = rank - for 6-card hands, see [six](crate::eval::six)
+    pub face_six_rank: PackedTable,
+    /// flush_six_suit[sum of suit keys] = suit - for 6-card hands, see [six](crate::eval::six)
+    pub flush_six_suit: Vec<i8>,
 }
 
 /// ## Build lookup tables for 7-card hand evaluation
@@ -38,31 +47,47 @@ pub struct TableSeven {
 ///
 /// Thus all possible 7-hand cards are assigned a rank.  
 /// This rank is very fast to lookup - performed by function [get_rank].  
+///
+/// The 6-card tables are built likewise, by [six::build_face_six_rank] and [six::build_flush_six_suit].  
 pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
+    build_tables_variant(Variant::Standard, verbose)
+}
+
+/// ## Build lookup tables for 7-card hand evaluation of a game variant
+/// Same as [build_tables], from the 5-card tables of the variant, see [five::build_tables_variant].  
+/// Only the hands made of faces of the variant deck are gone through.  
+/// The variant is stored in `t5.variant`, so [get_rank] and the equity calculations need no other argument.
+pub fn build_tables_variant(variant: Variant, verbose: bool) -> Arc<TableSeven> {
     let start = Instant::now();
 
-    let t5 = five::build_tables(false);
+    let t5 = five::build_tables_variant(variant, false);
 
     let face_key = t5.pk.face_seven_key;
     let flush_key = t5.pk.flush_seven_key;
     let nb_face = t5.pk.nb_face;
     let nb_suit = t5.pk.nb_suit;
+    // lowest face
+    let f0 = variant.min_face();
+
+    let mut face_rank = Vec::new();
 
     let mut t7 = TableSeven {
-        face_rank: vec![0; t5.pk.max_face_seven_key as usize + 1],
+        face_rank: PackedTable::default(),
         flush_rank: vec![0; t5.pk.max_flush_seven_key as usize + 1],
         flush_suit: vec![0; t5.pk.max_suit_key as usize + 1],
+        face_six_rank: six::build_face_six_rank(&t5),
+        flush_six_suit: six::build_flush_six_suit(&t5.pk),
         t5,
     };
 
     // face rank
-    for f1 in 0..nb_face {
-        for f2 in 0..(f1 + 1) {
-            for f3 in 0..(f2 + 1) {
-                for f4 in 0..(f3 + 1) {
-                    for f5 in 0..(f4 + 1) {
-                        for f6 in 0..(f5 + 1) {
-                            for f7 in 0..(f6 + 1) {
+    for f1 in f0..nb_face {
+        for f2 in f0..(f1 + 1) {
+            for f3 in f0..(f2 + 1) {
+                for f4 in f0..(f3 + 1) {
+                    for f5 in f0..(f4 + 1) {
+                        for f6 in f0..(f5 + 1) {
+                            for f7 in f0..(f6 + 1) {
                                 // no 5 or more same faces
                                 if (f1 - f5 > 0) && (f2 - f6 > 0) && (f3 - f7 > 0) {
                                     let hand_face_key = face_key[f1]
@@ -75,8 +100,8 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
                                     // arbitrary valid suits (4*0, 3*1)
                                     let (c1, c2, c3, c4, c5, c6, c7) =
                                         (4 * f1, 4 * f2, 4 * f3, 4 * f4, 4 * f5 + 1, 4 * f6 + 1, 4 * f7 + 1);
-                                    t7.face_rank[hand_face_key as usize] =
-                                        get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]);
+                                    let rank = get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]);
+                                    face_rank.push((hand_face_key, rank));
                                 }
                             }
                         }
@@ -86,14 +111,16 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
         }
     }
 
+    t7.face_rank = PackedTable::build(&face_rank);
+
     // flush rank 7 cards
-    for (f1, &k1) in zip(6.., flush_key[6..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., flush_key[0..f1].iter()) {
-            for (f3, &k3) in zip(0.., flush_key[0..f2].iter()) {
-                for (f4, &k4) in zip(0.., flush_key[0..f3].iter()) {
-                    for (f5, &k5) in zip(0.., flush_key[0..f4].iter()) {
-                        for (f6, &k6) in zip(0.., flush_key[0..f5].iter()) {
-                            for (f7, &k7) in zip(0.., flush_key[0..f6].iter()) {
+    for (f1, &k1) in zip(f0 + 6.., flush_key[f0 + 6..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., flush_key[f0..f1].iter()) {
+            for (f3, &k3) in zip(f0.., flush_key[f0..f2].iter()) {
+                for (f4, &k4) in zip(f0.., flush_key[f0..f3].iter()) {
+                    for (f5, &k5) in zip(f0.., flush_key[f0..f4].iter()) {
+                        for (f6, &k6) in zip(f0.., flush_key[f0..f5].iter()) {
+                            for (f7, &k7) in zip(f0.., flush_key[f0..f6].iter()) {
                                 let hand_flush_key = k1 + k2 + k3 + k4 + k5 + k6 + k7;
                                 // arbitrary suit (7*0)
                                 let c1 = 4 * f1;
@@ -103,8 +130,7 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
                                 let c5 = 4 * f5;
                                 let c6 = 4 * f6;
                                 let c7 = 4 * f7;
-                                t7.flush_rank[hand_flush_key as usize] =
-                                    get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]);
+                                t7.flush_rank[hand_flush_key as usize] = get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]) as u16;
                             }
                         }
                     }
@@ -114,12 +140,12 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
     }
 
     // flush rank 6 cards
-    for (f1, &k1) in zip(5.., flush_key[5..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., flush_key[0..f1].iter()) {
-            for (f3, &k3) in zip(0.., flush_key[0..f2].iter()) {
-                for (f4, &k4) in zip(0.., flush_key[0..f3].iter()) {
-                    for (f5, &k5) in zip(0.., flush_key[0..f4].iter()) {
-                        for (f6, &k6) in zip(0.., flush_key[0..f5].iter()) {
+    for (f1, &k1) in zip(f0 + 5.., flush_key[f0 + 5..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., flush_key[f0..f1].iter()) {
+            for (f3, &k3) in zip(f0.., flush_key[f0..f2].iter()) {
+                for (f4, &k4) in zip(f0.., flush_key[f0..f3].iter()) {
+                    for (f5, &k5) in zip(f0.., flush_key[f0..f4].iter()) {
+                        for (f6, &k6) in zip(f0.., flush_key[f0..f5].iter()) {
                             let hand_flush_key = k1 + k2 + k3 + k4 + k5 + k6;
                             // arbitrary suit (7*0)
                             let c1 = 4 * f1;
@@ -129,8 +155,7 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
                             let c5 = 4 * f5;
                             let c6 = 4 * f6;
                             let c7 = 4 * f6 + 1;
-                            t7.flush_rank[hand_flush_key as usize] =
-                                get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]);
+                            t7.flush_rank[hand_flush_key as usize] = get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]) as u16;
                         }
                     }
                 }
@@ -139,11 +164,11 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
     }
 
     // flush rank 5 cards
-    for (f1, &k1) in zip(4.., flush_key[4..nb_face].iter()) {
-        for (f2, &k2) in zip(0.., flush_key[0..f1].iter()) {
-            for (f3, &k3) in zip(0.., flush_key[0..f2].iter()) {
-                for (f4, &k4) in zip(0.., flush_key[0..f3].iter()) {
-                    for (f5, &k5) in zip(0.., flush_key[0..f4].iter()) {
+    for (f1, &k1) in zip(f0 + 4.., flush_key[f0 + 4..nb_face].iter()) {
+        for (f2, &k2) in zip(f0.., flush_key[f0..f1].iter()) {
+            for (f3, &k3) in zip(f0.., flush_key[f0..f2].iter()) {
+                for (f4, &k4) in zip(f0.., flush_key[f0..f3].iter()) {
+                    for (f5, &k5) in zip(f0.., flush_key[f0..f4].iter()) {
                         let hand_flush_key = k1 + k2 + k3 + k4 + k5;
                         // arbitrary suit (7*0)
                         let c1 = 4 * f1;
@@ -153,7 +178,7 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
                         let c5 = 4 * f5;
                         let c6 = 4 * f5 + 1;
                         let c7 = 4 * f5 + 1;
-                        t7.flush_rank[hand_flush_key as usize] = get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]);
+                        t7.flush_rank[hand_flush_key as usize] = get_rank_seven(&t7.t5, [c1, c2, c3, c4, c5, c6, c7]) as u16;
                     }
                 }
             }
@@ -201,7 +226,7 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
                                         suit_count += 1;
                                     }
                                     if suit_count >= 5 {
-                                        t7.flush_suit[hand_suit_key as usize] = suit as i32;
+                                        t7.flush_suit[hand_suit_key as usize] = suit as i8;
                                     }
                                 }
                             }
@@ -215,7 +240,7 @@ pub fn build_tables(verbose: bool) -> Arc<TableSeven> {
     let end = Instant::now();
 
     if verbose {
-        println!("seven::build_tables runtime = {:?}", (end - start));
+        println!("seven::build_tables runtime = {:?} variant = {}", (end - start), variant);
     }
 
     Arc::new(t7)
@@ -313,7 +338,7 @@ pub fn get_rank(t7: &TableSeven, c: [usize; 7]) -> u32 {
 
     if hand_suit == -1 {
         let hand_face_key = hand_key >> suit_bit_shift;
-        hand_rank = face_rank[hand_face_key as usize];
+        hand_rank = face_rank.get(hand_face_key);
     } else {
         let mut hand_flush_key = 0;
         for i in 0..7 {
@@ -321,16 +346,58 @@ pub fn get_rank(t7: &TableSeven, c: [usize; 7]) -> u32 {
                 hand_flush_key += card_flush_key[c[i]];
             }
         }
-        hand_rank = flush_rank[hand_flush_key as usize];
+        hand_rank = flush_rank[hand_flush_key as usize] as u32;
     }
 
     hand_rank
 }
 
+/// ## Best 5-card hand among 7 cards
+/// The rank is looked up by fast function [get_rank].  
+/// Then the 21 5-card hands (among 7) are evaluated by [get_rank_five](five::get_rank_five) until one has this rank.  
+/// The 5 cards are returned in input order.
+///
+/// ## Example
+/// ```rust
+/// use poker_eval::eval::seven::{best_five, build_tables};
+///
+/// let t7 = build_tables(false);
+///
+/// let (cards, rank) = best_five(&t7, [5, 4, 18, 31, 34, 48, 22]);
+/// assert_eq!(cards, [5, 4, 31, 34, 48]);
+/// assert_eq!(rank.describe(&t7.t5), "One pair, Threes");
+/// ```
+pub fn best_five(t7: &TableSeven, c: [usize; 7]) -> ([usize; 5], HandRank) {
+    let rank = get_rank(t7, c);
+    let mut arr = [0; 5];
+
+    for c1 in 0..7 {
+        for c2 in 0..c1 {
+            let mut k = 0;
+            for (i, card) in c.iter().enumerate() {
+                // exclude cards c1 and c2
+                if i != c1 && i != c2 {
+                    arr[k] = *card;
+                    k += 1;
+                }
+            }
+            if five::get_rank_five(&t7.t5, arr) == rank {
+                return (arr, HandRank(rank));
+            }
+        }
+    }
+    unreachable!("no 5-card hand among {:?} has rank {}", c, rank)
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::{build_tables, get_rank, TableSeven};
+    use rand::seq::SliceRandom;
+
+    use super::{best_five, build_tables, build_tables_variant, get_rank, get_rank_seven, TableSeven};
+    use crate::eval::five::get_rank_five;
+    use crate::eval::six::{get_rank_six, get_rank_six_slow};
+    use crate::keys::Variant;
 
     use crate::util::is_normal;
 
@@ -369,4 +436,53 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn eval_best_five() {
+        let t7 = build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..52).collect::<Vec<usize>>();
+
+        for _ in 0..10_000 {
+            deck.shuffle(&mut rng);
+            let c: [usize; 7] = deck[0..7].try_into().unwrap();
+            let (cards, rank) = best_five(&t7, c);
+
+            // brute force
+            let mut best_rank = 0;
+            for c1 in 0..7 {
+                for c2 in 0..c1 {
+                    let sub = (0..7)
+                        .filter(|i| *i != c1 && *i != c2)
+                        .map(|i| c[i])
+                        .collect::<Vec<usize>>();
+                    best_rank = best_rank.max(get_rank_five(&t7.t5, sub.try_into().unwrap()));
+                }
+            }
+
+            assert_eq!(rank.value(), best_rank, "-> fails: cards={:?}", c);
+            assert_eq!(get_rank_five(&t7.t5, cards), best_rank, "-> fails: cards={:?}", c);
+            assert!(cards.iter().all(|x| c.contains(x)), "-> fails: cards={:?}", c);
+        }
+    }
+
+    #[test]
+    fn eval_seven_short_deck() {
+        let t7 = build_tables_variant(Variant::ShortDeck, false);
+        let mut rng = rand::thread_rng();
+        let mut deck = Variant::ShortDeck.deck();
+
+        for _ in 0..100_000 {
+            deck.shuffle(&mut rng);
+            let c: [usize; 7] = deck[0..7].try_into().unwrap();
+            let rank_found = get_rank(&t7, c);
+            let rank_want = get_rank_seven(&t7.t5, c);
+            assert_eq!(rank_found, rank_want, "-> fails: cards={:?}", c);
+
+            let c: [usize; 6] = deck[0..6].try_into().unwrap();
+            let rank_found = get_rank_six(&t7, c);
+            let rank_want = get_rank_six_slow(&t7.t5, c);
+            assert_eq!(rank_found, rank_want, "-> fails: cards={:?}", c);
+        }
+    }
 }
@@ -0,0 +1,225 @@
+//! ## 6-card hand evaluation
+//! Typically 2 player cards and 4 table cards, i.e. on the turn.
+//! Contains the following functions:
+//! - [build_face_six_rank]: build the 6-card face lookup table - stored in [TableSeven]
+//! - [build_flush_six_suit]: build the 6-card flush suit lookup table - stored in [TableSeven]
+//! - [get_rank_six_slow]: slow evaluate the rank of a 6-card hand - used in [build_face_six_rank]
+//! - [get_rank_six]: fast evaluate the rank of a 6-card hand
+//!
+//! The 7-card keys also guarantee unique sums for 6 cards.
+//! But as the deuce face key and the clubs suit key are 0, a 6-card sum is also a 7-card sum, hence the dedicated face and suit tables.
+//! The flush table `flush_rank` is shared with 7-card hands: it covers 5, 6 and 7 cards of the same suit.
+
+use crate::eval::five;
+use crate::eval::packed::PackedTable;
+use crate::eval::seven::TableSeven;
+use crate::keys::Keys;
+
+/// ## Build 6-card face lookup table
+/// Same as the face rank part of [seven::build_tables](crate::eval::seven::build_tables), for 6 cards.
+/// face_six_rank.get(sum of face keys) = rank - for the faces of the variant of `t5`
+pub fn build_face_six_rank(t5: &five::TableFive) -> PackedTable {
+    let face_key = t5.pk.face_seven_key;
+    let nb_face = t5.pk.nb_face;
+    // lowest face of the variant
+    let f0 = t5.variant.min_face();
+
+    let mut face_six_rank = Vec::new();
+
+    for f1 in f0..nb_face {
+        for f2 in f0..(f1 + 1) {
+            for f3 in f0..(f2 + 1) {
+                for f4 in f0..(f3 + 1) {
+                    for f5 in f0..(f4 + 1) {
+                        for f6 in f0..(f5 + 1) {
+                            // no 5 or more same faces
+                            if (f1 - f5 > 0) && (f2 - f6 > 0) {
+                                let hand_face_key = face_key[f1]
+                                    + face_key[f2]
+                                    + face_key[f3]
+                                    + face_key[f4]
+                                    + face_key[f5]
+                                    + face_key[f6];
+                                // arbitrary valid suits (3*0, 3*1)
+                                let (c1, c2, c3, c4, c5, c6) =
+                                    (4 * f1, 4 * f2, 4 * f3, 4 * f4 + 1, 4 * f5 + 1, 4 * f6 + 1);
+                                let rank = get_rank_six_slow(t5, [c1, c2, c3, c4, c5, c6]);
+                                face_six_rank.push((hand_face_key, rank));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    PackedTable::build(&face_six_rank)
+}
+
+/// ## Build 6-card flush suit lookup table
+/// flush_six_suit[sum of suit keys] = suit if 5 or 6 cards have this suit, else -1
+pub fn build_flush_six_suit(pk: &Keys) -> Vec<i8> {
+    let nb_suit = pk.nb_suit;
+
+    let mut flush_six_suit = vec![-1; pk.max_suit_key as usize + 1];
+
+    for s1 in 0..nb_suit {
+        for s2 in 0..(s1 + 1) {
+            for s3 in 0..(s2 + 1) {
+                for s4 in 0..(s3 + 1) {
+                    for s5 in 0..(s4 + 1) {
+                        for s6 in 0..(s5 + 1) {
+                            let suits = [s1, s2, s3, s4, s5, s6];
+                            let hand_suit_key = suits.iter().map(|s| pk.suit_key[*s]).sum::<u32>();
+                            flush_six_suit[hand_suit_key as usize] = -1;
+
+                            for suit in 0..nb_suit {
+                                let suit_count = suits.iter().filter(|s| **s == suit).count();
+                                if suit_count >= 5 {
+                                    flush_six_suit[hand_suit_key as usize] = suit as i8;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    flush_six_suit
+}
+
+/// ## Slow evaluate 6-card hand rank
+///
+/// Used in [build_face_six_rank] only once.
+/// All 5-card (among 6) are evaluated and the best rank is returned.
+///
+pub fn get_rank_six_slow(t5: &five::TableFive, c: [usize; 6]) -> u32 {
+    let mut best_hand_rank = 0;
+    let mut arr = [0; 5];
+
+    for c1 in 0..6 {
+        let mut k = 0;
+        for (i, card) in c.iter().enumerate() {
+            // exclude card c1
+            if i != c1 {
+                arr[k] = *card;
+                k += 1;
+            }
+        }
+        let hand_rank = five::get_rank_five(t5, arr);
+
+        if hand_rank > best_hand_rank {
+            best_hand_rank = hand_rank;
+        }
+    }
+    best_hand_rank
+}
+
+/// ## Fast evaluate 6-card hand rank
+/// Same as [get_rank](crate::eval::seven::get_rank) for 6 cards:
+/// + lookup `hand_suit_key` in `flush_six_suit`
+/// + if not a flush, lookup `hand_face_key` in `face_six_rank`
+/// + else lookup `hand_flush_key` in `flush_rank`, shared with 7-card hands
+///
+/// ## Example
+/// ```rust
+/// use poker_eval::eval::seven::build_tables;
+/// use poker_eval::eval::six::get_rank_six;
+///
+/// // precalculate the lookup tables
+/// let t7 = build_tables(false);
+///
+/// // run the evaluation multiple times
+/// let rank = get_rank_six(&t7, [5, 4, 18, 31, 34, 48]);
+/// assert_eq!(rank, 1689);
+/// ```
+pub fn get_rank_six(t7: &TableSeven, c: [usize; 6]) -> u32 {
+    let card_face_key = &t7.t5.pk.card_face_key;
+    let card_flush_key = &t7.t5.pk.card_flush_key;
+
+    let suit_mask = t7.t5.pk.suit_mask;
+    let suit_bit_shift = t7.t5.pk.suit_bit_shift;
+    let card_suit = &t7.t5.pk.card_suit;
+
+    let hand_key = card_face_key[c[0]]
+        + card_face_key[c[1]]
+        + card_face_key[c[2]]
+        + card_face_key[c[3]]
+        + card_face_key[c[4]]
+        + card_face_key[c[5]];
+    let hand_suit_key = (hand_key & suit_mask) as usize;
+    let hand_suit = t7.flush_six_suit[hand_suit_key];
+
+    if hand_suit == -1 {
+        let hand_face_key = hand_key >> suit_bit_shift;
+        t7.face_six_rank.get(hand_face_key)
+    } else {
+        let mut hand_flush_key = 0;
+        for card in c.iter() {
+            if card_suit[*card] == hand_suit as usize {
+                hand_flush_key += card_flush_key[*card];
+            }
+        }
+        t7.flush_rank[hand_flush_key as usize] as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::seq::SliceRandom;
+
+    use super::{get_rank_six, get_rank_six_slow};
+    use crate::eval::seven::{build_tables, get_rank_seven};
+    use crate::keys::DECK_SIZE;
+
+    #[test]
+    fn eval_six_all() {
+        let t7 = build_tables(false);
+
+        for c1 in 0..DECK_SIZE {
+            for c2 in 0..c1 {
+                for c3 in 0..c2 {
+                    for c4 in 0..c3 {
+                        for c5 in 0..c4 {
+                            for c6 in 0..c5 {
+                                let c = [c1, c2, c3, c4, c5, c6];
+                                let rank_found = get_rank_six(&t7, c);
+                                let rank_want = get_rank_six_slow(&t7.t5, c);
+                                assert_eq!(
+                                    rank_found, rank_want,
+                                    "-> want:rank={} found:rank={} for cards={:?}",
+                                    rank_want, rank_found, c,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eval_six_vs_seven() {
+        let t7 = build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+
+        for _ in 0..100_000 {
+            deck.shuffle(&mut rng);
+            let c: [usize; 7] = deck[0..7].try_into().unwrap();
+            let rank_seven = get_rank_seven(&t7.t5, c);
+
+            // best 6-card subset is the 7-card rank
+            let mut best_rank = 0;
+            for i in 0..7 {
+                let sub = (0..7).filter(|j| *j != i).map(|j| c[j]).collect::<Vec<usize>>();
+                let rank_six = get_rank_six(&t7, sub.try_into().unwrap());
+                assert!(rank_six <= rank_seven, "-> fails: cards={:?}", c);
+                best_rank = best_rank.max(rank_six);
+            }
+            assert_eq!(best_rank, rank_seven, "-> fails: cards={:?}", c);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! ## Static 7-card lookup tables
+//! Only with feature `static-tables`.
+//! The tables are generated at compile time by the build script, with the same code as [seven::build_tables](crate::eval::seven::build_tables).
+//! So no table needs to be built at runtime, and [get_rank] does not need any [TableSeven](crate::eval::seven::TableSeven) argument.
+//!
+//! ```
+//! use poker_eval::eval::tables::get_rank;
+//!
+//! let rank = get_rank([5, 4, 18, 31, 34, 48, 22]);
+//! assert_eq!(rank, 1689);
+//! ```
+
+use crate::eval::packed;
+use crate::keys::{DECK_SIZE, FACE_SEVEN_KEY, FLUSH_SEVEN_KEY, NB_SUIT, SUIT_BIT_SHIFT, SUIT_KEY, SUIT_MASK};
+
+/// Bytes aligned as `u32` - so they can be viewed as `[u32]` or `[u16]`
+#[repr(C, align(4))]
+struct Aligned<T: ?Sized>(T);
+
+static FACE_ROW_OFFSET_BYTES: &Aligned<[u8]> = &Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/face_row_offset.bin")));
+static FACE_RANK_BYTES: &Aligned<[u8]> = &Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/face_rank.bin")));
+static FLUSH_RANK_BYTES: &Aligned<[u8]> = &Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/flush_rank.bin")));
+static FLUSH_SUIT_BYTES: &Aligned<[u8]> = &Aligned(*include_bytes!(concat!(env!("OUT_DIR"), "/flush_suit.bin")));
+
+/// face_row_offset[sum of face keys >> ROW_BITS] = row offset - see [PackedTable](crate::eval::packed::PackedTable)
+pub static FACE_ROW_OFFSET: &[u32] = as_u32(&FACE_ROW_OFFSET_BYTES.0);
+/// face_rank[row offset + (sum of face keys & ROW_MASK)] = rank - see [PackedTable](crate::eval::packed::PackedTable)
+pub static FACE_RANK: &[u16] = as_u16(&FACE_RANK_BYTES.0);
+/// flush_rank[sum of flush keys] = rank
+pub static FLUSH_RANK: &[u16] = as_u16(&FLUSH_RANK_BYTES.0);
+/// flush_suit[sum of suit keys] = suit
+pub static FLUSH_SUIT: &[i8] = as_i8(&FLUSH_SUIT_BYTES.0);
+
+/// card_face_key[card] - same as [Keys::card_face_key](crate::keys::Keys::card_face_key)
+pub const CARD_FACE_KEY: [u32; DECK_SIZE] = card_keys(true);
+/// card_flush_key[card] - same as [Keys::card_flush_key](crate::keys::Keys::card_flush_key)
+pub const CARD_FLUSH_KEY: [u32; DECK_SIZE] = card_keys(false);
+
+const fn as_u32(bytes: &'static [u8]) -> &'static [u32] {
+    // SAFETY: bytes are aligned as u32 by Aligned, any bit pattern is a valid u32,
+    // and they were written in target endianness by the build script
+    unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const u32, bytes.len() / 4) }
+}
+
+const fn as_u16(bytes: &'static [u8]) -> &'static [u16] {
+    // SAFETY: same as as_u32
+    unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const u16, bytes.len() / 2) }
+}
+
+const fn as_i8(bytes: &'static [u8]) -> &'static [i8] {
+    // SAFETY: i8 has the same size and alignment as u8, and any bit pattern is valid
+    unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const i8, bytes.len()) }
+}
+
+const fn card_keys(face: bool) -> [u32; DECK_SIZE] {
+    let mut keys = [0; DECK_SIZE];
+    let mut n = 0;
+    while n < DECK_SIZE {
+        let (f, s) = (n / NB_SUIT, n % NB_SUIT);
+        keys[n] = match face {
+            true => (FACE_SEVEN_KEY[f] << SUIT_BIT_SHIFT) + SUIT_KEY[s],
+            false => FLUSH_SEVEN_KEY[f],
+        };
+        n += 1;
+    }
+    keys
+}
+
+/// ## Fast evaluate 7-card hand rank from static tables
+/// Same as [seven::get_rank](crate::eval::seven::get_rank), without any table argument.
+pub fn get_rank(c: [usize; 7]) -> u32 {
+    let hand_key = CARD_FACE_KEY[c[0]]
+        + CARD_FACE_KEY[c[1]]
+        + CARD_FACE_KEY[c[2]]
+        + CARD_FACE_KEY[c[3]]
+        + CARD_FACE_KEY[c[4]]
+        + CARD_FACE_KEY[c[5]]
+        + CARD_FACE_KEY[c[6]];
+    let hand_suit_key = (hand_key & SUIT_MASK) as usize;
+    let hand_suit = FLUSH_SUIT[hand_suit_key];
+
+    if hand_suit == -1 {
+        let hand_face_key = hand_key >> SUIT_BIT_SHIFT;
+        packed::lookup(FACE_ROW_OFFSET, FACE_RANK, hand_face_key)
+    } else {
+        let mut hand_flush_key = 0;
+        for card in c.iter() {
+            if card % NB_SUIT == hand_suit as usize {
+                hand_flush_key += CARD_FLUSH_KEY[*card];
+            }
+        }
+        FLUSH_RANK[hand_flush_key as usize] as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::seq::SliceRandom;
+
+    use super::{get_rank, CARD_FACE_KEY, CARD_FLUSH_KEY, FACE_RANK, FACE_ROW_OFFSET, FLUSH_RANK, FLUSH_SUIT};
+    use crate::eval::seven;
+    use crate::keys::DECK_SIZE;
+
+    #[test]
+    fn static_tables_identical() {
+        let t7 = seven::build_tables(false);
+
+        assert_eq!(FACE_ROW_OFFSET, t7.face_rank.row_offset.as_slice());
+        assert_eq!(FACE_RANK, t7.face_rank.rank.as_slice());
+        assert_eq!(FLUSH_RANK, t7.flush_rank.as_slice());
+        assert_eq!(FLUSH_SUIT, t7.flush_suit.as_slice());
+        assert_eq!(CARD_FACE_KEY, t7.t5.pk.card_face_key);
+        assert_eq!(CARD_FLUSH_KEY, t7.t5.pk.card_flush_key);
+    }
+
+    #[test]
+    fn static_get_rank() {
+        let t7 = seven::build_tables(false);
+        let mut rng = rand::thread_rng();
+        let mut deck = (0..DECK_SIZE).collect::<Vec<usize>>();
+
+        for _ in 0..100_000 {
+            deck.shuffle(&mut rng);
+            let c: [usize; 7] = deck[0..7].try_into().unwrap();
+            assert_eq!(get_rank(c), seven::get_rank(&t7, c), "-> fails: cards={:?}", c);
+        }
+    }
+}
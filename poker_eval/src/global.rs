@@ -0,0 +1,64 @@
+//! ## Process-wide lookup tables
+//! Lazily built on first use, once, thread safe - no need to pass an `Arc<TableSeven>` around:
+//! - [global]: the 7-card lookup tables, which contain the 5-card ones
+//! - [rank7]: same as [get_rank](crate::eval::seven::get_rank) using [global]
+//! - [rank5]: same as [get_rank_five](crate::eval::five::get_rank_five) using [global]
+
+use std::sync::{Arc, OnceLock};
+
+use crate::eval::{
+    five::get_rank_five,
+    seven::{build_tables, get_rank, TableSeven},
+};
+
+static GLOBAL: OnceLock<Arc<TableSeven>> = OnceLock::new();
+
+/// ## Process-wide 7-card lookup tables
+/// Built by [build_tables](crate::eval::seven::build_tables) on first call, which takes about 1s.  
+/// Concurrent first calls wait for the same build.
+///
+/// ## Example
+/// ```
+/// use poker_eval::{global, rank5, rank7};
+///
+/// let t7 = global();
+/// assert_eq!(t7.t5.nb_hand_five_rank, 7462);
+///
+/// assert_eq!(rank7([5, 4, 18, 31, 34, 48, 22]), 1689);
+/// assert_eq!(rank5([31, 26, 50, 16, 49]), 3971);
+/// ```
+pub fn global() -> &'static TableSeven {
+    GLOBAL.get_or_init(|| build_tables(false))
+}
+
+/// ## Fast evaluate 7-card hand rank
+/// See [get_rank](crate::eval::seven::get_rank).
+pub fn rank7(cards: [usize; 7]) -> u32 {
+    get_rank(global(), cards)
+}
+
+/// ## Evaluate 5-card hand rank
+/// See [get_rank_five](crate::eval::five::get_rank_five).
+pub fn rank5(cards: [usize; 5]) -> u32 {
+    get_rank_five(&global().t5, cards)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use super::{global, rank5, rank7};
+
+    #[test]
+    fn global_once() {
+        let handles = (0..4)
+            .map(|_| thread::spawn(|| global() as *const _ as usize))
+            .collect::<Vec<_>>();
+        let ptrs = handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<usize>>();
+        assert!(ptrs.iter().all(|p| *p == ptrs[0]));
+
+        assert_eq!(rank7([50, 6, 0, 5, 38, 7, 17]), 5124);
+        assert_eq!(rank5([12, 8, 4, 0, 48]), 7452);
+    }
+}
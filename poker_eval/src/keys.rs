@@ -25,6 +25,8 @@ pub const SUIT_MASK: u32 = 2_u32.pow(SUIT_BIT_SHIFT as u32) - 1;
 
 /// Suits (Clubs, Diamonds, Hearts, Spades) as char (C, D, H, S)
 pub const SUIT: [char; 4] = ['C', 'D', 'H', 'S'];
+/// Suits (Clubs, Diamonds, Hearts, Spades) as symbol (♣, ♦, ♥, ♠)
+pub const SUIT_SYMBOL: [char; 4] = ['♣', '♦', '♥', '♠'];
 /// Suit keys - from **poker_keygen**
 pub const SUIT_KEY: [u32; 4] = [0, 1, 29, 37];
 
@@ -51,9 +53,68 @@ pub const MAX_FLUSH_SEVEN_KEY: u32 = sum_last(FLUSH_SEVEN_KEY, 7);
 
 /// Maximum face key for 5-card hands
 pub const MAX_FACE_FIVE_KEY: u32 = FACE_FIVE_KEY[NB_FACE - 1] * 4 + FACE_FIVE_KEY[NB_FACE - 2] * 1;
+/// Maximum face key for 6-card hands
+pub const MAX_FACE_SIX_KEY: u32 = FACE_SEVEN_KEY[NB_FACE - 1] * 4 + FACE_SEVEN_KEY[NB_FACE - 2] * 2;
 /// Maximum face key for 7-card hands
 pub const MAX_FACE_SEVEN_KEY: u32 = FACE_SEVEN_KEY[NB_FACE - 1] * 4 + FACE_SEVEN_KEY[NB_FACE - 2] * 3;
 
+/// Lowest face of the short deck: 6
+pub const SHORT_DECK_MIN_FACE: usize = 4;
+
+/// ## Game variant
+/// + `Standard`: Texas Hold'em, 52 cards
+/// + `ShortDeck`: Short-deck (6+) Hold'em, 36 cards from 6 to A - flush beats full house, A-6-7-8-9 is the lowest straight
+///
+/// The card numbers are the same, so the short deck is cards 16 to 51.
+/// The keys are the same too, as face key sums are unique for any subset of faces.
+/// Only the rank order differs - see [build_tables_variant](crate::eval::five::build_tables_variant).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Variant {
+    #[default]
+    Standard,
+    ShortDeck,
+}
+
+impl Variant {
+    /// Lowest face: 0 (2) or 4 (6)
+    pub fn min_face(self) -> usize {
+        match self {
+            Variant::Standard => 0,
+            Variant::ShortDeck => SHORT_DECK_MIN_FACE,
+        }
+    }
+
+    /// Number of cards in the deck: 52 or 36
+    pub fn deck_size(self) -> usize {
+        NB_SUIT * (NB_FACE - self.min_face())
+    }
+
+    /// True if the card is in the deck
+    pub fn contains(self, card: usize) -> bool {
+        card < DECK_SIZE && card / NB_SUIT >= self.min_face()
+    }
+
+    /// All cards of the deck, in increasing order
+    pub fn deck(self) -> Vec<usize> {
+        (NB_SUIT * self.min_face()..DECK_SIZE).collect()
+    }
+
+    /// Variant name, e.g. "short-deck"
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::ShortDeck => "short-deck",
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// Missing value - convention
 pub const NO_VALUE: u32 = 999_999_999;
 
@@ -78,6 +139,7 @@ pub struct Keys {
     pub max_flush_five_key: u32,
     pub max_flush_seven_key: u32,
     pub max_face_five_key: u32,
+    pub max_face_six_key: u32,
     pub max_face_seven_key: u32,
 
     #[cfg_attr(feature = "serde", serde(with = "BigArray"))]
@@ -146,6 +208,7 @@ pub fn build() -> Keys {
         max_flush_five_key: MAX_FLUSH_FIVE_KEY,
         max_flush_seven_key: MAX_FLUSH_SEVEN_KEY,
         max_face_five_key: MAX_FACE_FIVE_KEY,
+        max_face_six_key: MAX_FACE_SIX_KEY,
         max_face_seven_key: MAX_FACE_SEVEN_KEY,
 
         // constructed
@@ -200,6 +263,7 @@ impl fmt::Display for Keys {
         writeln!(f, "max_flush_five_key = {:?}", self.max_flush_five_key)?;
         writeln!(f, "max_flush_seven_key = {:?}", self.max_flush_seven_key)?;
         writeln!(f, "max_face_five_key = {:?}", self.max_face_five_key)?;
+        writeln!(f, "max_face_six_key = {:?}", self.max_face_six_key)?;
         writeln!(f, "max_face_seven_key = {:?}", self.max_face_seven_key)?;
 
         writeln!(f, "card_face = {:?}", self.card_face)?;
@@ -225,11 +289,26 @@ const fn sum_last(arr: [u32; NB_FACE], n: usize) -> u32 {
 #[cfg(test)]
 mod tests {
 
-    use super::Keys;
+    use super::{Keys, Variant, DECK_SIZE};
     use crate::util::is_normal;
 
     #[test]
     fn check_keys_normal() {
         is_normal::<Keys>();
+        is_normal::<Variant>();
+    }
+
+    #[test]
+    fn variant_deck() {
+        assert_eq!(Variant::Standard.deck(), (0..DECK_SIZE).collect::<Vec<usize>>());
+        assert_eq!(Variant::ShortDeck.deck(), (16..DECK_SIZE).collect::<Vec<usize>>());
+
+        for variant in [Variant::Standard, Variant::ShortDeck] {
+            assert_eq!(variant.deck().len(), variant.deck_size());
+            for card in 0..DECK_SIZE + 1 {
+                assert_eq!(variant.contains(card), variant.deck().contains(&card), "-> fails: card={}", card);
+            }
+        }
+        assert_eq!(Variant::ShortDeck.deck_size(), 36);
     }
 }
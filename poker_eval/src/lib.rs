@@ -1,7 +1,12 @@
 #![doc = include_str!("../README.md")]
 
 pub mod calc;
+pub mod card;
 pub mod eval;
+mod global;
 pub mod keys;
+pub mod range;
 pub mod stats;
 pub mod util;
+
+pub use global::{global, rank5, rank7};
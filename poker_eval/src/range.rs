@@ -0,0 +1,570 @@
+//! ## Hand ranges
+//! Sets of 2-card starting hands, or combos, each with a weight between 0 and 1:
+//! - [Range]: combos and weights, parsed from and formatted to the standard range notation
+//!
+//! A range is a list of tokens separated by commas and/or spaces:
+//! - pairs: `QQ`, `QQ+` for QQ to AA, `TT-77` for TT to 77
+//! - suited or offsuit hands: `AKs`, `AKo`, or `AK` for both
+//! - `KTo+` for KTo, KJo and KQo - the second face goes up to just below the first
+//! - `A5s-A2s` for A5s, A4s, A3s and A2s - both ends have the same first face and type
+//! - explicit combos: `AhKh`
+//! - a weight for all combos of a token: `AKs:0.5` - 1 by default
+//!
+//! When a combo is given more than once, the last token sets its weight.
+//!
+//! A combo is stored as `[u32; 2]` card numbers, as expected by the [calc](crate::calc) functions, the highest card first.
+//!
+//! Removing combos holding some cards, e.g. board or dead cards, also blocks these cards.
+//! So the range stays compact when formatted: a class is complete when it has all its combos without blocked cards.
+//!
+//! ```
+//! use poker_eval::card::Hand;
+//! use poker_eval::range::Range;
+//!
+//! let mut range: Range = "QQ+, AKs, A5s-A2s, KTo+, AhKh:0.5".parse().unwrap();
+//! assert_eq!(range.len(), 18 + 4 + 16 + 36);
+//!
+//! // remove combos holding a board or dead card
+//! let board: Hand = "Ah 7c 2d".parse().unwrap();
+//! range.remove_cards(&board.to_vec()).unwrap();
+//! assert_eq!(range.len(), 15 + 3 + 11 + 36);
+//! assert_eq!(range.to_string(), "QQ+, AKs, A5s-A2s, KTo+");
+//! ```
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::card::{parse_cards, Card, CardError, Face, Hand, Suit};
+
+/// ## Range parsing error
+/// This error type is used to describe the errors that can occur when parsing a range from text.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseRangeError {
+    /// Invalid token
+    #[error("invalid range token: '{0}' - must be e.g. 'QQ+', 'AKs', 'A5s-A2s', 'KTo+' or 'AhKh'")]
+    InvalidToken(String),
+    /// Invalid span
+    #[error("invalid range span: '{0}' - both ends must have the same first face and type, e.g. 'A5s-A2s' or 'TT-77'")]
+    InvalidSpan(String),
+    /// Invalid weight
+    #[error("invalid range weight: '{0}' - must be a number between 0 and 1")]
+    InvalidWeight(String),
+}
+
+/// ## Hand range
+/// Combos with their weights, the highest card of each combo first, and the blocked cards.
+/// Combos are iterated in card number order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Range {
+    combos: BTreeMap<[u32; 2], f64>,
+    blocked: Hand,
+}
+
+impl Range {
+    /// Empty range
+    pub fn new() -> Range {
+        Range {
+            combos: BTreeMap::new(),
+            blocked: Hand::new(),
+        }
+    }
+
+    /// Add a combo, in any card order, or update its weight - fails if the cards are invalid, equal or blocked
+    pub fn insert(&mut self, combo: [u32; 2], weight: f64) -> Result<(), CardError> {
+        let (c1, c2) = (Card::try_from(combo[0])?, Card::try_from(combo[1])?);
+        if c1 == c2 {
+            return Err(CardError::DuplicateCard(c1.index()));
+        }
+        if let Some(c) = [c1, c2].into_iter().find(|c| self.blocked.contains(*c)) {
+            return Err(CardError::DuplicateCard(c.index()));
+        }
+        self.combos.insert(sorted_combo(c1, c2), weight);
+        Ok(())
+    }
+
+    /// Weight of a combo, in any card order - `None` if not in range
+    pub fn weight(&self, combo: [u32; 2]) -> Option<f64> {
+        self.combos
+            .get(&[combo[0].max(combo[1]), combo[0].min(combo[1])])
+            .copied()
+    }
+
+    /// Number of combos
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    /// Whether range has no combo
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+
+    /// Combos and their weights, in card number order
+    pub fn combos(&self) -> Vec<([u32; 2], f64)> {
+        self.combos.iter().map(|(c, w)| (*c, *w)).collect()
+    }
+
+    /// Blocked cards, removed with [remove_cards](Range::remove_cards)
+    pub fn blocked(&self) -> Hand {
+        self.blocked
+    }
+
+    /// Remove the combos holding any of the cards, e.g. board or dead cards, and block these cards - fails if a card is invalid
+    pub fn remove_cards(&mut self, cards: &[u32]) -> Result<(), CardError> {
+        for c in cards.iter() {
+            let card = Card::try_from(*c)?;
+            if !self.blocked.contains(card) {
+                self.blocked.insert(card)?;
+            }
+        }
+        let blocked = self.blocked.to_vec();
+        self.combos
+            .retain(|c, _| !blocked.contains(&c[0]) && !blocked.contains(&c[1]));
+        Ok(())
+    }
+}
+
+/// Highest card first
+fn sorted_combo(c1: Card, c2: Card) -> [u32; 2] {
+    let (c1, c2) = (u32::from(c1), u32::from(c2));
+    [c1.max(c2), c1.min(c2)]
+}
+
+/// ## Hand class
+/// Starting hand ignoring suits, e.g. `AKs`, `AKo`, `AK` or `QQ`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Class {
+    high: Face,
+    low: Face,
+    kind: Kind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Pair,
+    Suited,
+    Offsuit,
+    Any,
+}
+
+impl Class {
+    /// Combos of the class, highest card first
+    fn combos(&self) -> Vec<[u32; 2]> {
+        self.combos_without(Hand::new())
+    }
+
+    /// Same, without the combos holding a blocked card
+    fn combos_without(&self, blocked: Hand) -> Vec<[u32; 2]> {
+        let mut combos = vec![];
+        for s1 in Suit::all() {
+            for s2 in Suit::all() {
+                let keep = match self.kind {
+                    Kind::Pair => s1 > s2,
+                    Kind::Suited => s1 == s2,
+                    Kind::Offsuit => s1 != s2,
+                    Kind::Any => true,
+                };
+                let (c1, c2) = (Card::new(self.high, s1), Card::new(self.low, s2));
+                if keep && !blocked.contains(c1) && !blocked.contains(c2) {
+                    combos.push(sorted_combo(c1, c2));
+                }
+            }
+        }
+        combos
+    }
+
+    /// Class of a combo - suited or offsuit for non pairs
+    fn of(combo: [u32; 2]) -> Class {
+        let (c1, c2) = (Card::try_from(combo[0]).unwrap(), Card::try_from(combo[1]).unwrap());
+        let kind = match (c1.face() == c2.face(), c1.suit() == c2.suit()) {
+            (true, _) => Kind::Pair,
+            (false, true) => Kind::Suited,
+            (false, false) => Kind::Offsuit,
+        };
+        Class {
+            high: c1.face().max(c2.face()),
+            low: c1.face().min(c2.face()),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            Kind::Pair | Kind::Any => "",
+            Kind::Suited => "s",
+            Kind::Offsuit => "o",
+        };
+        write!(f, "{}{}{}", self.high, self.low, kind)
+    }
+}
+
+/// Parse a class, e.g. `AKs`, `AK` or `QQ`, and whether it is followed by `+`
+fn parse_class(s: &str) -> Option<(Class, bool)> {
+    let (s, plus) = match s.strip_suffix('+') {
+        Some(s_) => (s_, true),
+        None => (s, false),
+    };
+    let chars = s.chars().collect::<Vec<char>>();
+    let (f1, f2) = match chars.as_slice() {
+        [f1, f2] | [f1, f2, _] => (*f1, *f2),
+        _ => return None,
+    };
+    let f1 = f1.to_string().parse::<Face>().ok()?;
+    let f2 = f2.to_string().parse::<Face>().ok()?;
+    let kind = match (f1 == f2, chars.get(2).map(|c| c.to_ascii_lowercase())) {
+        (true, None) => Kind::Pair,
+        (false, None) => Kind::Any,
+        (false, Some('s')) => Kind::Suited,
+        (false, Some('o')) => Kind::Offsuit,
+        _ => return None,
+    };
+    Some((
+        Class {
+            high: f1.max(f2),
+            low: f1.min(f2),
+            kind,
+        },
+        plus,
+    ))
+}
+
+/// Classes of a token without weight, e.g. `QQ+`, `A5s-A2s` or `KTo+`
+fn parse_classes(s: &str) -> Result<Vec<Class>, ParseRangeError> {
+    let err = || ParseRangeError::InvalidToken(s.to_string());
+
+    if let Some((s1, s2)) = s.split_once('-') {
+        let ((c1, false), (c2, false)) = (parse_class(s1).ok_or_else(err)?, parse_class(s2).ok_or_else(err)?) else {
+            return Err(err());
+        };
+        // the faces that move between both ends
+        let faces = match c1.kind {
+            Kind::Pair if c2.kind == Kind::Pair => (c1.high.min(c2.high), c1.high.max(c2.high)),
+            _ if c1.kind == c2.kind && c1.high == c2.high => (c1.low.min(c2.low), c1.low.max(c2.low)),
+            _ => return Err(ParseRangeError::InvalidSpan(s.to_string())),
+        };
+        return Ok(Face::all()
+            .filter(|f| faces.0 <= *f && *f <= faces.1)
+            .map(|f| match c1.kind {
+                Kind::Pair => Class { high: f, low: f, ..c1 },
+                _ => Class { low: f, ..c1 },
+            })
+            .collect());
+    }
+
+    let (class, plus) = parse_class(s).ok_or_else(err)?;
+    match (plus, class.kind) {
+        (false, _) => Ok(vec![class]),
+        (true, Kind::Pair) => Ok(Face::all()
+            .filter(|f| *f >= class.high)
+            .map(|f| Class {
+                high: f,
+                low: f,
+                ..class
+            })
+            .collect()),
+        (true, _) => Ok(Face::all()
+            .filter(|f| class.low <= *f && *f < class.high)
+            .map(|f| Class { low: f, ..class })
+            .collect()),
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Range, ParseRangeError> {
+        let mut range = Range::new();
+
+        for token in s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty())
+        {
+            let (hands, weight) = match token.split_once(':') {
+                Some((h, w)) => match w.parse::<f64>() {
+                    Ok(x) if (0.0..=1.0).contains(&x) => (h, x),
+                    _ => return Err(ParseRangeError::InvalidWeight(token.to_string())),
+                },
+                None => (token, 1.0),
+            };
+
+            // explicit combo, else classes
+            let combos = match parse_cards(hands).as_deref() {
+                Ok([c1, c2]) if c1 != c2 => vec![sorted_combo(*c1, *c2)],
+                _ => parse_classes(hands)?.iter().flat_map(|c| c.combos()).collect(),
+            };
+            for combo in combos {
+                range.combos.insert(combo, weight);
+            }
+        }
+
+        Ok(range)
+    }
+}
+
+/// Compact notation, e.g. `QQ+, AKs, A5s-A2s, KTo+, AhKh:0.5`:
+/// - classes with all their combos, except those with blocked cards, are grouped with their most common weight, then merged into `+` and spans
+/// - pairs first, then the other classes by first face, suited before offsuit
+/// - the other combos, or with another weight, are listed explicitly at the end, from the highest
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // combos by class, suited and offsuit apart
+        let mut classes = BTreeMap::<(Face, Face, Kind), Vec<([u32; 2], f64)>>::new();
+        for (combo, weight) in self.combos.iter() {
+            let c = Class::of(*combo);
+            classes
+                .entry((c.high, c.low, c.kind))
+                .or_default()
+                .push((*combo, *weight));
+        }
+
+        // complete classes with their most common weight, and explicit combos for the others
+        let mut complete = BTreeMap::<(Face, Face, Kind), f64>::new();
+        let mut explicit = vec![];
+        for ((high, low, kind), combos) in classes.iter() {
+            let class = Class {
+                high: *high,
+                low: *low,
+                kind: *kind,
+            };
+            if combos.len() < class.combos_without(self.blocked).len() {
+                explicit.extend(combos.iter().copied());
+                continue;
+            }
+            let weight = combos
+                .iter()
+                .map(|(_, w)| *w)
+                .max_by_key(|w| combos.iter().filter(|(_, x)| x == w).count())
+                .unwrap();
+            complete.insert((*high, *low, *kind), weight);
+            explicit.extend(combos.iter().filter(|(_, w)| *w != weight).copied());
+        }
+
+        // suited and offsuit with the same weight merge into any
+        let keys = complete.keys().copied().collect::<Vec<(Face, Face, Kind)>>();
+        for (high, low, kind) in keys {
+            if kind != Kind::Suited {
+                continue;
+            }
+            if let (Some(ws), Some(wo)) = (
+                complete.get(&(high, low, kind)),
+                complete.get(&(high, low, Kind::Offsuit)),
+            ) {
+                if ws == wo {
+                    let w = *ws;
+                    complete.remove(&(high, low, Kind::Suited));
+                    complete.remove(&(high, low, Kind::Offsuit));
+                    complete.insert((high, low, Kind::Any), w);
+                }
+            }
+        }
+
+        let mut tokens = vec![];
+
+        // pairs, from AA down, by runs of the same weight
+        let pairs = complete
+            .iter()
+            .filter(|((_, _, k), _)| *k == Kind::Pair)
+            .map(|((h, _, _), w)| (*h, *w))
+            .rev()
+            .collect::<Vec<(Face, f64)>>();
+        for run in runs(&pairs) {
+            let (top, bottom, w) = (run[0].0, run[run.len() - 1].0, run[0].1);
+            let pair = |x: Face| Class {
+                high: x,
+                low: x,
+                kind: Kind::Pair,
+            };
+            let token = match (run.len(), top == Face::ACE) {
+                (1, _) => pair(top).to_string(),
+                (_, true) => format!("{}+", pair(bottom)),
+                (_, false) => format!("{}-{}", pair(top), pair(bottom)),
+            };
+            tokens.push(with_weight(token, w));
+        }
+
+        // other classes, by first face from A down, then by kind
+        let mut firsts = complete
+            .keys()
+            .filter(|(_, _, k)| *k != Kind::Pair)
+            .map(|(h, _, k)| (*h, *k))
+            .collect::<Vec<(Face, Kind)>>();
+        firsts.sort_by_key(|(h, k)| (std::cmp::Reverse(*h), *k));
+        firsts.dedup();
+
+        for (high, kind) in firsts {
+            let lows = complete
+                .iter()
+                .filter(|((h, _, k), _)| *h == high && *k == kind)
+                .map(|((_, l, _), w)| (*l, *w))
+                .rev()
+                .collect::<Vec<(Face, f64)>>();
+            for run in runs(&lows) {
+                let (top, bottom, w) = (run[0].0, run[run.len() - 1].0, run[0].1);
+                let class = |x: Face| Class { high, low: x, kind };
+                let token = match (run.len(), top.index() + 1 == high.index()) {
+                    (1, _) => class(top).to_string(),
+                    (_, true) => format!("{}+", class(bottom)),
+                    (_, false) => format!("{}-{}", class(top), class(bottom)),
+                };
+                tokens.push(with_weight(token, w));
+            }
+        }
+
+        // explicit combos last, so that they override the classes
+        for (combo, w) in explicit.iter().rev() {
+            tokens.push(with_weight(combo_to_string(*combo), *w));
+        }
+
+        write!(f, "{}", tokens.join(", "))
+    }
+}
+
+/// Runs of consecutive faces, in decreasing order, with the same weight
+fn runs(items: &[(Face, f64)]) -> Vec<&[(Face, f64)]> {
+    let mut runs = vec![];
+    let mut start = 0;
+    for i in 1..=items.len() {
+        if i == items.len() || items[i].0.index() + 1 != items[i - 1].0.index() || items[i].1 != items[i - 1].1 {
+            runs.push(&items[start..i]);
+            start = i;
+        }
+    }
+    runs
+}
+
+fn with_weight(token: String, weight: f64) -> String {
+    match weight == 1.0 {
+        true => token,
+        false => format!("{}:{}", token, weight),
+    }
+}
+
+fn combo_to_string(combo: [u32; 2]) -> String {
+    format!(
+        "{}{}",
+        Card::try_from(combo[0]).unwrap(),
+        Card::try_from(combo[1]).unwrap()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ParseRangeError, Range};
+    use crate::card::{parse_cards, CardError};
+    use crate::util::is_normal;
+
+    fn card(s: &str) -> u32 {
+        u32::from(parse_cards(s).unwrap()[0])
+    }
+
+    fn combo(s: &str) -> [u32; 2] {
+        let cards = parse_cards(s).unwrap();
+        [u32::from(cards[0]), u32::from(cards[1])]
+    }
+
+    #[test]
+    fn check_range_normal() {
+        is_normal::<Range>();
+    }
+
+    #[test]
+    fn range_parse() {
+        for (s, nb) in [
+            ("AA", 6),
+            ("QQ+", 18),
+            ("TT-77", 24),
+            ("77-TT", 24),
+            ("AKs", 4),
+            ("AKo", 12),
+            ("AK", 16),
+            ("KAs", 4),
+            ("KTo+", 36),
+            ("KTs+", 12),
+            ("A5s-A2s", 16),
+            ("A2s-A5s", 16),
+            ("AhKh", 1),
+            ("10h9h", 1),
+            ("QQ+, AKs, A5s-A2s, KTo+", 18 + 4 + 16 + 36),
+            ("QQ+ AKs,AKs", 22),
+            ("", 0),
+        ] {
+            let range = s.parse::<Range>();
+            assert_eq!(range.as_ref().map(|r| r.len()), Ok(nb), "-> fails: s={}", s);
+        }
+
+        let range = "AKs:0.5, AhKh, QQ:0".parse::<Range>().unwrap();
+        assert_eq!(range.weight(combo("AsKs")), Some(0.5));
+        assert_eq!(range.weight(combo("KhAh")), Some(1.0));
+        assert_eq!(range.weight(combo("QsQh")), Some(0.0));
+        assert_eq!(range.weight(combo("AsKh")), None);
+        assert!(range.combos().iter().all(|(c, _)| c[0] > c[1]));
+
+        for (s, e) in [
+            ("AKx", ParseRangeError::InvalidToken("AKx".to_string())),
+            ("AAs", ParseRangeError::InvalidToken("AAs".to_string())),
+            ("AKs++", ParseRangeError::InvalidToken("AKs++".to_string())),
+            ("AKs+-AQs", ParseRangeError::InvalidToken("AKs+-AQs".to_string())),
+            ("AhAh", ParseRangeError::InvalidToken("AhAh".to_string())),
+            ("A5s-K2s", ParseRangeError::InvalidSpan("A5s-K2s".to_string())),
+            ("A5s-A2o", ParseRangeError::InvalidSpan("A5s-A2o".to_string())),
+            ("TT-A2s", ParseRangeError::InvalidSpan("TT-A2s".to_string())),
+            ("AKs:2", ParseRangeError::InvalidWeight("AKs:2".to_string())),
+            ("AKs:x", ParseRangeError::InvalidWeight("AKs:x".to_string())),
+        ] {
+            assert_eq!(s.parse::<Range>(), Err(e), "-> fails: s={}", s);
+        }
+    }
+
+    #[test]
+    fn range_cards() {
+        let mut range = Range::new();
+        assert!(range.is_empty());
+        assert_eq!(range.insert([46, 50], 0.5), Ok(()));
+        assert_eq!(range.insert([50, 50], 1.0), Err(CardError::DuplicateCard(50)));
+        assert_eq!(range.insert([50, 52], 1.0), Err(CardError::InvalidCard(52)));
+        assert_eq!(range.combos(), vec![([50, 46], 0.5)]);
+
+        // board and dead cards
+        let mut range = "QQ+, AKs".parse::<Range>().unwrap();
+        assert_eq!(range.remove_cards(&[card("As"), card("Kd")]), Ok(()));
+        assert_eq!(range.len(), 3 + 3 + 6 + 2);
+        assert!(range
+            .combos()
+            .iter()
+            .all(|(c, _)| !c.contains(&card("As")) && !c.contains(&card("Kd"))));
+        assert_eq!(range.blocked().len(), 2);
+        assert_eq!(range.to_string(), "QQ+, AKs", "-> fails: blocked combos not ignored");
+        assert_eq!(
+            range.insert(combo("AsQs"), 1.0),
+            Err(CardError::DuplicateCard(card("As") as usize))
+        );
+        assert_eq!(range.remove_cards(&[52]), Err(CardError::InvalidCard(52)));
+    }
+
+    #[test]
+    fn range_display() {
+        for (s, want) in [
+            ("QQ+, AKs, A5s-A2s, KTo+", "QQ+, AKs, A5s-A2s, KTo+"),
+            ("AKs, AKo", "AK"),
+            ("AA, KK, QQ, 88, 77", "QQ+, 88-77"),
+            ("KK, QQ", "KK-QQ"),
+            ("KQo, KJo, KTo, K9o:0.5", "KTo+, K9o:0.5"),
+            ("A2s-A5s, A7s", "A7s, A5s-A2s"),
+            ("AKs:0.5, AKo", "AKs:0.5, AKo"),
+            ("AhKh, AKo", "AKo, AhKh"),
+            ("AhKh:0.25", "AhKh:0.25"),
+            ("JJ, AsAh", "JJ, AsAh"),
+            ("AKs, AhKh:0.5", "AKs, AhKh:0.5"),
+            ("QQ+:0.5, AsAh:1, KK", "AA:0.5, KK, QQ:0.5, AsAh"),
+            ("", ""),
+        ] {
+            let range = s.parse::<Range>().unwrap();
+            assert_eq!(range.to_string(), want, "-> fails: s={}", s);
+            assert_eq!(range.to_string().parse::<Range>(), Ok(range), "-> fails: s={}", s);
+        }
+    }
+}
@@ -55,7 +55,7 @@ pub fn build_five(t5: Arc<TableFive>, verbose: bool) -> HashMap<String, HandStat
     }
 
     for (rank, count) in rank_count.iter() {
-        let ht = &t5_.hand_type[*rank as usize];
+        let ht = t5_.hand_type[*rank as usize].as_str();
         let hs = hand_stats.get_mut(ht).unwrap();
 
         hs.nb_hand += 1;
@@ -165,7 +165,7 @@ pub fn build_seven(t7: Arc<TableSeven>, verbose: bool) -> HashMap<String, HandSt
 
     let t7_ = Arc::clone(&t7_);
     for (rank, count) in rank_count.iter() {
-        let ht = &t7_.t5.hand_type[*rank as usize];
+        let ht = t7_.t5.hand_type[*rank as usize].as_str();
         let hs = hand_stats.get_mut(ht).unwrap();
 
         hs.nb_hand += 1;
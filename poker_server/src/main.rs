@@ -13,7 +13,8 @@ use std::{
     collections::HashMap,
     net::{IpAddr, Ipv6Addr, SocketAddr},
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, OnceLock},
+    time::Duration,
     vec,
 };
 
@@ -23,9 +24,16 @@ use poker_eval::{
     calc::{
         self,
         equity_det::{GameError, HandEquity},
-        equity_mc::McGameError,
+        equity_mc::{McEquity, McGameError, McStop},
+        equity_omaha::OmahaGameError,
+    },
+    card::CardError,
+    eval::{
+        self,
+        batch::{get_rank_batch_soa, HandBatch},
+        five::get_rank_five,
+        target::HandStats,
     },
-    eval::{self, five::get_rank_five, seven::get_rank, target::HandStats},
     stats,
 };
 
@@ -45,16 +53,20 @@ struct Config {
 
 /// ## Poker eval app state
 /// Contains the tables and hand statistics for the poker server.
-/// Calculated at server start.
+/// Calculated at server start, except the 7-card hand statistics, built on first request.
 #[derive(Clone, Debug, Serialize)]
 struct AppState {
     /// 5-card and 7-card lookup tables used by poker_eval
     #[serde(skip)]
     t7: Arc<eval::seven::TableSeven>,
+    /// 5-card lookup tables used by poker_eval for Omaha
+    #[serde(skip)]
+    t5: Arc<eval::five::TableFive>,
     /// 5-card hand statistics
     stats_five: HashMap<String, HandStats>,
-    /// 7-card hand statistics
-    stats_seven: HashMap<String, HandStats>,
+    /// 7-card hand statistics, built on first request
+    #[serde(skip)]
+    stats_seven: Arc<OnceLock<HashMap<String, HandStats>>>,
 }
 
 /// ## Poker eval server config
@@ -68,6 +80,9 @@ struct ServerConfig {
     /// Port to listen on, default is 3000
     #[clap(short = 'p', long = "port", default_value = "3000")]
     port: u16,
+    /// Lookup tables file, loaded if valid else built and saved, default is none i.e. always build
+    #[clap(short = 't', long = "tables")]
+    tables: Option<String>,
 }
 
 /// ## 5-card hands
@@ -92,6 +107,8 @@ struct GameDet {
     players: Vec<[u32; 2]>,
     /// table cards
     table: Vec<u32>,
+    /// dead cards, optional
+    dead: Option<Vec<u32>>,
 }
 
 /// ## Monte Carlo game description
@@ -102,8 +119,30 @@ struct GameMc {
     players: Vec<Vec<u32>>,
     /// table cards
     table: Vec<u32>,
-    /// number of games to simulate
+    /// dead cards, optional
+    dead: Option<Vec<u32>>,
+    /// number of games to simulate, at most
     nb_game: u32,
+    /// target standard error of the win and tie probabilities, optional
+    std_err: Option<f64>,
+    /// time budget in milliseconds, optional
+    time_ms: Option<u64>,
+    /// seed for reproducible results, optional
+    seed: Option<u64>,
+}
+
+/// ## Omaha game description
+/// Contains all players and the table cards, for PLO4 or PLO5.
+#[derive(Debug, Deserialize)]
+struct GameOmaha {
+    /// list of players with their known cards (4 or 5 for the first player, as many or fewer for the others)
+    players: Vec<Vec<u32>>,
+    /// table cards
+    table: Vec<u32>,
+    /// number of games to simulate - if absent, deterministic calculation with all player cards known
+    nb_game: Option<u32>,
+    /// seed for reproducible results, optional
+    seed: Option<u64>,
 }
 
 /// ## Start poker server
@@ -138,7 +177,7 @@ async fn main() {
     tracing_subscriber::fmt::init();
 
     // init app state
-    let state = build_app_state();
+    let state = build_app_state(server_config.tables);
 
     // create app
     let app = Router::new()
@@ -150,6 +189,7 @@ async fn main() {
         .route("/rank-seven", post(rank_seven))
         .route("/calc-det", post(calc_det))
         .route("/calc-mc", post(calc_mc))
+        .route("/calc-omaha", post(calc_omaha))
         .with_state(state);
 
     // start server
@@ -160,23 +200,41 @@ async fn main() {
 
 /// ## Init function
 /// Builds the app state at server start.  
-/// Contains all lookup tables used by poker_eval and hand statistics for `/stats-five` and  `/stats-seven` endpoints.
-fn build_app_state() -> AppState {
+/// Contains all lookup tables used by poker_eval and hand statistics for `/stats-five` endpoint.
+/// If a tables file is given, the lookup tables are loaded from it, or built and saved to it if it is missing or invalid.
+/// The hand statistics for `/stats-seven` endpoint go through all 7-card hands, so they are built on first request, not to slow down the start.
+fn build_app_state(tables: Option<String>) -> AppState {
     let start = std::time::Instant::now();
 
-    let t7 = eval::seven::build_tables(false);
-    let stats_seven = stats::build_seven(t7.clone(), false);
-
+    let t7 = match tables {
+        Some(path) => match eval::seven::TableSeven::load(&path) {
+            Ok(t7) => {
+                tracing::info!("tables loaded from {}", path);
+                t7
+            }
+            Err(err) => {
+                tracing::info!("tables not loaded from {} -> {}", path, err);
+                let t7 = eval::seven::build_tables(false);
+                match t7.save(&path) {
+                    Ok(()) => tracing::info!("tables saved to {}", path),
+                    Err(err) => tracing::error!("tables not saved to {} -> {}", path, err),
+                }
+                t7
+            }
+        },
+        None => eval::seven::build_tables(false),
+    };
     let t5_ = Arc::new(t7.t5.clone());
-    let stats_five = stats::build_five(t5_, false);
+    let stats_five = stats::build_five(t5_.clone(), false);
 
     let end = std::time::Instant::now();
     tracing::info!("build_app_state runtime = {:?} s", end - start);
 
     AppState {
         t7,
+        t5: t5_,
         stats_five,
-        stats_seven,
+        stats_seven: Arc::new(OnceLock::new()),
     }
 }
 
@@ -219,11 +277,20 @@ async fn stats_five(
 
 /// ## 7-card hand statistics endpoint
 /// Returns the 7-card hand statistics.
+/// They are built on first request, about a second.
 #[tracing::instrument(skip(state))]
 async fn stats_seven(
     State(state): State<AppState>,
 ) -> (StatusCode, Json<HashMap<String, HandStats>>) {
-    let stats_seven = state.stats_seven.clone();
+    let t7_ = state.t7.clone();
+    let stats_seven = tokio::task::spawn_blocking(move || {
+        state
+            .stats_seven
+            .get_or_init(|| stats::build_seven(t7_, false))
+            .clone()
+    })
+    .await
+    .unwrap();
 
     tracing::info!("-> {:?}", stats_seven);
     (StatusCode::OK, Json(stats_seven))
@@ -255,25 +322,21 @@ async fn rank_five(
 #[tracing::instrument(skip(state))]
 async fn rank_seven(
     State(state): State<AppState>,
-    Json(payload): Json<HandsSeven>,
-) -> (StatusCode, Json<Vec<u32>>) {
+    AppJson(payload): AppJson<HandsSeven>,
+) -> Result<AppJson<Vec<u32>>, AppError> {
     let t7_ = state.t7;
 
-    let hands = payload.hands;
-    let mut ranks = vec![];
-
-    for hand in hands.iter() {
-        let rank = get_rank(&t7_, *hand);
-        ranks.push(rank);
-    }
+    let batch = HandBatch::try_from(payload.hands.as_slice())?;
+    let mut ranks = vec![0; batch.len()];
+    get_rank_batch_soa(&t7_, &batch, &mut ranks);
 
     tracing::info!("-> ranks={:?}", ranks);
-    (StatusCode::OK, Json(ranks))
+    Ok(AppJson(ranks))
 }
 
 /// ## Deterministic hand equity calculation
 /// Calculates the equity of each player hand with all players and table cards known.  
-/// Exhaustive calculation through all possible table unknown cards.  
+/// Exhaustive calculation through all possible table unknown cards, except the dead cards.  
 #[tracing::instrument(skip(state))]
 async fn calc_det(
     State(state): State<AppState>,
@@ -285,6 +348,7 @@ async fn calc_det(
         t7_,
         payload.players.clone(),
         payload.table.clone(),
+        payload.dead.clone().unwrap_or_default(),
         false,
     )?;
 
@@ -294,20 +358,30 @@ async fn calc_det(
 }
 
 /// ## Monte Carlo hand equity calculation  
-/// Calculates the equity of the first player hand with partial information about other players hands and table cards known.  
-/// Monte Carlo simulation through `nb_game` games.  
+/// Calculates the equity of each player hand with partial information about players hands and table cards known.  
+/// Monte Carlo simulation through `nb_game` games, reproducible with `seed`.  
+/// Stops earlier if `std_err` is reached or `time_ms` is spent.  
+/// Returns the number of games simulated, and the standard errors and 95% confidence intervals.  
 #[tracing::instrument(skip(state))]
 async fn calc_mc(
     State(state): State<AppState>,
     AppJson(payload): AppJson<GameMc>,
-) -> Result<AppJson<HandEquity>, AppError> {
+) -> Result<AppJson<McEquity>, AppError> {
     let t7_ = state.t7.clone();
 
+    let stop = McStop {
+        nb_game: payload.nb_game,
+        std_err: payload.std_err,
+        time: payload.time_ms.map(Duration::from_millis),
+    };
+
     let equity = calc::equity_mc::calc_equity_monte_carlo(
         t7_,
         payload.players.clone(),
         payload.table.clone(),
-        payload.nb_game,
+        payload.dead.clone().unwrap_or_default(),
+        stop,
+        payload.seed,
     )?;
 
     tracing::info!("-> equity={:?}", equity);
@@ -315,6 +389,38 @@ async fn calc_mc(
     Ok(AppJson(equity))
 }
 
+/// ## Omaha hand equity calculation
+/// Calculates the equity of each player hand in PLO4 or PLO5.  
+/// Deterministic if `nb_game` is absent, with all players cards known.  
+/// Else Monte Carlo simulation through `nb_game` games, with partial information about other players hands, reproducible with `seed`.  
+#[tracing::instrument(skip(state))]
+async fn calc_omaha(
+    State(state): State<AppState>,
+    AppJson(payload): AppJson<GameOmaha>,
+) -> Result<AppJson<Vec<HandEquity>>, AppError> {
+    let t5_ = state.t5.clone();
+
+    let equity = match payload.nb_game {
+        None => calc::equity_omaha::calc_equity_omaha_det(
+            t5_,
+            payload.players.clone(),
+            payload.table.clone(),
+            false,
+        )?,
+        Some(nb_game) => calc::equity_omaha::calc_equity_omaha_monte_carlo(
+            t5_,
+            payload.players.clone(),
+            payload.table.clone(),
+            nb_game,
+            payload.seed,
+        )?,
+    };
+
+    tracing::info!("-> equity={:?}", equity);
+
+    Ok(AppJson(equity))
+}
+
 /// ## Util function
 /// Prints a banner.
 fn banner(txt: &str, n: u8) {
@@ -349,6 +455,10 @@ enum AppError {
     GameError(GameError),
     /// Error from the poker_eval lib.
     McGameError(McGameError),
+    /// Error from the poker_eval lib.
+    CardError(CardError),
+    /// Error from the poker_eval lib.
+    OmahaGameError(OmahaGameError),
 }
 
 impl IntoResponse for AppError {
@@ -372,6 +482,14 @@ impl IntoResponse for AppError {
                 tracing::error!("error from poker lib -> {}", err);
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err))
             }
+            AppError::CardError(err) => {
+                tracing::error!("error from poker lib -> {}", err);
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err))
+            }
+            AppError::OmahaGameError(err) => {
+                tracing::error!("error from poker lib -> {}", err);
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err))
+            }
         };
 
         (status, AppJson(ErrorResponse { message })).into_response()
@@ -398,3 +516,17 @@ impl From<McGameError> for AppError {
         Self::McGameError(error)
     }
 }
+
+impl From<CardError> for AppError {
+    fn from(error: CardError) -> Self {
+        // transparent
+        Self::CardError(error)
+    }
+}
+
+impl From<OmahaGameError> for AppError {
+    fn from(error: OmahaGameError) -> Self {
+        // transparent
+        Self::OmahaGameError(error)
+    }
+}